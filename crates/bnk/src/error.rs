@@ -1,6 +1,4 @@
-use std::fmt::Debug;
-
-use nom::{error::VerboseError, Err};
+use nom_error::ParseError;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, BnkError>;
@@ -11,16 +9,16 @@ pub enum BnkError {
     Corrupt(String),
     #[error("section '{0}' is missing")]
     MissingSection(String),
-    #[error("parsing error: {0}")]
-    Nom(String),
+    /// The offset is relative to the start of the parsed bytes.
+    #[error("parsing {context} failed at byte {offset}")]
+    Parse { context: String, offset: usize },
 }
 
-impl<T: Debug> From<Err<VerboseError<T>>> for BnkError {
-    fn from(err: Err<VerboseError<T>>) -> Self {
-        match err {
-            Err::Incomplete(_) => todo!(),
-            Err::Error(err) => Self::Nom(format!("{:?}", err)),
-            Err::Failure(_) => todo!(),
+impl From<ParseError> for BnkError {
+    fn from(err: ParseError) -> Self {
+        Self::Parse {
+            context: err.context,
+            offset: err.offset,
         }
     }
 }
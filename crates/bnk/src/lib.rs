@@ -5,6 +5,7 @@ use std::collections::HashMap;
 pub use error::BnkError;
 use error::Result;
 use nom::{bytes::complete::take, error::context, number::complete::le_u32};
+use nom_error::WithOffset;
 
 /// Size of each file description in the didx section.
 const DIDX_FILE_SIZE: usize = 12;
@@ -27,7 +28,8 @@ pub fn wem_filenames(bytes: &[u8]) -> Result<Vec<String>> {
             let offset = index * DIDX_FILE_SIZE;
 
             let i = &section_data[offset..offset + DIDX_FILE_SIZE];
-            let (_, wem_file_id) = context("bnk didx section file id", le_u32)(i)?;
+            let (_, wem_file_id) =
+                context("bnk didx section file id", le_u32)(i).offset_in(bytes)?;
 
             Ok(format!("{}.wem", wem_file_id))
         })
@@ -36,17 +38,19 @@ pub fn wem_filenames(bytes: &[u8]) -> Result<Vec<String>> {
 
 /// Get all sections.
 #[profiling::function]
-pub fn sections(mut i: &[u8]) -> Result<HashMap<[u8; 4], &[u8]>> {
+pub fn sections(bytes: &[u8]) -> Result<HashMap<[u8; 4], &[u8]>> {
     let mut result: HashMap<[u8; 4], &[u8]> = HashMap::new();
 
+    let mut i = bytes;
+
     // Read all bytes
     while !i.is_empty() {
         let identifier;
-        (i, identifier) = context("bnk section identifier", take(4u8))(i)?;
+        (i, identifier) = context("bnk section identifier", take(4u8))(i).offset_in(bytes)?;
         let size;
-        (i, size) = context("bnk section size", le_u32)(i)?;
+        (i, size) = context("bnk section size", le_u32)(i).offset_in(bytes)?;
         let data;
-        (i, data) = context("bnk section data", take(size))(i)?;
+        (i, data) = context("bnk section data", take(size))(i).offset_in(bytes)?;
 
         result.insert(
             identifier
@@ -0,0 +1,33 @@
+use bnk::BnkError;
+use proptest::prelude::*;
+
+const BNK: &[u8] = include_bytes!("./test.bnk");
+
+#[test]
+fn test_wem_filenames() {
+    assert_eq!(bnk::wem_filenames(BNK).unwrap(), vec!["2085836403.wem"]);
+}
+
+#[test]
+fn test_truncated_offset() {
+    // The data of the first section is missing
+    assert!(matches!(
+        bnk::wem_filenames(&BNK[..10]),
+        Err(BnkError::Parse { offset: 8, .. })
+    ));
+}
+
+proptest! {
+    #[test]
+    fn truncated_doesnt_panic(length in 0..BNK.len()) {
+        let _ = bnk::wem_filenames(&BNK[..length]);
+    }
+
+    #[test]
+    fn bit_flipped_doesnt_panic(bit in 0..BNK.len() * 8) {
+        let mut bytes = BNK.to_vec();
+        bytes[bit / 8] ^= 1 << (bit % 8);
+
+        let _ = bnk::wem_filenames(&bytes);
+    }
+}
@@ -0,0 +1,69 @@
+use nom::{
+    error::{VerboseError, VerboseErrorKind},
+    Err,
+};
+use thiserror::Error;
+
+/// Where and why parsing with nom failed.
+///
+/// Every parser crate converts this into the `Parse` variant of its own error type.
+#[derive(Debug, Error)]
+#[error("parsing {context} failed at byte {offset}")]
+pub struct ParseError {
+    /// The innermost context the parser was in.
+    pub context: String,
+    /// The offset is relative to the start of the parsed bytes.
+    pub offset: usize,
+}
+
+impl ParseError {
+    /// Convert a nom error, the offset is calculated from where in the input it failed.
+    pub fn new(input: &[u8], err: Err<VerboseError<&[u8]>>) -> Self {
+        match err {
+            Err::Incomplete(_) => Self {
+                context: "incomplete input".to_string(),
+                offset: input.len(),
+            },
+            Err::Error(err) | Err::Failure(err) => {
+                // The first error is where it failed, the contexts are added while unwinding
+                let offset = err
+                    .errors
+                    .first()
+                    .map(|(remaining, _)| offset(input, remaining))
+                    .unwrap_or_default();
+                let context = err
+                    .errors
+                    .iter()
+                    .find_map(|(_, kind)| match kind {
+                        VerboseErrorKind::Context(context) => Some(context.to_string()),
+                        _ => None,
+                    })
+                    .or_else(|| err.errors.first().map(|(_, kind)| format!("{:?}", kind)))
+                    .unwrap_or_default();
+
+                Self { context, offset }
+            }
+        }
+    }
+}
+
+/// Convert nom results to results with the byte offset of the failure.
+pub trait WithOffset<T> {
+    /// The offset of the error will be relative to the start of the input.
+    fn offset_in(self, input: &[u8]) -> Result<T, ParseError>;
+}
+
+impl<T> WithOffset<T> for Result<T, Err<VerboseError<&[u8]>>> {
+    fn offset_in(self, input: &[u8]) -> Result<T, ParseError> {
+        self.map_err(|err| ParseError::new(input, err))
+    }
+}
+
+/// Position of the remaining bytes in the input.
+fn offset(input: &[u8], remaining: &[u8]) -> usize {
+    // Remaining is always a subslice when the input is what was parsed
+    (remaining.as_ptr() as usize)
+        .checked_sub(input.as_ptr() as usize)
+        .filter(|offset| *offset <= input.len())
+        .unwrap_or_else(|| input.len().saturating_sub(remaining.len()))
+}
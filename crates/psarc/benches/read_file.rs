@@ -0,0 +1,28 @@
+//! Compare with and without the `parallel` feature:
+//!
+//! ```sh
+//! cargo bench -p psarc
+//! cargo bench -p psarc --features parallel
+//! ```
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use psarc::PlaystationArchive;
+
+fn read_file(c: &mut Criterion) {
+    let psarc = PlaystationArchive::parse(include_bytes!("../tests/test.psarc")).unwrap();
+
+    // The song audio is by far the biggest file
+    let wem = psarc.index_for_path("audio/mac/1499529296.wem").unwrap();
+    c.bench_function("read wem", |b| b.iter(|| psarc.read_file(wem).unwrap()));
+
+    c.bench_function("read all files", |b| {
+        b.iter(|| {
+            (0..psarc.len()).for_each(|index| {
+                psarc.read_file(index).unwrap();
+            })
+        })
+    });
+}
+
+criterion_group!(benches, read_file);
+criterion_main!(benches);
@@ -0,0 +1,400 @@
+use std::{
+    fs,
+    io::{Cursor, Write},
+    path::{Path, PathBuf},
+};
+
+use aes::{
+    cipher::{AsyncStreamCipher, KeyIvInit},
+    Aes256,
+};
+use cfb_mode::Encryptor;
+use flate2::{write::ZlibEncoder, Compression};
+
+use crate::{
+    index::entry_digest, ArchiveFlags, ArchiveReadError, BlockSize, CompressionType,
+    PlaystationArchive, Result, ARC_IV, ARC_KEY, ENTRY_SIZE, HEADER_SIZE, MAGIC,
+};
+
+/// Create a new playstation archive from files.
+///
+/// The manifest is generated from the paths of the added files.
+#[derive(Debug, Default, Clone)]
+pub struct PlaystationArchiveBuilder {
+    /// Files with their path in the archive, in the order they are added.
+    files: Vec<(String, Vec<u8>)>,
+    /// How big each data block is.
+    block_size: BlockSize,
+    /// How each block is compressed.
+    compression_type: CompressionType,
+    /// How the paths are stored and whether the table of content will be encrypted.
+    archive_flags: ArchiveFlags,
+}
+
+impl PlaystationArchiveBuilder {
+    /// Start with an empty archive.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start with all files from an existing archive, used for repacking.
+    #[profiling::function]
+    pub fn from_archive(archive: &PlaystationArchive) -> Result<Self> {
+        let mut this = Self::with_settings_of(archive);
+
+        // Skip the manifest, it will be generated again
+        for (index, path) in archive.paths_iter().enumerate().skip(1) {
+            this.add_file(path, archive.read_file(index)?)?;
+        }
+
+        Ok(this)
+    }
+
+    /// Start without any files but with the same settings as an existing archive.
+    pub(crate) fn with_settings_of(archive: &PlaystationArchive) -> Self {
+        Self {
+            files: Vec::new(),
+            block_size: archive.block_size,
+            compression_type: archive.compression_type,
+            archive_flags: archive.archive_flags,
+        }
+    }
+
+    /// Set the size of the blocks the data is split into.
+    pub fn block_size(mut self, block_size: BlockSize) -> Self {
+        self.block_size = block_size;
+
+        self
+    }
+
+    /// Set how the blocks are compressed.
+    pub fn compression_type(mut self, compression_type: CompressionType) -> Self {
+        self.compression_type = compression_type;
+
+        self
+    }
+
+    /// Encrypt the table of content with the Rocksmith key.
+    pub fn encrypt_toc(mut self, encrypt_toc: bool) -> Self {
+        self.archive_flags.set(ArchiveFlags::ENCRYPTED, encrypt_toc);
+
+        self
+    }
+
+    /// Store all paths with a slash at the start.
+    pub fn absolute_paths(mut self, absolute_paths: bool) -> Self {
+        self.archive_flags
+            .set(ArchiveFlags::ABSOLUTE, absolute_paths);
+
+        self
+    }
+
+    /// Mark the paths as case insensitive.
+    pub fn ignore_case(mut self, ignore_case: bool) -> Self {
+        self.archive_flags
+            .set(ArchiveFlags::IGNORE_CASE, ignore_case);
+
+        self
+    }
+
+    /// Add a file with it's path in the archive.
+    pub fn add_file(&mut self, path: &str, bytes: Vec<u8>) -> Result<&mut Self> {
+        let path = valid_path(path)?;
+        if self.files.iter().any(|(existing, _)| existing == path) {
+            return Err(ArchiveReadError::DuplicatePath(path.to_string()));
+        }
+
+        self.files.push((path.to_string(), bytes));
+
+        Ok(self)
+    }
+
+    /// Add a file from disk with it's path in the archive.
+    pub fn add_file_from_disk<P>(&mut self, path: &str, disk_path: P) -> Result<&mut Self>
+    where
+        P: AsRef<Path>,
+    {
+        let bytes = fs::read(disk_path)?;
+
+        self.add_file(path, bytes)
+    }
+
+    /// Add all files in a directory recursively, the paths are relative to the directory.
+    #[profiling::function]
+    pub fn add_directory<P>(&mut self, directory: P) -> Result<&mut Self>
+    where
+        P: AsRef<Path>,
+    {
+        let directory = directory.as_ref();
+
+        // Walk the directory tree
+        let mut to_visit = vec![directory.to_path_buf()];
+        let mut disk_paths = Vec::new();
+        while let Some(current) = to_visit.pop() {
+            for dir_entry in fs::read_dir(current)? {
+                let disk_path = dir_entry?.path();
+                if disk_path.is_dir() {
+                    to_visit.push(disk_path);
+                } else {
+                    disk_paths.push(disk_path);
+                }
+            }
+        }
+
+        // Always add the files in the same order
+        disk_paths.sort();
+
+        for disk_path in disk_paths {
+            let path = archive_path(directory, &disk_path)?;
+
+            self.add_file_from_disk(&path, disk_path)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Amount of files that will be written, excluding the manifest.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether no files have been added yet.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Create the archive bytes.
+    pub fn build(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes)?;
+
+        Ok(bytes)
+    }
+
+    /// Write the archive to a writer.
+    #[profiling::function]
+    pub fn write<W>(&self, writer: W) -> Result<()>
+    where
+        W: Write,
+    {
+        // Paths as they will be written to the manifest
+        let paths = self
+            .files
+            .iter()
+            .map(|(path, _)| self.manifest_path(path))
+            .collect::<Vec<_>>();
+
+        // Compress all files, the manifest is always the first entry
+        let mut entries = Vec::with_capacity(self.files.len() + 1);
+        let mut block_sizes = Vec::new();
+        entries.push(self.compress_manifest(&paths, &mut block_sizes)?);
+        for (path, (_, bytes)) in paths.iter().zip(self.files.iter()) {
+            entries.push(self.compress_entry(Some(path), bytes, &mut block_sizes)?);
+        }
+
+        self.write_entries(writer, entries, block_sizes)
+    }
+
+    /// Write the header, table of content and data of already compressed entries.
+    #[profiling::function]
+    pub(crate) fn write_entries<W>(
+        &self,
+        mut writer: W,
+        entries: Vec<CompressedEntry>,
+        block_sizes: Vec<u32>,
+    ) -> Result<()>
+    where
+        W: Write,
+    {
+        // The data starts right after the table of content
+        let toc_length = HEADER_SIZE
+            + entries.len() * ENTRY_SIZE as usize
+            + block_sizes.len() * self.block_size.table_entry_size();
+        let toc_length = u32::try_from(toc_length)
+            .map_err(|_| ArchiveReadError::Corrupt("table of content is too big".to_string()))?;
+
+        // Write the header
+        writer.write_all(&MAGIC.to_be_bytes())?;
+        writer.write_all(&1u16.to_be_bytes())?;
+        writer.write_all(&4u16.to_be_bytes())?;
+        writer.write_all(&self.compression_type.to_u32().to_be_bytes())?;
+        writer.write_all(&toc_length.to_be_bytes())?;
+        writer.write_all(&ENTRY_SIZE.to_be_bytes())?;
+        writer.write_all(&(entries.len() as u32).to_be_bytes())?;
+        writer.write_all(&self.block_size.to_u32().to_be_bytes())?;
+        writer.write_all(&self.archive_flags.to_u32().to_be_bytes())?;
+
+        // Write the table of content
+        let mut toc = Vec::with_capacity(toc_length as usize - HEADER_SIZE);
+        let mut offset = toc_length as u64;
+        for entry in entries.iter() {
+            toc.extend(entry.name_digest.to_be_bytes());
+            toc.extend(entry.first_block.to_be_bytes());
+            toc.extend(to_u40_bytes(entry.length as u64, "file length")?);
+            toc.extend(to_u40_bytes(offset, "file offset")?);
+
+            offset += entry.data.len() as u64;
+        }
+
+        // Write the block sizes with the width belonging to the block size
+        let width = self.block_size.table_entry_size();
+        for block_size in block_sizes {
+            toc.extend(&block_size.to_be_bytes()[4 - width..]);
+        }
+
+        if self.archive_flags.contains(ArchiveFlags::ENCRYPTED) {
+            let encryptor = Encryptor::<Aes256>::new(&ARC_KEY.into(), &ARC_IV.into());
+
+            encryptor.encrypt(&mut toc);
+        }
+        writer.write_all(&toc)?;
+
+        // Write the compressed data
+        for entry in entries {
+            writer.write_all(&entry.data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Path as it's written to the manifest.
+    pub(crate) fn manifest_path(&self, path: &str) -> String {
+        if self.archive_flags.contains(ArchiveFlags::ABSOLUTE) {
+            format!("/{}", path)
+        } else {
+            path.to_string()
+        }
+    }
+
+    /// Name digest of a path as it's written to the manifest.
+    pub(crate) fn name_digest(&self, manifest_path: &str) -> u128 {
+        entry_digest(
+            manifest_path,
+            self.archive_flags.contains(ArchiveFlags::IGNORE_CASE),
+        )
+    }
+
+    /// Compress the manifest listing the paths of all other entries.
+    pub(crate) fn compress_manifest(
+        &self,
+        paths: &[String],
+        block_sizes: &mut Vec<u32>,
+    ) -> Result<CompressedEntry> {
+        self.compress_entry(None, paths.join("\n").as_bytes(), block_sizes)
+    }
+
+    /// Split the bytes into compressed blocks.
+    #[profiling::function]
+    pub(crate) fn compress_entry(
+        &self,
+        path: Option<&str>,
+        bytes: &[u8],
+        block_sizes: &mut Vec<u32>,
+    ) -> Result<CompressedEntry> {
+        let first_block = block_sizes.len() as u32;
+        let block_size = self.block_size.to_u32() as usize;
+
+        let mut data = Vec::new();
+        for block in bytes.chunks(block_size) {
+            let compressed = match self.compression_type {
+                CompressionType::None => block.to_vec(),
+                CompressionType::Zlib => {
+                    // Rocksmith uses the best compression, which is also what the reader recognizes
+                    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+                    encoder.write_all(block)?;
+
+                    encoder.finish()?
+                }
+                CompressionType::Lzma => {
+                    let mut compressed = Vec::new();
+                    lzma_rs::lzma_compress(&mut Cursor::new(block), &mut compressed)?;
+
+                    compressed
+                }
+            };
+
+            if compressed.len() < block.len() {
+                block_sizes.push(compressed.len() as u32);
+                data.extend(compressed);
+            } else {
+                // Store the block raw when compressing doesn't help
+                block_sizes.push(self.raw_block_table_value(block.len()));
+                data.extend(block);
+            }
+        }
+
+        // The manifest doesn't have a name
+        let name_digest = path.map(|path| self.name_digest(path)).unwrap_or(0);
+
+        Ok(CompressedEntry {
+            name_digest,
+            first_block,
+            length: bytes.len(),
+            data,
+        })
+    }
+
+    /// Block size table value for a block stored without compression, a full block is 0.
+    pub(crate) fn raw_block_table_value(&self, length: usize) -> u32 {
+        if length == self.block_size.to_u32() as usize {
+            0
+        } else {
+            length as u32
+        }
+    }
+}
+
+/// File entry ready to be written.
+pub(crate) struct CompressedEntry {
+    /// MD5 hash of the path.
+    pub name_digest: u128,
+    /// Index of the first block in the block size table.
+    pub first_block: u32,
+    /// Uncompressed size.
+    pub length: usize,
+    /// All compressed blocks.
+    pub data: Vec<u8>,
+}
+
+/// Check whether the path can be stored in an archive, the slash at the start is removed.
+pub(crate) fn valid_path(path: &str) -> Result<&str> {
+    let trimmed = path.trim_start_matches('/');
+    if trimmed.is_empty() || trimmed.contains('\n') {
+        return Err(ArchiveReadError::InvalidPath(path.to_string()));
+    }
+
+    Ok(trimmed)
+}
+
+/// Convert a length or offset to the 40-bit number stored in the table of content.
+fn to_u40_bytes(value: u64, name: &str) -> Result<[u8; 5]> {
+    if value >> 40 != 0 {
+        return Err(ArchiveReadError::Corrupt(format!(
+            "{} {} doesn't fit in 40 bits",
+            name, value
+        )));
+    }
+
+    let mut bytes = [0; 5];
+    bytes.copy_from_slice(&value.to_be_bytes()[3..]);
+
+    Ok(bytes)
+}
+
+/// Convert a path on disk to a path in the archive relative to the directory.
+fn archive_path(directory: &Path, disk_path: &Path) -> Result<String> {
+    let relative = disk_path
+        .strip_prefix(directory)
+        .map(PathBuf::from)
+        .map_err(|_| ArchiveReadError::InvalidPath(disk_path.display().to_string()))?;
+
+    relative
+        .iter()
+        .map(|component| {
+            component
+                .to_str()
+                .ok_or_else(|| ArchiveReadError::InvalidPath(disk_path.display().to_string()))
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(|components| components.join("/"))
+}
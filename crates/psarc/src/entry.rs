@@ -0,0 +1,156 @@
+use std::{
+    io::{Error, ErrorKind, Read, Seek, SeekFrom},
+    sync::{Arc, Mutex},
+};
+
+use crate::{read_raw, ArchiveReadError, Block, CompressionType, PlaystationArchive, ReadSeek};
+
+/// Streaming reader for a single file in the archive.
+///
+/// Only the block containing the current position is kept in memory, it's decompressed when
+/// it's first read from.
+///
+/// The reader of the archive is shared, so the entry can outlive the archive it's opened from.
+pub struct EntryReader {
+    /// Source of the archive data, shared with the archive.
+    reader: Arc<Mutex<Box<dyn ReadSeek>>>,
+    /// Size of the whole archive in bytes.
+    archive_len: u64,
+    /// How the blocks are compressed.
+    compression_type: CompressionType,
+    /// All blocks of the file.
+    blocks: Vec<Block>,
+    /// Uncompressed size of the whole file.
+    length: u64,
+    /// Uncompressed size of every block except the last one.
+    block_size: u64,
+    /// Index of the block containing the current position.
+    current_block: usize,
+    /// Position inside the current block.
+    block_position: usize,
+    /// Index of the block that's decompressed in the buffer.
+    loaded_block: Option<usize>,
+    /// Decompressed bytes of the loaded block.
+    buffer: Vec<u8>,
+}
+
+impl EntryReader {
+    /// Create the reader at the start of the file.
+    pub(crate) fn new(archive: &PlaystationArchive, blocks: Vec<Block>, length: u64) -> Self {
+        Self {
+            reader: archive.reader.clone(),
+            archive_len: archive.archive_len,
+            compression_type: archive.compression_type,
+            blocks,
+            length,
+            block_size: archive.block_size.to_u32() as u64,
+            current_block: 0,
+            block_position: 0,
+            loaded_block: None,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Uncompressed size of the file.
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    /// Whether the file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Current position in the uncompressed file.
+    pub fn position(&self) -> u64 {
+        self.current_block as u64 * self.block_size + self.block_position as u64
+    }
+
+    /// Decompress the current block into the buffer if it isn't already.
+    fn load_current_block(&mut self) -> Result<(), ArchiveReadError> {
+        if self.loaded_block == Some(self.current_block) {
+            return Ok(());
+        }
+
+        log::trace!("streaming block {}", self.current_block);
+
+        let block = self.blocks[self.current_block];
+        let stored = read_raw(
+            &self.reader,
+            self.archive_len,
+            block.offset,
+            block.stored_length,
+        )?;
+
+        self.loaded_block = None;
+        self.buffer.resize(block.length, 0);
+        self.compression_type
+            .decode_block(&block, &stored, &mut self.buffer)?;
+        self.loaded_block = Some(self.current_block);
+
+        Ok(())
+    }
+}
+
+impl Read for EntryReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.current_block < self.blocks.len() {
+            self.load_current_block().map_err(into_io_error)?;
+
+            if self.block_position < self.buffer.len() {
+                let bytes = &self.buffer[self.block_position..];
+                let amount = bytes.len().min(buf.len());
+                buf[..amount].copy_from_slice(&bytes[..amount]);
+                self.block_position += amount;
+
+                return Ok(amount);
+            }
+
+            // Stay at the end of the last block so the position is still correct
+            if self.current_block + 1 == self.blocks.len() {
+                break;
+            }
+
+            // Continue with the next block
+            self.current_block += 1;
+            self.block_position = 0;
+        }
+
+        // Reached the end of the file
+        Ok(0)
+    }
+}
+
+impl Seek for EntryReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.length.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position().checked_add_signed(offset),
+        }
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        // Only the block index is set, it will be decompressed when it's read
+        self.current_block = (position / self.block_size) as usize;
+        self.block_position = (position % self.block_size) as usize;
+
+        Ok(position)
+    }
+
+    fn stream_position(&mut self) -> std::io::Result<u64> {
+        Ok(self.position())
+    }
+}
+
+/// Convert an archive error so it can be returned from the IO traits.
+fn into_io_error(err: ArchiveReadError) -> Error {
+    match err {
+        ArchiveReadError::Io(err) => err,
+        err => Error::new(ErrorKind::InvalidData, err),
+    }
+}
@@ -1,7 +1,4 @@
-use std::fmt::Debug;
-
-use nom::error::VerboseError;
-use nom::Err;
+use nom_error::ParseError;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, ArchiveReadError>;
@@ -12,6 +9,8 @@ pub enum ArchiveReadError {
     UnrecognizedFile,
     #[error("unsupported version")]
     UnsupportedVersion,
+    #[error("unsupported compression type 0x{0:08X}")]
+    UnsupportedCompression(u32),
     #[error("corrupt file, reason: {0}")]
     Corrupt(String),
     #[error("file at index {0} does not exist")]
@@ -21,16 +20,49 @@ pub enum ArchiveReadError {
         path: String,
         possible_paths: Vec<String>,
     },
-    #[error("parsing error: {0}")]
-    Nom(String),
+    #[error("path '{0}' can't be stored in an archive")]
+    InvalidPath(String),
+    #[error("path '{0}' is already in the archive")]
+    DuplicatePath(String),
+    /// The offset is relative to the start of the parsed bytes.
+    #[error("parsing {context} failed at byte {offset}")]
+    Parse { context: String, offset: usize },
+    #[error("reading archive: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Single problem found when verifying an archive.
+#[derive(Debug, Error)]
+pub enum IntegrityError {
+    #[error("file '{path}' decompressed to {actual} bytes instead of {expected}")]
+    LengthMismatch {
+        path: String,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("block {block} of file '{path}' is out of bounds")]
+    BlockOutOfBounds { path: String, block: usize },
+    #[error("name digest of file '{path}' is {actual:032X} instead of {expected:032X}")]
+    DigestMismatch {
+        path: String,
+        expected: u128,
+        actual: u128,
+    },
+    #[error("manifest path '{0}' has no file entry")]
+    MissingEntry(String),
+    #[error("block {block} of file '{path}' can't be read: {source}")]
+    Unreadable {
+        path: String,
+        block: usize,
+        source: ArchiveReadError,
+    },
 }
 
-impl<T: Debug> From<Err<VerboseError<T>>> for ArchiveReadError {
-    fn from(err: Err<VerboseError<T>>) -> Self {
-        match err {
-            Err::Incomplete(_) => todo!(),
-            Err::Error(err) => ArchiveReadError::Nom(format!("{:?}", err)),
-            Err::Failure(_) => todo!(),
+impl From<ParseError> for ArchiveReadError {
+    fn from(err: ParseError) -> Self {
+        Self::Parse {
+            context: err.context,
+            offset: err.offset,
         }
     }
 }
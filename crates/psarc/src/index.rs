@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use md5::{Digest, Md5};
+
+/// Calculate the name digest of a path as it's stored in the table of content.
+pub fn name_digest(path: &str) -> u128 {
+    u128::from_be_bytes(Md5::digest(path.as_bytes()).into())
+}
+
+/// Calculate the name digest of an entry from the path as it's written in the manifest.
+///
+/// Archives ignoring case hash the path with its case folded, like the index compares them.
+pub(crate) fn entry_digest(manifest_path: &str, ignore_case: bool) -> u128 {
+    if ignore_case {
+        name_digest(&manifest_path.to_ascii_lowercase())
+    } else {
+        name_digest(manifest_path)
+    }
+}
+
+/// Lookup tables for finding file entries, built when the archive is parsed.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct PathIndex {
+    /// Normalized path of every entry, the position is the entry index.
+    normalized: Vec<String>,
+    /// Normalized full path to the entry index.
+    paths: HashMap<String, usize>,
+    /// Normalized last path component to all entry indices having it.
+    filenames: HashMap<String, Vec<usize>>,
+    /// MD5 name digest to the entry index.
+    digests: HashMap<u128, usize>,
+    /// Whether paths are compared case insensitive.
+    ignore_case: bool,
+}
+
+impl PathIndex {
+    /// Build the index from the paths and name digests of all entries.
+    #[profiling::function]
+    pub fn new<'a, I>(entries: I, ignore_case: bool) -> Self
+    where
+        I: Iterator<Item = (&'a str, u128)>,
+    {
+        let mut this = Self {
+            ignore_case,
+            ..Default::default()
+        };
+
+        for (index, (path, digest)) in entries.enumerate() {
+            let normalized = this.normalize(path);
+
+            // When a path is in the archive multiple times the first one is used
+            this.paths.entry(normalized.clone()).or_insert(index);
+            this.filenames
+                .entry(filename(&normalized).to_string())
+                .or_default()
+                .push(index);
+            this.digests.entry(digest).or_insert(index);
+
+            this.normalized.push(normalized);
+        }
+
+        this
+    }
+
+    /// Find the entry with exactly this path.
+    pub fn exact(&self, path: &str) -> Option<usize> {
+        self.paths.get(&self.normalize(path)).copied()
+    }
+
+    /// Find the first entry where the last path components are the same as the suffix.
+    ///
+    /// `"song.wem"` will match `"audio/song.wem"` but not `"audio/othersong.wem"`.
+    pub fn ending_with(&self, suffix: &str) -> Option<usize> {
+        let suffix = self.normalize(suffix);
+        let suffix = suffix.as_str();
+
+        self.filename(filename(suffix))
+            .iter()
+            .copied()
+            .find(|index| {
+                let path = &self.normalized[*index];
+
+                path == suffix
+                    || (path.ends_with(suffix) && path[..path.len() - suffix.len()].ends_with('/'))
+            })
+    }
+
+    /// All entries where the last path component is the filename.
+    pub fn filename(&self, filename: &str) -> &[usize] {
+        self.filenames
+            .get(&self.normalize(filename))
+            .map(|indices| indices.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Find the entry with the MD5 digest of the path.
+    pub fn digest(&self, digest: u128) -> Option<usize> {
+        self.digests.get(&digest).copied()
+    }
+
+    /// Convert a path to the form used as a key.
+    fn normalize(&self, path: &str) -> String {
+        normalize(path, self.ignore_case)
+    }
+}
+
+/// Convert a path to the form used for comparing it with other paths.
+pub(crate) fn normalize(path: &str, ignore_case: bool) -> String {
+    // Absolute paths are stored without the slash
+    let path = path.trim_start_matches('/');
+
+    if ignore_case {
+        path.to_ascii_lowercase()
+    } else {
+        path.to_string()
+    }
+}
+
+/// Get the last component of a path.
+fn filename(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
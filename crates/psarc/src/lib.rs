@@ -1,33 +1,62 @@
+mod builder;
+mod entry;
 mod error;
+mod index;
+mod metadata;
+mod overlay;
+mod patch;
 mod utils;
 
 use std::{
     fmt::{Debug, Formatter},
-    io::{Cursor, Write},
+    io::{Cursor, Read, Seek, SeekFrom, Write},
+    sync::{Arc, Mutex},
 };
 
 use aes::{
     cipher::{AsyncStreamCipher, KeyIvInit},
     Aes256,
 };
+pub use builder::PlaystationArchiveBuilder;
 use cfb_mode::Decryptor;
-pub use error::{ArchiveReadError, Result};
+pub use entry::EntryReader;
+pub use error::{ArchiveReadError, IntegrityError, Result};
 use flate2::read::ZlibDecoder;
+pub use index::name_digest;
+use index::{entry_digest, PathIndex};
+pub use metadata::EntryMetadata;
 use nom::{
     bytes::complete::take,
     error::{context, VerboseError},
     multi::count,
-    number::complete::{be_u128, be_u16, be_u32},
+    number::complete::{be_u128, be_u16, be_u24, be_u32},
     IResult,
 };
-use semver::Version;
+use nom_error::WithOffset;
+pub use overlay::{ArchiveOverlay, OverlayEntry};
+pub use patch::ArchivePatch;
+pub use semver::Version;
 
 /// Rocksmith decryption primitives.
 const ARC_KEY: [u8; 32] =
     hex_literal::hex!("C53DB23870A1A2F71CAE64061FDD0E1157309DC85204D4C5BFDF25090DF2572C");
 const ARC_IV: [u8; 16] = hex_literal::hex!("E915AA018FEF71FC508132E4BB4CEB42");
 
+/// Magic number at the start of every archive, "PSAR".
+const MAGIC: u32 = 0x50534152;
+/// Size of the fixed part of the header, before the table of content entries.
+const HEADER_SIZE: usize = 32;
+/// Size of a single file entry in the table of content.
+const ENTRY_SIZE: u32 = 30;
+
+/// Source the archive data can be read from.
+trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
 /// Parsed Playstation archive file.
+///
+/// Clones share the same reader.
 #[derive(Clone)]
 pub struct PlaystationArchive {
     /// Supported version of this archive format.
@@ -38,55 +67,108 @@ pub struct PlaystationArchive {
     file_entries: Vec<FileEntry>,
     /// How big the file block is.
     block_size: BlockSize,
-    /// The actual file data.
-    data: Vec<u8>,
+    /// Source of the file data, only read when a file is requested.
+    reader: Arc<Mutex<Box<dyn ReadSeek>>>,
     /// How the paths of the archive are formatted.
     archive_flags: ArchiveFlags,
     /// Sizes of the blocks.
-    block_sizes: Vec<u16>,
+    block_sizes: Vec<u32>,
+    /// Lookup tables for the paths, built after the manifest is parsed.
+    index: PathIndex,
+    /// Size of the whole archive in bytes.
+    archive_len: u64,
+    /// Manifest lines that don't have a file entry.
+    orphan_paths: Vec<String>,
 }
 
 impl PlaystationArchive {
     /// Parse the bytes of a playstation archive file.
+    ///
+    /// The bytes are copied, use [`Self::from_reader`] to avoid keeping the whole archive in
+    /// memory.
     #[profiling::function]
     pub fn parse(file: &[u8]) -> Result<Self> {
-        log::debug!("parsing psarc file of {} bytes", file.len());
+        Self::from_reader(Cursor::new(file.to_vec()))
+    }
 
-        let (i, magic) = parse_magic(file)?;
+    /// Parse a playstation archive from a seekable source, such as a file handle.
+    ///
+    /// Only the header, table of content and block sizes are read, the file data is read when
+    /// requested.
+    #[profiling::function]
+    pub fn from_reader<R>(mut reader: R) -> Result<Self>
+    where
+        R: Read + Seek + Send + 'static,
+    {
+        let archive_len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+
+        log::debug!("parsing psarc file of {} bytes", archive_len);
+
+        // Read the fixed part of the header first so we know how big the table of content is
+        let mut header = vec![0; HEADER_SIZE];
+        reader.read_exact(&mut header).map_err(|_| {
+            ArchiveReadError::Corrupt("file is too small to contain a header".to_string())
+        })?;
+
+        let (i, magic) = parse_magic(&header).offset_in(&header)?;
         if !magic {
             return Err(ArchiveReadError::UnrecognizedFile);
         }
 
-        let (i, version) = parse_version(i)?;
+        let (i, version) = parse_version(i).offset_in(&header)?;
         if version != Version::new(1, 4, 0) {
             return Err(ArchiveReadError::UnsupportedVersion);
         }
 
-        let (i, compression_type_value) = parse_compression_type(i)?;
+        let (i, compression_type_value) = parse_compression_type(i).offset_in(&header)?;
         let compression_type = CompressionType::try_from_u32(compression_type_value)?;
 
-        let (i, table_of_content) = parse_toc(i)?;
+        let (_, toc_length) = context("table of contents length", be_u32)(i).offset_in(&header)?;
+        if (toc_length as usize) < HEADER_SIZE || toc_length as u64 > archive_len {
+            return Err(ArchiveReadError::Corrupt(
+                "table of content length is out of bounds".to_string(),
+            ));
+        }
+
+        // Read the rest of the table of content, including the block sizes
+        header.resize(toc_length as usize, 0);
+        reader.read_exact(&mut header[HEADER_SIZE..])?;
+        let file = header.as_slice();
+
+        let (i, table_of_content) = parse_toc(&file[12..]).offset_in(file)?;
+        if table_of_content.entry_size != ENTRY_SIZE {
+            return Err(ArchiveReadError::Corrupt(format!(
+                "table of content entry size {} is not {}",
+                table_of_content.entry_size, ENTRY_SIZE
+            )));
+        }
 
         log::trace!("got {} entries", table_of_content.entry_count);
 
-        let (i, block_size_value) = parse_block_size(i)?;
+        let (i, block_size_value) = parse_block_size(i).offset_in(file)?;
         let block_size = BlockSize::try_from_u32(block_size_value)?;
 
         log::trace!("got block size of {}", block_size.to_u32());
 
-        let (_, archive_flags_value) = parse_archive_flags(i)?;
+        let (_, archive_flags_value) = parse_archive_flags(i).offset_in(file)?;
         let archive_flags = ArchiveFlags::try_from_u32(archive_flags_value)?;
 
+        // If the archive flag is set to encrypted we'll have to decrypt the data
+        let toc_bytes = table_of_content.decrypt(archive_flags)?;
+
         // Get all file entries from the table of content
-        let file_entries = table_of_content.file_entries(archive_flags)?;
+        let file_entries = table_of_content.file_entries(&toc_bytes)?;
 
         // Skip the file entries part
-        let blocks_offset = table_of_content.size() + 32;
-        let i = &file[blocks_offset as usize..];
+        let i = toc_bytes.get(table_of_content.size()..).ok_or_else(|| {
+            ArchiveReadError::Corrupt("table of content entries are out of bounds".to_string())
+        })?;
 
         // Calculate the amount of block sizes based on the size of the table of content
-        let num_blocks = (table_of_content.length - blocks_offset) / 2;
-        let (_, block_sizes) = parse_block_sizes(i, num_blocks as usize)?;
+        let num_blocks = i.len() / block_size.table_entry_size();
+        let (_, block_sizes) =
+            parse_block_sizes(i, num_blocks, block_size).offset_in(&toc_bytes)?;
 
         log::trace!("got {} block sizes", block_sizes.len());
 
@@ -95,15 +177,25 @@ impl PlaystationArchive {
             compression_type,
             file_entries,
             block_size,
-            data: file.to_vec(),
+            reader: Arc::new(Mutex::new(Box::new(reader))),
             archive_flags,
             block_sizes,
+            index: PathIndex::default(),
+            archive_len,
+            orphan_paths: Vec::new(),
         };
 
-        this.calculate_file_entry_sizes()?;
+        this.calculate_file_entry_sizes(archive_len)?;
 
         this.parse_manifest()?;
 
+        this.index = PathIndex::new(
+            this.file_entries
+                .iter()
+                .map(|entry| (entry.path.as_str(), entry.name_digest)),
+            this.archive_flags.contains(ArchiveFlags::IGNORE_CASE),
+        );
+
         log::debug!("file succesfully parsed");
 
         Ok(this)
@@ -125,99 +217,285 @@ impl PlaystationArchive {
         if self.compression_type == CompressionType::None
             || entry.input_length == entry.length as usize
         {
-            return Ok(self.data
-                [entry.offset as usize..entry.offset as usize + entry.length as usize]
-                .to_vec());
-        } else if self.compression_type == CompressionType::Lzma {
-            // We don't support this compression type yet
-            todo!();
+            return self.read_raw(entry.offset, entry.length as usize);
         }
 
-        // Setup a cursor that will write the result into the vector
-        let mut result = Cursor::new(Vec::with_capacity(entry.length as usize));
+        let blocks = self.blocks(entry)?;
 
-        // Get a slice which will be data for this entry
-        let all_block_bytes =
-            &self.data[entry.offset as usize..entry.offset as usize + entry.input_length];
+        log::trace!("reading {} blocks", blocks.len());
 
-        // Calculate how much blocks must be parsed
-        let total_blocks = (entry.length as f32 / self.block_size.to_u32() as f32).ceil() as usize;
+        // Get the bytes which will be data for this entry
+        let stored_length = blocks.iter().map(|block| block.stored_length).sum();
+        let all_block_bytes = self.read_raw(entry.offset, stored_length)?;
 
-        log::trace!("reading {} blocks", total_blocks);
+        // Split the stored bytes into the data of every block
+        let mut chunk = all_block_bytes.as_slice();
+        let stored_blocks = blocks
+            .iter()
+            .map(|block| {
+                let (stored, rest) = chunk.split_at(block.stored_length);
+                chunk = rest;
 
-        // Extract all blocks
-        let block_start = entry.index_list_size as usize;
-        let mut chunk = all_block_bytes;
-        for block_index in block_start..block_start + total_blocks {
-            // Get the block size from the blocks
-            let block_length = self.block_sizes.get(block_index).unwrap_or(&0);
+                (block, stored)
+            })
+            .collect::<Vec<_>>();
 
-            // Decrypt the blocks
-            if *block_length == 0 {
-                log::trace!("parsing uncompressed block {}", block_index);
+        // Every block is decoded into it's own part of the result
+        let mut result = vec![0; entry.length as usize];
+        self.decode_blocks(&stored_blocks, &mut result)?;
 
-                todo!()
-            } else {
-                // Try to find the magic bytes denoting the block as zlib compressed
-                let zlib_magic = if chunk.len() >= 2 {
-                    context("zlib magic", be_u16)(chunk)?.1
-                } else {
-                    // This value will always trigger the rest of the bytes to be copied
-                    0
-                };
+        log::trace!("read total of {} bytes", result.len());
 
-                log::trace!("parsing block {}", block_index,);
-
-                if zlib_magic == 0x78DA || zlib_magic == 0x7801 {
-                    // Decode if compressed
-                    let mut decoder = ZlibDecoder::new(chunk);
-                    std::io::copy(&mut decoder, &mut result).map_err(|_| {
-                        ArchiveReadError::Corrupt(
-                            "could not copy decoded bytes to output".to_string(),
-                        )
-                    })?;
-
-                    // Move the chunk further by how many bytes the decoder read
-                    chunk = &chunk[decoder.total_in() as usize..];
-                } else {
-                    let mut block_size = self.block_size.to_u32() as usize;
-                    if block_size > chunk.len() {
-                        // Ensure that the block can't be read out of bounds
-                        block_size = chunk.len();
-                    }
+        Ok(result)
+    }
+
+    /// Open a file for streaming, the blocks are decompressed one at a time while reading.
+    ///
+    /// Prefer this over [`Self::read_file`] for big files such as audio.
+    pub fn open_entry(&self, file_index: usize) -> Result<EntryReader> {
+        let entry = self
+            .file_entries
+            .get(file_index)
+            .ok_or(ArchiveReadError::FileDoesNotExist(file_index))?;
 
-                    log::trace!(
-                        "found magic value 0x{:04X}, block is uncompressed with {} bytes",
-                        zlib_magic,
-                        block_size
-                    );
+        if !entry.path.is_empty() {
+            log::debug!("opening file '{}'", entry.path);
+        }
 
-                    // Write the rest
-                    result.write(&chunk[..block_size]).map_err(|_| {
-                        ArchiveReadError::Corrupt(
-                            "could not copy uncompressed bytes to result buffer".to_string(),
-                        )
-                    })?;
+        Ok(EntryReader::new(self, self.blocks(entry)?, entry.length))
+    }
 
-                    chunk = &chunk[block_size..];
-                }
+    /// Open a file from a path for streaming.
+    pub fn open_entry_with_path(&self, path: &str) -> Result<EntryReader> {
+        let index = self
+            .index_for_path(path)
+            .ok_or_else(|| ArchiveReadError::PathNotFound {
+                path: path.to_string(),
+                possible_paths: self.paths(),
+            })?;
+
+        self.open_entry(index)
+    }
+
+    /// Start changing files, the result is written as a new archive.
+    ///
+    /// Unchanged files are copied without recompressing them.
+    pub fn patch(&self) -> ArchivePatch<'_> {
+        ArchivePatch::new(self)
+    }
+
+    /// Check every file in the archive, all problems found are returned.
+    ///
+    /// All blocks are decompressed, so this is as slow as reading every file.
+    #[profiling::function]
+    pub fn verify(&self) -> Vec<IntegrityError> {
+        let mut errors = Vec::new();
+
+        // The manifest doesn't have a name digest
+        for entry in self.file_entries.iter().skip(1) {
+            // The digest is calculated from the path as it's written in the manifest
+            let manifest_path = if self.archive_flags.contains(ArchiveFlags::ABSOLUTE) {
+                format!("/{}", entry.path)
+            } else {
+                entry.path.clone()
+            };
+            let digest = entry_digest(
+                &manifest_path,
+                self.archive_flags.contains(ArchiveFlags::IGNORE_CASE),
+            );
+            if digest != entry.name_digest {
+                errors.push(IntegrityError::DigestMismatch {
+                    path: entry.path.clone(),
+                    expected: digest,
+                    actual: entry.name_digest,
+                });
             }
         }
 
-        let string = result.into_inner();
+        for entry in self.file_entries.iter() {
+            if let Err(err) = self.verify_entry_data(entry) {
+                errors.push(err);
+            }
+        }
 
-        log::trace!("read total of {} bytes", string.len());
+        errors.extend(
+            self.orphan_paths
+                .iter()
+                .map(|path| IntegrityError::MissingEntry(path.clone())),
+        );
 
-        // Verify the result size
-        if string.len() != entry.length as usize {
-            Err(ArchiveReadError::Corrupt(
-                "read entry bytes doesn't match expected bytes".to_string(),
-            ))
+        errors
+    }
+
+    /// Decompress all blocks of a file and check whether they are the expected size.
+    fn verify_entry_data(&self, entry: &FileEntry) -> std::result::Result<(), IntegrityError> {
+        let mut decompressed = 0;
+        for (index, block) in self.blocks_iter(entry).enumerate() {
+            let block = block.map_err(|_| IntegrityError::BlockOutOfBounds {
+                path: entry.path.clone(),
+                block: index,
+            })?;
+            let unreadable = |source| IntegrityError::Unreadable {
+                path: entry.path.clone(),
+                block: index,
+                source,
+            };
+
+            let stored = self
+                .read_raw(block.offset, block.stored_length)
+                .map_err(unreadable)?;
+
+            if block.stored_length == block.length {
+                decompressed += block.stored_length as u64;
+            } else {
+                let mut result = Vec::with_capacity(block.length);
+                self.compression_type
+                    .decompress_block(&stored, &mut result)
+                    .map_err(unreadable)?;
+
+                decompressed += result.len() as u64;
+            }
+        }
+
+        if decompressed != entry.length {
+            Err(IntegrityError::LengthMismatch {
+                path: entry.path.clone(),
+                expected: entry.length,
+                actual: decompressed,
+            })
         } else {
-            Ok(string)
+            Ok(())
         }
     }
 
+    /// Get the metadata of a file.
+    pub fn metadata(&self, file_index: usize) -> Result<EntryMetadata<'_>> {
+        let entry = self
+            .file_entries
+            .get(file_index)
+            .ok_or(ArchiveReadError::FileDoesNotExist(file_index))?;
+
+        let blocks = self.blocks(entry)?;
+
+        Ok(EntryMetadata {
+            path: &entry.path,
+            name_digest: entry.name_digest,
+            first_block: entry.index_list_size,
+            block_count: blocks.len(),
+            length: entry.length,
+            stored_length: blocks.iter().map(|block| block.stored_length as u64).sum(),
+            offset: entry.offset,
+        })
+    }
+
+    /// Metadata of all files as an iterator.
+    pub fn metadata_iter(&'_ self) -> impl Iterator<Item = EntryMetadata<'_>> {
+        (0..self.file_entries.len()).filter_map(|index| self.metadata(index).ok())
+    }
+
+    /// Version of the archive format.
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    /// How the blocks are compressed.
+    pub fn compression_type(&self) -> CompressionType {
+        self.compression_type
+    }
+
+    /// How big each data block is.
+    pub fn block_size(&self) -> BlockSize {
+        self.block_size
+    }
+
+    /// How the paths are formatted and whether the table of content is encrypted.
+    pub fn archive_flags(&self) -> ArchiveFlags {
+        self.archive_flags
+    }
+
+    /// Calculate where all blocks of a file are stored.
+    fn blocks(&self, entry: &FileEntry) -> Result<Vec<Block>> {
+        self.blocks_iter(entry).collect()
+    }
+
+    /// Calculate where each block of a file is stored, one by one.
+    fn blocks_iter<'a>(&'a self, entry: &'a FileEntry) -> impl Iterator<Item = Result<Block>> + 'a {
+        let block_size = self.block_size.to_u32() as usize;
+        let length = entry.length as usize;
+
+        // When nothing is compressed the blocks are the uncompressed data
+        let raw = self.compression_type == CompressionType::None || entry.input_length == length;
+
+        let mut offset = entry.offset;
+        let mut remaining_input = entry.input_length;
+        (0..length.div_ceil(block_size)).map(move |block_index| {
+            // How many bytes this block will be when uncompressed, only the last block is
+            // smaller
+            let uncompressed_length = (length - block_index * block_size).min(block_size);
+
+            let stored_length = if raw {
+                if offset + uncompressed_length as u64 > self.archive_len {
+                    return Err(ArchiveReadError::Corrupt(
+                        "file entry data is out of bounds".to_string(),
+                    ));
+                }
+
+                uncompressed_length
+            } else {
+                // Get the block size from the blocks, a zero length means a raw block filling
+                // the whole block size
+                let table_index = entry.index_list_size as usize + block_index;
+                let block_length = match self.block_sizes.get(table_index) {
+                    Some(0) => block_size,
+                    Some(block_length) => *block_length as usize,
+                    None => {
+                        return Err(ArchiveReadError::Corrupt(format!(
+                            "block {} is missing from the block size table",
+                            table_index
+                        )))
+                    }
+                };
+
+                // Ensure that the block can't be read out of the bounds of the entry
+                block_length.min(remaining_input)
+            };
+
+            let block = Block {
+                offset,
+                stored_length,
+                length: uncompressed_length,
+            };
+
+            offset += stored_length as u64;
+            remaining_input = remaining_input.saturating_sub(stored_length);
+
+            Ok(block)
+        })
+    }
+
+    /// Decode all blocks of a file, on the rayon thread pool with the `parallel` feature.
+    #[profiling::function]
+    fn decode_blocks(&self, blocks: &[(&Block, &[u8])], result: &mut [u8]) -> Result<()> {
+        #[cfg(feature = "parallel")]
+        use rayon::prelude::*;
+
+        let block_size = self.block_size.to_u32() as usize;
+
+        #[cfg(not(feature = "parallel"))]
+        let mut chunks = result.chunks_mut(block_size).zip(blocks);
+        #[cfg(feature = "parallel")]
+        let chunks = result.par_chunks_mut(block_size).zip(blocks.par_iter());
+
+        chunks.try_for_each(|(output, (block, stored))| {
+            self.compression_type.decode_block(block, stored, output)
+        })
+    }
+
+    /// Read the stored bytes at an offset in the archive.
+    fn read_raw(&self, offset: u64, length: usize) -> Result<Vec<u8>> {
+        read_raw(&self.reader, self.archive_len, offset, length)
+    }
+
     /// Read a file from a path.
     #[profiling::function]
     pub fn read_file_with_path(&self, path: &str) -> Result<Vec<u8>> {
@@ -240,7 +518,7 @@ impl PlaystationArchive {
 
         let searchable_path = format!(
             "{}.{}",
-            path.split(':').last().expect("malformed path"),
+            path.split(':').next_back().expect("malformed path"),
             extension
         );
         let index = self
@@ -269,34 +547,39 @@ impl PlaystationArchive {
     }
 
     /// Get the index for a file path.
+    ///
+    /// The comparison is case insensitive when the archive flags say so.
     #[profiling::function]
     pub fn index_for_path(&self, path: &str) -> Option<usize> {
-        self.file_entries
-            .iter()
-            .enumerate()
-            .find(|(_, entry)| entry.path == path)
-            .map(|(i, _)| i)
+        self.index.exact(path)
     }
 
-    /// Get the index for a file path.
+    /// Get the index for a file path where the last path components match.
+    ///
+    /// `"song.wem"` will match `"audio/song.wem"` but not `"audio/othersong.wem"`.
+    #[profiling::function]
     pub fn index_for_path_ending_with(&self, path: &str) -> Option<usize> {
-        self.file_entries
-            .iter()
-            .enumerate()
-            .find(|(_, entry)| entry.path.ends_with(path))
-            .map(|(i, _)| i)
+        self.index.ending_with(path)
+    }
+
+    /// Get the indices of all files with this filename, in any directory.
+    pub fn indices_for_filename(&self, filename: &str) -> &[usize] {
+        self.index.filename(filename)
+    }
+
+    /// Get the index for the 128-bit MD5 digest of a path, see [`name_digest`].
+    pub fn index_for_name_digest(&self, digest: u128) -> Option<usize> {
+        self.index.digest(digest)
     }
 
     /// Get the full path name for a path ending with the string.
     #[profiling::function]
     pub fn path_ending_with(&self, path: &str) -> Option<&str> {
-        self.index_for_path_ending_with(path)
-            .map(|index| {
-                self.file_entries
-                    .get(index)
-                    .map(|entry| entry.path.as_str())
-            })
-            .flatten()
+        self.index_for_path_ending_with(path).and_then(|index| {
+            self.file_entries
+                .get(index)
+                .map(|entry| entry.path.as_str())
+        })
     }
 
     /// Get the full path name for a path ending with a string, throw an error when it fails.
@@ -308,6 +591,13 @@ impl PlaystationArchive {
             })
     }
 
+    /// Get the full path of a file.
+    pub fn path(&self, file_index: usize) -> Option<&str> {
+        self.file_entries
+            .get(file_index)
+            .map(|entry| entry.path.as_str())
+    }
+
     /// All file paths as an iterator.
     #[profiling::function]
     pub fn paths_iter(&'_ self) -> impl Iterator<Item = &'_ String> {
@@ -323,7 +613,8 @@ impl PlaystationArchive {
         self.file_entries
             .iter()
             .enumerate()
-            .filter_map(move |(i, entry)| entry.path.ends_with(extension).then(|| (i, &entry.path)))
+            .filter(move |(_, entry)| entry.path.ends_with(extension))
+            .map(|(i, entry)| (i, &entry.path))
     }
 
     /// Amount of files in the archive.
@@ -346,7 +637,7 @@ impl PlaystationArchive {
 
     /// Fill the file entry sizes with the calculated total size.
     #[profiling::function]
-    pub fn calculate_file_entry_sizes(&mut self) -> Result<()> {
+    fn calculate_file_entry_sizes(&mut self, archive_len: u64) -> Result<()> {
         // Calculate the input lengths for the every but the last item
         let next_offsets = self
             .file_entries
@@ -358,13 +649,13 @@ impl PlaystationArchive {
         self.file_entries
             .iter_mut()
             .zip(next_offsets)
-            .for_each(|(mut entry, next_offset)| {
-                entry.input_length = (next_offset - entry.offset) as usize;
+            .for_each(|(entry, next_offset)| {
+                entry.input_length = next_offset.saturating_sub(entry.offset) as usize;
             });
 
         // Calculate the input length for the last item
-        if let Some(mut last_entry) = self.file_entries.last_mut() {
-            last_entry.input_length = self.data.len() - last_entry.offset as usize;
+        if let Some(last_entry) = self.file_entries.last_mut() {
+            last_entry.input_length = archive_len.saturating_sub(last_entry.offset) as usize;
         }
 
         Ok(())
@@ -375,11 +666,22 @@ impl PlaystationArchive {
     fn parse_manifest(&mut self) -> Result<()> {
         log::debug!("reading manifest");
 
-        // Convert the lines to a vector of strings
-        std::iter::once("manifest.txt")
-            .chain(self.read_file_as_string(0)?.lines())
+        // Convert the lines to a vector of strings, without the slash for absolute paths
+        let flags = self.archive_flags;
+        let manifest = self.read_file_as_string(0)?;
+        for (i, line) in std::iter::once("manifest.txt")
+            .chain(manifest.lines())
             .enumerate()
-            .for_each(|(i, line)| self.file_entries[i].path = line.to_string());
+        {
+            match self.file_entries.get_mut(i) {
+                Some(entry) => entry.path = flags.normalize_path(line),
+                None => {
+                    log::warn!("manifest path '{}' has no file entry", line);
+
+                    self.orphan_paths.push(line.to_string());
+                }
+            }
+        }
 
         Ok(())
     }
@@ -399,10 +701,14 @@ impl Debug for PlaystationArchive {
 }
 
 /// How the archive is compressed.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum CompressionType {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompressionType {
+    /// All data is stored raw.
     None,
+    /// Each block is compressed with zlib, what Rocksmith uses.
+    #[default]
     Zlib,
+    /// Each block is compressed with LZMA.
     Lzma,
 }
 
@@ -413,47 +719,123 @@ impl CompressionType {
             0x00000000 => Ok(CompressionType::None),
             0x7A6C6962 => Ok(CompressionType::Zlib),
             0x6C7A6D61 => Ok(CompressionType::Lzma),
-            _ => Err(ArchiveReadError::Corrupt(
-                "unrecognized compression type".to_string(),
-            )),
+            _ => Err(ArchiveReadError::UnsupportedCompression(value)),
+        }
+    }
+
+    /// Convert the compression type to it's header representation.
+    pub fn to_u32(self) -> u32 {
+        match self {
+            CompressionType::None => 0x00000000,
+            CompressionType::Zlib => 0x7A6C6962,
+            CompressionType::Lzma => 0x6C7A6D61,
+        }
+    }
+
+    /// Decode the stored bytes of a single block into the output, which has the size of the
+    /// uncompressed block.
+    fn decode_block(self, block: &Block, stored: &[u8], output: &mut [u8]) -> Result<()> {
+        let mut remaining = &mut output[..];
+
+        if stored.len() == block.length {
+            // The block didn't get smaller by compressing so it's stored raw
+            remaining.write_all(stored)?;
+        } else {
+            self.decompress_block(stored, &mut remaining)?;
+        }
+
+        if !remaining.is_empty() {
+            Err(ArchiveReadError::Corrupt(format!(
+                "block decoded to {} bytes instead of {}",
+                block.length - remaining.len(),
+                block.length
+            )))
+        } else {
+            Ok(())
         }
     }
+
+    /// Decompress a single block and write it to the result.
+    fn decompress_block<W>(self, block: &[u8], result: &mut W) -> Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            CompressionType::Zlib => {
+                // Every compression level has a different header, validate it instead
+                let (_, zlib_header) = context("zlib header", be_u16)(block).offset_in(block)?;
+                if !is_zlib_header(zlib_header) {
+                    return Err(ArchiveReadError::Corrupt(format!(
+                        "block has invalid zlib header 0x{:04X}",
+                        zlib_header
+                    )));
+                }
+
+                let mut decoder = ZlibDecoder::new(block);
+                std::io::copy(&mut decoder, result).map_err(|_| {
+                    ArchiveReadError::Corrupt("could not copy decoded bytes to output".to_string())
+                })?;
+            }
+            CompressionType::Lzma => {
+                lzma_rs::lzma_decompress(&mut &*block, result).map_err(|err| {
+                    ArchiveReadError::Corrupt(format!("could not decode lzma block: {}", err))
+                })?;
+            }
+            CompressionType::None => result.write_all(block)?,
+        }
+
+        Ok(())
+    }
 }
 
-/// How the paths of the archive are formatted.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum ArchiveFlags {
-    /// The paths won't have slash at the start of every line, everything is accessed as if the
-    /// archive is a directory.
-    Relative,
-    /// All paths are case insensitive.
-    IgnoreCase,
-    /// All paths start with a slash.
-    Absolute,
-    /// TOC is encrypted.
-    Encrypted,
+bitflags::bitflags! {
+    /// How the paths of the archive are formatted, the flags can be combined.
+    ///
+    /// Without any flags set the paths won't have slash at the start of every line, everything
+    /// is accessed as if the archive is a directory.
+    #[derive(Default)]
+    pub struct ArchiveFlags: u32 {
+        /// All paths are case insensitive.
+        const IGNORE_CASE = 1;
+        /// All paths start with a slash.
+        const ABSOLUTE = 2;
+        /// TOC is encrypted.
+        const ENCRYPTED = 4;
+    }
 }
 
 impl ArchiveFlags {
     /// Parse the value from the archive header.
     pub fn try_from_u32(value: u32) -> Result<Self> {
-        match value {
-            0 => Ok(ArchiveFlags::Relative),
-            1 => Ok(ArchiveFlags::IgnoreCase),
-            2 => Ok(ArchiveFlags::Absolute),
-            4 => Ok(ArchiveFlags::Encrypted),
-            _ => Err(ArchiveReadError::Corrupt(
-                "unrecognized archive flags".to_string(),
-            )),
+        Self::from_bits(value).ok_or_else(|| {
+            ArchiveReadError::Corrupt(format!("unrecognized archive flags 0x{:X}", value))
+        })
+    }
+
+    /// Convert the flags to it's header representation.
+    pub fn to_u32(self) -> u32 {
+        self.bits()
+    }
+
+    /// Convert a path from the manifest to the form used for accessing it.
+    pub fn normalize_path(self, path: &str) -> String {
+        if self.contains(ArchiveFlags::ABSOLUTE) {
+            path.trim_start_matches('/').to_string()
+        } else {
+            path.to_string()
         }
     }
 }
 
 /// How big each data block is in bytes.
-#[derive(Debug, Clone, Copy)]
-enum BlockSize {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlockSize {
+    /// Blocks of 64KiB, the size Rocksmith uses.
+    #[default]
     U16,
+    /// Blocks of 16MiB.
     U24,
+    /// Blocks of 4GiB.
     U32,
 }
 
@@ -478,6 +860,15 @@ impl BlockSize {
             BlockSize::U32 => 4294967295,
         }
     }
+
+    /// How many bytes each entry in the block size table takes.
+    pub fn table_entry_size(self) -> usize {
+        match self {
+            BlockSize::U16 => 2,
+            BlockSize::U24 => 3,
+            BlockSize::U32 => 4,
+        }
+    }
 }
 
 /// Archive table of content data.
@@ -490,41 +881,42 @@ struct TableOfContent<'a> {
 }
 
 impl<'a> TableOfContent<'a> {
-    /// Get all file entries.
+    /// Get all file entries from the decrypted table of content.
     #[profiling::function]
-    pub fn file_entries(&self, flags: ArchiveFlags) -> Result<Vec<FileEntry>> {
-        // If the archive flag is set to encrypted we'll have to decrypt the data
-        let mut i = self.decrypt(flags)?;
+    pub fn file_entries(&self, decrypted: &[u8]) -> Result<Vec<FileEntry>> {
+        let mut i = decrypted;
 
         (0..self.entry_count)
             .map(|_| {
-                let (i_ref, file_entry) = parse_file_entry(&i)?;
-                i = i_ref.into();
+                let (i_ref, file_entry) = parse_file_entry(i).offset_in(decrypted)?;
+                i = i_ref;
 
                 Ok(file_entry)
             })
             .collect()
     }
 
-    /// Decrypt the TOC if the archive flag is set to encrypted.
+    /// Decrypt the TOC, including the block sizes, if the archive flag is set to encrypted.
     #[profiling::function]
     pub fn decrypt(&self, flags: ArchiveFlags) -> Result<Vec<u8>> {
         // Skip the first bytes that have already been parsed
-        let (i, _) = take(8usize)(self.data)?;
+        let (i, _) = take(8usize)(self.data).offset_in(self.data)?;
 
-        // Take the exact bytes for the TOS
-        let (_, bytes) = context("table of content bytes", take(self.size()))(i)?;
+        // Take the exact bytes for the TOS, the block sizes are encrypted with it
+        let total_size = self.length.saturating_sub(HEADER_SIZE as u32);
+        let (_, bytes) =
+            context("table of content bytes", take(total_size))(i).offset_in(self.data)?;
         let mut bytes = bytes.to_vec();
 
         // Decrypt the TOS if the Rocksmith encryption flags have been set
-        if flags == ArchiveFlags::Encrypted {
+        if flags.contains(ArchiveFlags::ENCRYPTED) {
             // Decrypt the TOS
             let decryptor = Decryptor::<Aes256>::new(&ARC_KEY.into(), &ARC_IV.into());
 
             decryptor.decrypt(&mut bytes);
         }
 
-        if bytes.len() != self.size() as usize {
+        if bytes.len() < self.size() {
             Err(ArchiveReadError::Corrupt(
                 "table of content input size doesn't match decrypted size".to_string(),
             ))
@@ -533,9 +925,9 @@ impl<'a> TableOfContent<'a> {
         }
     }
 
-    /// Get the true amount of bytes for the TOC.
-    pub fn size(&self) -> u32 {
-        self.entry_size * self.entry_count
+    /// Get the true amount of bytes for the TOC entries.
+    pub fn size(&self) -> usize {
+        self.entry_size as usize * self.entry_count as usize
     }
 }
 
@@ -544,6 +936,8 @@ impl<'a> TableOfContent<'a> {
 struct FileEntry {
     /// Will be set after manifest is parsed.
     path: String,
+    /// MD5 hash of the path.
+    name_digest: u128,
     /// Index in the block list size.
     index_list_size: u32,
     /// Uncompressed size.
@@ -558,6 +952,7 @@ impl Debug for FileEntry {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("FileEntry")
             .field("path", &self.path)
+            .field("name_digest", &format_args!("{:032X}", self.name_digest))
             .field("index_list_size", &self.index_list_size)
             .field("length", &self.length)
             .field("offset", &self.offset)
@@ -566,12 +961,52 @@ impl Debug for FileEntry {
     }
 }
 
+/// Location of a single block of a file entry.
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    /// Byte offset in the whole archive.
+    offset: u64,
+    /// Amount of bytes stored in the archive.
+    stored_length: usize,
+    /// Amount of bytes after decoding.
+    length: usize,
+}
+
+/// Read the stored bytes at an offset in the archive.
+#[profiling::function]
+fn read_raw(
+    reader: &Mutex<Box<dyn ReadSeek>>,
+    archive_len: u64,
+    offset: u64,
+    length: usize,
+) -> Result<Vec<u8>> {
+    // Don't allocate the buffer when a corrupt entry points outside of the archive
+    if offset.saturating_add(length as u64) > archive_len {
+        return Err(ArchiveReadError::Corrupt(
+            "file entry data is out of bounds".to_string(),
+        ));
+    }
+
+    let mut reader = reader
+        .lock()
+        .map_err(|_| ArchiveReadError::Corrupt("archive reader is poisoned".to_string()))?;
+
+    reader.seek(SeekFrom::Start(offset))?;
+
+    let mut bytes = vec![0; length];
+    reader
+        .read_exact(&mut bytes)
+        .map_err(|_| ArchiveReadError::Corrupt("file entry data is out of bounds".to_string()))?;
+
+    Ok(bytes)
+}
+
 /// Parse the magic number at the beginning of the header.
 #[profiling::function]
 fn parse_magic<'a>(i: &'a [u8]) -> IResult<&'a [u8], bool, VerboseError<&'a [u8]>> {
     let (i, magic) = context("magic", be_u32)(i)?;
 
-    Ok((i, magic == 0x50534152))
+    Ok((i, magic == MAGIC))
 }
 
 /// Parse major and minor version numbers.
@@ -621,7 +1056,7 @@ fn parse_archive_flags<'a>(i: &'a [u8]) -> IResult<&'a [u8], u32, VerboseError<&
 /// Parse file entry.
 #[profiling::function]
 fn parse_file_entry<'a>(i: &'a [u8]) -> IResult<&'a [u8], FileEntry, VerboseError<&'a [u8]>> {
-    let (i, _name_digest_block) = context("file entry", be_u128)(i)?;
+    let (i, name_digest) = context("file entry name digest", be_u128)(i)?;
 
     let (i, index_list_size) = context("file entry index list size", be_u32)(i)?;
 
@@ -629,6 +1064,7 @@ fn parse_file_entry<'a>(i: &'a [u8]) -> IResult<&'a [u8], FileEntry, VerboseErro
     let (i, offset) = context("file entry offset", utils::be_u40)(i)?;
 
     let file_entry = FileEntry {
+        name_digest,
         index_list_size,
         length,
         offset,
@@ -639,11 +1075,25 @@ fn parse_file_entry<'a>(i: &'a [u8]) -> IResult<&'a [u8], FileEntry, VerboseErro
     Ok((i, file_entry))
 }
 
-/// Parse block sizes.
+/// Whether the first two bytes are a zlib header using the deflate method.
+fn is_zlib_header(header: u16) -> bool {
+    // The low nibble of the first byte is the method, the header is a multiple of 31
+    (header >> 8) & 0x0F == 8 && header.is_multiple_of(31)
+}
+
+/// Parse block sizes, the width of each item depends on the block size.
 #[profiling::function]
-fn parse_block_sizes<'a>(
-    i: &'a [u8],
+fn parse_block_sizes(
+    i: &[u8],
     num_blocks: usize,
-) -> IResult<&'a [u8], Vec<u16>, VerboseError<&'a [u8]>> {
-    context("block_sizes", count(be_u16, num_blocks))(i)
+    block_size: BlockSize,
+) -> IResult<&[u8], Vec<u32>, VerboseError<&[u8]>> {
+    match block_size {
+        BlockSize::U16 => context(
+            "block_sizes",
+            count(nom::combinator::map(be_u16, u32::from), num_blocks),
+        )(i),
+        BlockSize::U24 => context("block_sizes", count(be_u24, num_blocks))(i),
+        BlockSize::U32 => context("block_sizes", count(be_u32, num_blocks))(i),
+    }
 }
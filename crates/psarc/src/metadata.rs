@@ -0,0 +1,66 @@
+/// Read-only information about a single file in the archive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EntryMetadata<'a> {
+    /// Path from the manifest.
+    pub(crate) path: &'a str,
+    /// MD5 hash of the path.
+    pub(crate) name_digest: u128,
+    /// Index of the first block in the block size table.
+    pub(crate) first_block: u32,
+    /// Amount of blocks the data is split into.
+    pub(crate) block_count: usize,
+    /// Uncompressed size.
+    pub(crate) length: u64,
+    /// Size of all blocks as stored in the archive.
+    pub(crate) stored_length: u64,
+    /// Byte offset in the whole archive.
+    pub(crate) offset: u64,
+}
+
+impl<'a> EntryMetadata<'a> {
+    /// Path of the file, the manifest itself is `manifest.txt`.
+    pub fn path(&self) -> &'a str {
+        self.path
+    }
+
+    /// MD5 hash of the path as stored in the table of content.
+    pub fn name_digest(&self) -> u128 {
+        self.name_digest
+    }
+
+    /// Index of the first block in the block size table.
+    pub fn first_block(&self) -> u32 {
+        self.first_block
+    }
+
+    /// Amount of blocks the data is split into.
+    pub fn block_count(&self) -> usize {
+        self.block_count
+    }
+
+    /// Size of the file after decompressing.
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// Size of the file as stored in the archive.
+    pub fn stored_length(&self) -> u64 {
+        self.stored_length
+    }
+
+    /// Byte offset of the first block in the archive.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Stored size divided by the uncompressed size, lower is better compressed.
+    ///
+    /// Empty files have a ratio of `1.0`.
+    pub fn compression_ratio(&self) -> f64 {
+        if self.length == 0 {
+            1.0
+        } else {
+            self.stored_length as f64 / self.length as f64
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use std::collections::BTreeSet;
+
+use crate::{ArchiveFlags, ArchiveReadError, EntryReader, PlaystationArchive, Result};
+
+/// Multiple archives layered on top of each other as a single virtual filesystem.
+///
+/// A path resolves to the archive with the highest priority containing it, this way DLC
+/// archives can be layered over the base game archive.
+#[derive(Debug, Default)]
+pub struct ArchiveOverlay {
+    /// All archives, sorted from highest to lowest priority.
+    layers: Vec<Layer>,
+}
+
+impl ArchiveOverlay {
+    /// Create an overlay without any archives.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an archive, files in archives with a higher priority shadow the others.
+    ///
+    /// When the priority is the same as an already added archive the new one shadows it.
+    pub fn add_archive(&mut self, archive: PlaystationArchive, priority: i32) -> &mut Self {
+        let position = self
+            .layers
+            .iter()
+            .position(|layer| layer.priority <= priority)
+            .unwrap_or(self.layers.len());
+        self.layers.insert(position, Layer { archive, priority });
+
+        self
+    }
+
+    /// All archives, from highest to lowest priority.
+    pub fn archives(&'_ self) -> impl Iterator<Item = &'_ PlaystationArchive> {
+        self.layers.iter().map(|layer| &layer.archive)
+    }
+
+    /// Amount of archives.
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Whether there are any archives.
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Find the file with exactly this path.
+    #[profiling::function]
+    pub fn resolve(&self, path: &str) -> Option<OverlayEntry<'_>> {
+        self.find(|archive| archive.index_for_path(path))
+    }
+
+    /// Find the file where the last path components match.
+    ///
+    /// `"song.wem"` will match `"audio/song.wem"` but not `"audio/othersong.wem"`.
+    #[profiling::function]
+    pub fn resolve_ending_with(&self, path: &str) -> Option<OverlayEntry<'_>> {
+        self.find(|archive| archive.index_for_path_ending_with(path))
+    }
+
+    /// Find the file for a Rocksmith urn such as `"urn:image:dds:album_song"`.
+    ///
+    /// Only the last part of the urn is used as the filename, the extension is added to it.
+    #[profiling::function]
+    pub fn resolve_urn(&self, urn: &str, extension: &str) -> Option<OverlayEntry<'_>> {
+        self.resolve_ending_with(&urn_path(urn, extension))
+    }
+
+    /// Whether any archive contains a file with exactly this path.
+    pub fn contains(&self, path: &str) -> bool {
+        self.resolve(path).is_some()
+    }
+
+    /// Read a file from a path.
+    pub fn read_file_with_path(&self, path: &str) -> Result<Vec<u8>> {
+        self.try_resolve(self.resolve(path), path)?.read()
+    }
+
+    /// Stream a file from a path.
+    pub fn open_entry_with_path(&self, path: &str) -> Result<EntryReader> {
+        self.try_resolve(self.resolve(path), path)?.open()
+    }
+
+    /// Read a file from a Rocksmith urn.
+    pub fn read_urn_file(&self, urn: &str, extension: &str) -> Result<Vec<u8>> {
+        let path = urn_path(urn, extension);
+
+        self.try_resolve(self.resolve_ending_with(&path), &path)?
+            .read()
+    }
+
+    /// Read a file from a Rocksmith urn as a string.
+    pub fn read_urn_file_as_string(&self, urn: &str, extension: &str) -> Result<String> {
+        String::from_utf8(self.read_urn_file(urn, extension)?)
+            .map_err(|_| ArchiveReadError::Corrupt("could not convert bytes to utf-8".to_string()))
+    }
+
+    /// Get the full path name for a path ending with the string.
+    pub fn path_ending_with(&self, path: &str) -> Option<&str> {
+        self.resolve_ending_with(path).map(|entry| entry.path())
+    }
+
+    /// Get the full path name for a path ending with a string, throw an error when it fails.
+    pub fn try_path_ending_with(&self, path: &str) -> Result<&str> {
+        self.try_resolve(self.resolve_ending_with(path), path)
+            .map(|entry| entry.path())
+    }
+
+    /// All paths that aren't shadowed by an archive with a higher priority.
+    #[profiling::function]
+    pub fn paths(&self) -> Vec<String> {
+        self.layers
+            .iter()
+            .flat_map(|layer| {
+                layer
+                    .archive
+                    .paths_iter()
+                    .enumerate()
+                    .map(move |(index, path)| (&layer.archive, index, path))
+            })
+            // Only keep the paths that resolve to themselves, this uses the same case folding
+            .filter(|(archive, index, path)| {
+                matches!(self.resolve(path), Some(entry)
+                    if std::ptr::eq(entry.archive, *archive) && entry.index == *index)
+            })
+            .map(|(_, _, path)| path.clone())
+            .collect()
+    }
+
+    /// Direct children of a directory, sorted.
+    ///
+    /// Files are returned as their full path, subdirectories as their full path ending with a
+    /// slash. Listing `"gfxassets/album_art"` can return `"gfxassets/album_art/album_song_256.dds"`.
+    #[profiling::function]
+    pub fn read_directory(&self, directory: &str) -> Vec<String> {
+        let directory = directory.trim_matches('/');
+        let prefix = if directory.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", directory)
+        };
+
+        let mut children = BTreeSet::new();
+        for layer in &self.layers {
+            let ignore_case = layer.ignore_case();
+
+            for path in layer.archive.paths_iter() {
+                let rest = match strip_prefix(path, &prefix, ignore_case) {
+                    Some(rest) if !rest.is_empty() => rest,
+                    _ => continue,
+                };
+
+                // Only keep the first component, everything deeper is a subdirectory
+                let child = match rest.split_once('/') {
+                    Some((subdirectory, _)) => format!("{}{}/", prefix, subdirectory),
+                    None => format!("{}{}", prefix, rest),
+                };
+                children.insert(child);
+            }
+        }
+
+        children.into_iter().collect()
+    }
+
+    /// Whether any archive has a file inside this directory.
+    pub fn is_directory(&self, directory: &str) -> bool {
+        !self.read_directory(directory).is_empty()
+    }
+
+    /// All paths matching a glob pattern, sorted.
+    ///
+    /// `?` matches a single character and `*` any amount of characters, both don't match a
+    /// slash. `**` matches anything including slashes, so `"songs/**/*.sng"` finds the SNG files
+    /// in all subdirectories.
+    #[profiling::function]
+    pub fn glob(&self, pattern: &str) -> Vec<String> {
+        let pattern = pattern.trim_start_matches('/');
+        let mut matches = BTreeSet::new();
+
+        for layer in &self.layers {
+            let ignore_case = layer.ignore_case();
+
+            for path in layer.archive.paths_iter() {
+                let is_match = if ignore_case {
+                    glob_match(
+                        pattern.to_ascii_lowercase().as_bytes(),
+                        path.to_ascii_lowercase().as_bytes(),
+                    )
+                } else {
+                    glob_match(pattern.as_bytes(), path.as_bytes())
+                };
+
+                if is_match {
+                    matches.insert(path.clone());
+                }
+            }
+        }
+
+        matches.into_iter().collect()
+    }
+
+    /// Find the first archive in priority order returning an index.
+    fn find<F>(&self, mut find_index: F) -> Option<OverlayEntry<'_>>
+    where
+        F: FnMut(&PlaystationArchive) -> Option<usize>,
+    {
+        self.layers.iter().find_map(|layer| {
+            find_index(&layer.archive).map(|index| OverlayEntry {
+                archive: &layer.archive,
+                index,
+            })
+        })
+    }
+
+    /// Convert a missing entry to an error.
+    fn try_resolve<'a>(
+        &self,
+        entry: Option<OverlayEntry<'a>>,
+        path: &str,
+    ) -> Result<OverlayEntry<'a>> {
+        entry.ok_or_else(|| ArchiveReadError::PathNotFound {
+            path: path.to_string(),
+            possible_paths: self.paths(),
+        })
+    }
+}
+
+impl From<PlaystationArchive> for ArchiveOverlay {
+    fn from(archive: PlaystationArchive) -> Self {
+        let mut this = Self::new();
+        this.add_archive(archive, 0);
+
+        this
+    }
+}
+
+/// File resolved from one of the archives in the overlay.
+#[derive(Debug, Clone, Copy)]
+pub struct OverlayEntry<'a> {
+    /// Archive containing the file.
+    archive: &'a PlaystationArchive,
+    /// Index of the file in the archive.
+    index: usize,
+}
+
+impl<'a> OverlayEntry<'a> {
+    /// Archive containing the file.
+    pub fn archive(&self) -> &'a PlaystationArchive {
+        self.archive
+    }
+
+    /// Index of the file in the archive.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Full path of the file.
+    pub fn path(&self) -> &'a str {
+        // The index always comes from a lookup in the same archive
+        self.archive.path(self.index).unwrap_or_default()
+    }
+
+    /// Read the whole file.
+    pub fn read(&self) -> Result<Vec<u8>> {
+        self.archive.read_file(self.index)
+    }
+
+    /// Read the whole file as a string.
+    pub fn read_as_string(&self) -> Result<String> {
+        self.archive.read_file_as_string(self.index)
+    }
+
+    /// Stream the file.
+    pub fn open(&self) -> Result<EntryReader> {
+        self.archive.open_entry(self.index)
+    }
+}
+
+/// Single archive in the overlay.
+#[derive(Debug)]
+struct Layer {
+    /// The archive itself.
+    archive: PlaystationArchive,
+    /// Higher priorities shadow lower ones.
+    priority: i32,
+}
+
+impl Layer {
+    /// Whether paths in this archive are compared case insensitive.
+    fn ignore_case(&self) -> bool {
+        self.archive
+            .archive_flags()
+            .contains(ArchiveFlags::IGNORE_CASE)
+    }
+}
+
+/// Convert an urn to the filename it's stored as in the archive.
+fn urn_path(urn: &str, extension: &str) -> String {
+    format!("{}.{}", urn.rsplit(':').next().unwrap_or(urn), extension)
+}
+
+/// Remove the prefix from the path, optionally ignoring ASCII case.
+fn strip_prefix<'a>(path: &'a str, prefix: &str, ignore_case: bool) -> Option<&'a str> {
+    if ignore_case {
+        path.get(..prefix.len())
+            .filter(|start| start.eq_ignore_ascii_case(prefix))
+            .map(|_| &path[prefix.len()..])
+    } else {
+        path.strip_prefix(prefix)
+    }
+}
+
+/// Match a path against a glob pattern.
+fn glob_match(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            // Also allow `**/` to match nothing so `a/**/b` matches `a/b`
+            let rest_without_slash = rest.strip_prefix(b"/").unwrap_or(rest);
+
+            glob_match(rest_without_slash, path)
+                || (0..=path.len()).any(|skip| glob_match(rest, &path[skip..]))
+        }
+        [b'*', rest @ ..] => {
+            // Try every amount of characters until the next slash
+            let until_slash = path.iter().position(|c| *c == b'/').unwrap_or(path.len());
+
+            (0..=until_slash).any(|skip| glob_match(rest, &path[skip..]))
+        }
+        [b'?', rest @ ..] => match path {
+            [c, path_rest @ ..] if *c != b'/' => glob_match(rest, path_rest),
+            _ => false,
+        },
+        [c, rest @ ..] => match path {
+            [path_c, path_rest @ ..] if path_c == c => glob_match(rest, path_rest),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn glob() {
+        let matches = |pattern: &str, path: &str| glob_match(pattern.as_bytes(), path.as_bytes());
+
+        assert!(matches("*.wem", "song.wem"));
+        assert!(!matches("*.wem", "audio/song.wem"));
+        assert!(matches("audio/*/*.wem", "audio/mac/song.wem"));
+        assert!(matches("**/*.wem", "audio/mac/song.wem"));
+        assert!(matches("audio/**/song.wem", "audio/song.wem"));
+        assert!(matches("song_??.sng", "song_ab.sng"));
+        assert!(!matches("song_?.sng", "song_/.sng"));
+        assert!(!matches("song.*", "song"));
+        assert!(matches("song*", "song"));
+        assert!(matches("songs/**", "songs/bin/generic/song.sng"));
+    }
+}
@@ -0,0 +1,182 @@
+use std::io::Write;
+
+use crate::{
+    builder::{valid_path, CompressedEntry},
+    ArchiveFlags, ArchiveReadError, PlaystationArchive, PlaystationArchiveBuilder, Result,
+};
+
+/// Changes to an existing archive, written as a new archive.
+///
+/// Files that aren't changed are copied block by block without recompressing them. Created with
+/// [`PlaystationArchive::patch`].
+#[derive(Debug)]
+pub struct ArchivePatch<'a> {
+    /// Archive the unchanged files are copied from.
+    archive: &'a PlaystationArchive,
+    /// Files with their path in the archive, in the order they will be written.
+    files: Vec<(String, PatchedFile)>,
+}
+
+impl<'a> ArchivePatch<'a> {
+    /// Start with all files of the archive unchanged.
+    pub(crate) fn new(archive: &'a PlaystationArchive) -> Self {
+        // Skip the manifest, it will be generated again
+        let files = archive
+            .paths_iter()
+            .enumerate()
+            .skip(1)
+            .map(|(index, path)| (path.clone(), PatchedFile::Original(index)))
+            .collect();
+
+        Self { archive, files }
+    }
+
+    /// Replace the contents of a file that's already in the archive.
+    pub fn replace_file(&mut self, path: &str, bytes: Vec<u8>) -> Result<&mut Self> {
+        let position = self.try_position(path)?;
+        self.files[position].1 = PatchedFile::New(bytes);
+
+        Ok(self)
+    }
+
+    /// Add a new file with it's path in the archive.
+    pub fn add_file(&mut self, path: &str, bytes: Vec<u8>) -> Result<&mut Self> {
+        let path = valid_path(path)?;
+        if self.position(path).is_some() {
+            return Err(ArchiveReadError::DuplicatePath(path.to_string()));
+        }
+
+        self.files.push((path.to_string(), PatchedFile::New(bytes)));
+
+        Ok(self)
+    }
+
+    /// Remove a file from the archive.
+    pub fn remove_file(&mut self, path: &str) -> Result<&mut Self> {
+        let position = self.try_position(path)?;
+        self.files.remove(position);
+
+        Ok(self)
+    }
+
+    /// Amount of files that will be written, excluding the manifest.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether all files are removed.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Create the patched archive bytes.
+    pub fn build(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes)?;
+
+        Ok(bytes)
+    }
+
+    /// Write the patched archive to a writer.
+    ///
+    /// The writer can't be the source of the archive, write to a temporary file first.
+    #[profiling::function]
+    pub fn write<W>(&self, writer: W) -> Result<()>
+    where
+        W: Write,
+    {
+        // Use the same compression and flags as the original
+        let builder = PlaystationArchiveBuilder::with_settings_of(self.archive);
+
+        let paths = self
+            .files
+            .iter()
+            .map(|(path, _)| builder.manifest_path(path))
+            .collect::<Vec<_>>();
+
+        let mut entries = Vec::with_capacity(self.files.len() + 1);
+        let mut block_sizes = Vec::new();
+        entries.push(builder.compress_manifest(&paths, &mut block_sizes)?);
+        for (path, (_, file)) in paths.iter().zip(self.files.iter()) {
+            entries.push(match file {
+                PatchedFile::Original(index) => {
+                    self.copy_entry(&builder, path, *index, &mut block_sizes)?
+                }
+                PatchedFile::New(bytes) => {
+                    builder.compress_entry(Some(path), bytes, &mut block_sizes)?
+                }
+            });
+        }
+
+        builder.write_entries(writer, entries, block_sizes)
+    }
+
+    /// Copy the stored blocks of an unchanged file.
+    fn copy_entry(
+        &self,
+        builder: &PlaystationArchiveBuilder,
+        path: &str,
+        file_index: usize,
+        block_sizes: &mut Vec<u32>,
+    ) -> Result<CompressedEntry> {
+        let entry = &self.archive.file_entries[file_index];
+        let blocks = self.archive.blocks(entry)?;
+
+        log::debug!("copying {} blocks of file '{}'", blocks.len(), path);
+
+        let first_block = block_sizes.len() as u32;
+        let stored_length = blocks.iter().map(|block| block.stored_length).sum();
+        let data = self.archive.read_raw(entry.offset, stored_length)?;
+
+        // A block with the same size before and after decompressing is stored raw
+        block_sizes.extend(blocks.iter().map(|block| {
+            if block.stored_length == block.length {
+                builder.raw_block_table_value(block.length)
+            } else {
+                block.stored_length as u32
+            }
+        }));
+
+        Ok(CompressedEntry {
+            name_digest: builder.name_digest(path),
+            first_block,
+            length: entry.length as usize,
+            data,
+        })
+    }
+
+    /// Find the position of a file, respecting the case sensitivity of the archive.
+    fn position(&self, path: &str) -> Option<usize> {
+        let path = path.trim_start_matches('/');
+        let ignore_case = self
+            .archive
+            .archive_flags
+            .contains(ArchiveFlags::IGNORE_CASE);
+
+        self.files.iter().position(|(existing, _)| {
+            if ignore_case {
+                existing.eq_ignore_ascii_case(path)
+            } else {
+                existing == path
+            }
+        })
+    }
+
+    /// Find the position of a file, throw an error when it's not in the archive.
+    fn try_position(&self, path: &str) -> Result<usize> {
+        self.position(path)
+            .ok_or_else(|| ArchiveReadError::PathNotFound {
+                path: path.to_string(),
+                possible_paths: self.files.iter().map(|(path, _)| path.clone()).collect(),
+            })
+    }
+}
+
+/// Where the data of a file in the patched archive comes from.
+#[derive(Debug)]
+enum PatchedFile {
+    /// Unchanged file at the index in the original archive.
+    Original(usize),
+    /// New contents.
+    New(Vec<u8>),
+}
@@ -0,0 +1,136 @@
+#!/usr/bin/env python3
+"""Generate the synthetic PSARC test fixtures.
+
+The archives are written straight from the file format, without using the
+`psarc` crate, so the tests don't only check that the reader agrees with the
+writer. Blocks are compressed with Python's zlib and liblzma.
+
+Run from this directory: `python3 generate_fixtures.py`.
+"""
+
+import hashlib
+import lzma
+import struct
+import zlib
+
+BLOCK_SIZE = 65536
+ENTRY_SIZE = 30
+TEXT_EXTENSIONS = ("txt", "xml", "json", "hsan", "version", "appid", "flat", "xblock")
+# Every zlib header the levels produce: 0x7801, 0x785E, 0x789C and 0x78DA
+ZLIB_LEVELS = (1, 4, 6, 9)
+
+
+def contents(path, length):
+    """Deterministic contents, the tests generate the same bytes."""
+    if path.rsplit(".", 1)[-1] in TEXT_EXTENSIONS:
+        # Compressible text
+        text = b""
+        line = 0
+        while len(text) < length:
+            text += f"{path} line {line}\n".encode()
+            line += 1
+
+        return text[:length]
+
+    # Incompressible bytes from a xorshift32 generator seeded with the length
+    state = length
+    data = bytearray(length)
+    for i in range(length):
+        state ^= (state << 13) & 0xFFFFFFFF
+        state ^= state >> 17
+        state ^= (state << 5) & 0xFFFFFFFF
+        data[i] = state & 0xFF
+
+    return bytes(data)
+
+
+def compress(block, compression, index):
+    if compression == b"zlib":
+        return zlib.compress(block, ZLIB_LEVELS[index % len(ZLIB_LEVELS)])
+
+    return lzma.compress(
+        block,
+        format=lzma.FORMAT_ALONE,
+        filters=[{"id": lzma.FILTER_LZMA1, "preset": 6}],
+    )
+
+
+def build(files, compression):
+    """Build an unencrypted archive with relative paths."""
+    manifest = "\n".join(path for path, _ in files).encode()
+    entries = [("", manifest)] + [(path, contents(path, length)) for path, length in files]
+
+    block_sizes = []
+    data = b""
+    toc = []
+    for index, (path, bytes_) in enumerate(entries):
+        first_block = len(block_sizes)
+        offset = len(data)
+        for start in range(0, len(bytes_), BLOCK_SIZE):
+            block = bytes_[start : start + BLOCK_SIZE]
+            compressed = compress(block, compression, index)
+            if len(compressed) < len(block):
+                block_sizes.append(len(compressed))
+                data += compressed
+            else:
+                # Stored raw, a full block is marked with a zero length
+                block_sizes.append(0 if len(block) == BLOCK_SIZE else len(block))
+                data += block
+
+        digest = hashlib.md5(path.encode()).digest() if path else bytes(16)
+        toc.append((digest, first_block, len(bytes_), offset))
+
+    toc_length = 32 + len(entries) * ENTRY_SIZE + len(block_sizes) * 2
+    header = b"PSAR" + struct.pack(
+        ">HH4sIIIII", 1, 4, compression, toc_length, ENTRY_SIZE, len(entries), BLOCK_SIZE, 0
+    )
+    table = b"".join(
+        digest
+        + struct.pack(">I", first_block)
+        + length.to_bytes(5, "big")
+        + (toc_length + offset).to_bytes(5, "big")
+        for digest, first_block, length, offset in toc
+    )
+    sizes = b"".join(struct.pack(">H", size) for size in block_sizes)
+
+    return header + table + sizes + data
+
+
+TEST2 = [
+    ("gfxassets/album_art/album_testsong_64.dds", 2048),
+    ("gfxassets/album_art/album_testsong_128.dds", 8192),
+    ("gfxassets/album_art/album_testsong_256.dds", 65536),
+    ("flatmodels/rs/rsenumerable_root.flat", 200),
+    ("flatmodels/rs/rsenumerable_song.flat", 200),
+    ("toolkit.version", 65),
+    ("appid.appid", 6),
+    ("songs/arr/testsong_lead.xml", 132066),
+    ("songs/bin/generic/testsong_lead.sng", 70000),
+    ("manifests/songs_dlc_testsong/testsong_lead.json", 110),
+    ("songs/arr/testsong_rhythm.xml", 132066),
+    ("songs/bin/generic/testsong_rhythm.sng", 20000),
+    ("manifests/songs_dlc_testsong/testsong_rhythm.json", 110),
+    ("songs/arr/testsong_bass.xml", 132066),
+    ("songs/bin/generic/testsong_bass.sng", 20000),
+    ("manifests/songs_dlc_testsong/testsong_bass.json", 110),
+    ("audio/windows/song_testsong.bnk", 3000),
+    ("audio/windows/123456789.wem", 3 * BLOCK_SIZE + 1234),
+    ("audio/windows/song_testsong_preview.bnk", 3000),
+    ("audio/windows/987654321.wem", 4000),
+    ("manifests/songs_dlc_testsong/songs_dlc_testsong.hsan", 15),
+    ("songs/arr/testsong_showlights.xml", 76),
+    ("songs/arr/testsong_vocals.xml", 21),
+    ("gamexblocks/nsongs/testsong.xblock", 48),
+]
+
+LZMA = [
+    ("songs/arr/testsong_lead.xml", 2 * BLOCK_SIZE + 100),
+    ("audio/windows/123456789.wem", BLOCK_SIZE + 1234),
+    ("manifests/songs_dlc_testsong/testsong_lead.json", 110),
+]
+
+if __name__ == "__main__":
+    with open("test2.psarc", "wb") as f:
+        f.write(build(TEST2, b"zlib"))
+    with open("test_lzma.psarc", "wb") as f:
+        f.write(build(LZMA, b"lzma"))
@@ -1,4 +1,13 @@
-use psarc::PlaystationArchive;
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+};
+
+use proptest::prelude::*;
+use psarc::{
+    ArchiveFlags, ArchiveOverlay, ArchiveReadError, BlockSize, CompressionType, IntegrityError,
+    PlaystationArchive, PlaystationArchiveBuilder,
+};
 
 #[test]
 fn test1() {
@@ -11,14 +20,549 @@ fn test1() {
     });
 }
 
+#[test]
+fn test_reader() {
+    let file = File::open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/test.psarc")).unwrap();
+    let streamed = PlaystationArchive::from_reader(file).unwrap();
+    let psarc = PlaystationArchive::parse(include_bytes!("./test.psarc")).unwrap();
+    assert_eq!(streamed.paths(), psarc.paths());
+
+    // Reading from the file must give the same result as reading from memory
+    (0..psarc.len()).for_each(|index| {
+        assert_eq!(
+            streamed.read_file(index).unwrap(),
+            psarc.read_file(index).unwrap()
+        );
+    });
+
+    // Clones share the file handle
+    let cloned = streamed.clone();
+    assert_eq!(cloned.read_file(1).unwrap(), psarc.read_file(1).unwrap());
+}
+
+#[test]
+fn test_entry_reader() {
+    let psarc = PlaystationArchive::parse(include_bytes!("./test2.psarc")).unwrap();
+
+    // Streaming must give the same result as reading everything at once
+    (0..psarc.len()).for_each(|index| {
+        let mut streamed = Vec::new();
+        psarc
+            .open_entry(index)
+            .unwrap()
+            .read_to_end(&mut streamed)
+            .unwrap();
+        assert_eq!(streamed, psarc.read_file(index).unwrap());
+    });
+
+    let wem = psarc
+        .read_file_with_path("audio/windows/123456789.wem")
+        .unwrap();
+    let mut reader = psarc
+        .open_entry_with_path("audio/windows/123456789.wem")
+        .unwrap();
+    assert_eq!(reader.len(), wem.len() as u64);
+
+    // Seek to a block boundary
+    let mut bytes = vec![0; 100];
+    reader.seek(SeekFrom::Start(65536)).unwrap();
+    reader.read_exact(&mut bytes).unwrap();
+    assert_eq!(bytes, wem[65536..65636]);
+
+    // Seek inside the last block
+    let mut bytes = Vec::new();
+    reader.seek(SeekFrom::End(-1000)).unwrap();
+    reader.read_to_end(&mut bytes).unwrap();
+    assert_eq!(bytes, wem[wem.len() - 1000..]);
+    assert_eq!(reader.stream_position().unwrap(), wem.len() as u64);
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn test_parallel() {
+    // Decoding the blocks in parallel must give the same result as decoding them one by one
+    for bytes in [
+        &include_bytes!("./test.psarc")[..],
+        &include_bytes!("./test2.psarc")[..],
+    ] {
+        let psarc = PlaystationArchive::parse(bytes).unwrap();
+        (0..psarc.len()).for_each(|index| {
+            let mut streamed = Vec::new();
+            psarc
+                .open_entry(index)
+                .unwrap()
+                .read_to_end(&mut streamed)
+                .unwrap();
+            assert_eq!(psarc.read_file(index).unwrap(), streamed);
+        });
+    }
+}
+
+#[test]
+fn test_lookup() {
+    let psarc = PlaystationArchive::parse(include_bytes!("./test.psarc")).unwrap();
+
+    let index = psarc.index_for_path("audio/mac/1499529296.wem").unwrap();
+    assert_eq!(
+        psarc.index_for_path_ending_with("1499529296.wem"),
+        Some(index)
+    );
+    assert_eq!(
+        psarc.index_for_path_ending_with("mac/1499529296.wem"),
+        Some(index)
+    );
+    assert_eq!(psarc.indices_for_filename("1499529296.wem"), &[index]);
+    assert_eq!(
+        psarc.index_for_name_digest(psarc::name_digest("audio/mac/1499529296.wem")),
+        Some(index)
+    );
+
+    // Only whole path components should match
+    assert_eq!(psarc.index_for_path_ending_with("9529296.wem"), None);
+}
+
+#[test]
+fn test_metadata() {
+    let psarc = PlaystationArchive::parse(include_bytes!("./test2.psarc")).unwrap();
+    assert_eq!(psarc.version(), &psarc::Version::new(1, 4, 0));
+    assert_eq!(psarc.compression_type(), CompressionType::Zlib);
+    assert_eq!(psarc.block_size(), BlockSize::U16);
+    assert_eq!(psarc.archive_flags(), ArchiveFlags::empty());
+    assert_eq!(psarc.metadata_iter().count(), psarc.len());
+
+    let index = psarc.index_for_path("audio/windows/123456789.wem").unwrap();
+    let metadata = psarc.metadata(index).unwrap();
+    assert_eq!(metadata.path(), "audio/windows/123456789.wem");
+    assert_eq!(
+        metadata.name_digest(),
+        psarc::name_digest("audio/windows/123456789.wem")
+    );
+    assert_eq!(metadata.length(), 3 * 65536 + 1234);
+    assert_eq!(metadata.block_count(), 4);
+    assert!(metadata.stored_length() <= metadata.length());
+    assert!(metadata.compression_ratio() <= 1.0);
+}
+
+#[test]
+fn test_verify() {
+    let psarc = PlaystationArchive::parse(include_bytes!("./test.psarc")).unwrap();
+    assert!(psarc.verify().is_empty());
+
+    // Store everything raw so the manifest can be changed
+    let bytes = PlaystationArchiveBuilder::new()
+        .compression_type(CompressionType::None)
+        .add_file("a/first.txt", b"first".to_vec())
+        .unwrap()
+        .add_file("b/second.txt", b"second".to_vec())
+        .unwrap()
+        .build()
+        .unwrap();
+    assert!(PlaystationArchive::parse(&bytes)
+        .unwrap()
+        .verify()
+        .is_empty());
+
+    // Split the second path into two lines
+    let mut split = bytes.clone();
+    let slash = split.windows(8).position(|w| w == b"b/second").unwrap() + 1;
+    split[slash] = b'\n';
+    let broken = PlaystationArchive::parse(&split).unwrap();
+    let errors = broken.verify();
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(
+        &errors[0],
+        IntegrityError::DigestMismatch { path, .. } if path == "b"
+    ));
+    assert!(matches!(
+        &errors[1],
+        IntegrityError::MissingEntry(path) if path == "second.txt"
+    ));
+
+    // Cut off the data of the last file
+    let truncated = PlaystationArchive::parse(&bytes[..bytes.len() - 2]).unwrap();
+    assert!(matches!(
+        truncated.verify().last().unwrap(),
+        IntegrityError::BlockOutOfBounds { block: 0, .. }
+    ));
+}
+
+#[test]
+fn test_flags() {
+    let psarc = PlaystationArchive::parse(include_bytes!("./test.psarc")).unwrap();
+
+    // Absolute, case insensitive and encrypted at the same time
+    let bytes = PlaystationArchiveBuilder::from_archive(&psarc)
+        .unwrap()
+        .absolute_paths(true)
+        .ignore_case(true)
+        .encrypt_toc(true)
+        .build()
+        .unwrap();
+    let combined = PlaystationArchive::parse(&bytes).unwrap();
+    assert_eq!(combined.paths(), psarc.paths());
+
+    assert_eq!(
+        combined.index_for_path("/AUDIO/MAC/1499529296.WEM"),
+        psarc.index_for_path("audio/mac/1499529296.wem")
+    );
+    assert_eq!(
+        combined
+            .read_file_with_path("Audio/Mac/1499529296.wem")
+            .unwrap(),
+        psarc
+            .read_file_with_path("audio/mac/1499529296.wem")
+            .unwrap()
+    );
+    assert!(combined.verify().is_empty());
+
+    // Archives ignoring case hash the path with the case folded
+    let mut builder = PlaystationArchiveBuilder::new().ignore_case(true);
+    builder
+        .add_file("Songs/Song.xml", b"song".to_vec())
+        .unwrap();
+    let ignore_case = PlaystationArchive::parse(&builder.build().unwrap()).unwrap();
+    assert_eq!(
+        ignore_case.index_for_name_digest(psarc::name_digest("songs/song.xml")),
+        ignore_case.index_for_path("Songs/Song.xml")
+    );
+    assert!(ignore_case.verify().is_empty());
+}
+
+#[test]
+fn test_roundtrip() {
+    let psarc = PlaystationArchive::parse(include_bytes!("./test.psarc")).unwrap();
+
+    // Repack the archive and parse it again
+    let bytes = PlaystationArchiveBuilder::from_archive(&psarc)
+        .unwrap()
+        .build()
+        .unwrap();
+    let rebuilt = PlaystationArchive::parse(&bytes).unwrap();
+    assert_eq!(rebuilt.paths(), psarc.paths());
+
+    (0..psarc.len()).for_each(|index| {
+        assert_eq!(
+            rebuilt.read_file(index).unwrap(),
+            psarc.read_file(index).unwrap()
+        );
+    });
+}
+
+#[test]
+fn test_lzma() {
+    let psarc = PlaystationArchive::parse(include_bytes!("./test.psarc")).unwrap();
+
+    // Repack the archive with LZMA compressed blocks
+    let bytes = PlaystationArchiveBuilder::from_archive(&psarc)
+        .unwrap()
+        .compression_type(CompressionType::Lzma)
+        .build()
+        .unwrap();
+    let lzma = PlaystationArchive::parse(&bytes).unwrap();
+    assert_eq!(lzma.paths(), psarc.paths());
+
+    (0..psarc.len()).for_each(|index| {
+        assert_eq!(
+            lzma.read_file(index).unwrap(),
+            psarc.read_file(index).unwrap()
+        );
+    });
+}
+
+#[test]
+fn test_lzma_fixture() {
+    // Written and compressed without this crate, see `generate_fixtures.py`
+    let psarc = PlaystationArchive::parse(include_bytes!("./test_lzma.psarc")).unwrap();
+    assert_eq!(psarc.compression_type(), CompressionType::Lzma);
+    assert!(psarc.verify().is_empty());
+
+    psarc
+        .paths_iter()
+        .enumerate()
+        .skip(1)
+        .for_each(|(index, path)| {
+            let length = psarc.metadata(index).unwrap().length() as usize;
+            assert_eq!(
+                psarc.read_file(index).unwrap(),
+                fixture_contents(path, length)
+            );
+        });
+}
+
 #[test]
 fn test2() {
+    // Written without this crate, see `generate_fixtures.py`
     let psarc = PlaystationArchive::parse(include_bytes!("./test2.psarc")).unwrap();
     assert_eq!(psarc.len(), 25);
+    assert!(psarc.verify().is_empty());
 
-    // Try parsing all files
-    psarc.paths_iter().enumerate().for_each(|(_index, _)| {
-        // TODO: fix
-        //psarc.read_file(index).unwrap();
-    });
+    // The blocks use every zlib header and raw blocks, including full-size ones
+    psarc
+        .paths_iter()
+        .enumerate()
+        .skip(1)
+        .for_each(|(index, path)| {
+            let length = psarc.metadata(index).unwrap().length() as usize;
+            assert_eq!(
+                psarc.read_file(index).unwrap(),
+                fixture_contents(path, length)
+            );
+        });
+
+    let wem = psarc
+        .read_file_with_path("audio/windows/123456789.wem")
+        .unwrap();
+    assert_eq!(wem.len(), 3 * 65536 + 1234);
+}
+
+#[test]
+fn test_overlay() {
+    let archive = |files: &[(&str, &str)]| {
+        let mut builder = PlaystationArchiveBuilder::new();
+        for (path, contents) in files {
+            builder
+                .add_file(path, contents.as_bytes().to_vec())
+                .unwrap();
+        }
+
+        PlaystationArchive::parse(&builder.build().unwrap()).unwrap()
+    };
+
+    let mut overlay = ArchiveOverlay::new();
+    overlay
+        .add_archive(
+            archive(&[
+                ("gfxassets/album_art/base.dds", "base"),
+                ("songs/arr/song.xml", "base"),
+            ]),
+            0,
+        )
+        .add_archive(
+            archive(&[
+                ("gfxassets/album_art/dlc.dds", "dlc"),
+                ("songs/arr/song.xml", "dlc"),
+            ]),
+            10,
+        );
+    assert_eq!(overlay.len(), 2);
+
+    // The highest priority archive wins
+    assert_eq!(
+        overlay.read_file_with_path("songs/arr/song.xml").unwrap(),
+        b"dlc"
+    );
+    assert_eq!(
+        overlay
+            .read_urn_file_as_string("urn:image:dds:base", "dds")
+            .unwrap(),
+        "base"
+    );
+    assert!(overlay.read_file_with_path("missing").is_err());
+
+    // Shadowed files are only listed once
+    assert_eq!(overlay.paths().len(), 4);
+
+    assert_eq!(
+        overlay.read_directory("gfxassets/album_art/"),
+        [
+            "gfxassets/album_art/base.dds",
+            "gfxassets/album_art/dlc.dds"
+        ]
+    );
+    assert_eq!(
+        overlay.read_directory(""),
+        ["gfxassets/", "manifest.txt", "songs/"]
+    );
+    assert!(overlay.is_directory("songs/arr"));
+
+    assert_eq!(overlay.glob("**/*.xml"), ["songs/arr/song.xml"]);
+    assert_eq!(
+        overlay.glob("gfxassets/*/d*.dds"),
+        ["gfxassets/album_art/dlc.dds"]
+    );
+
+    // An equal priority shadows the previous archives
+    overlay.add_archive(archive(&[("songs/arr/song.xml", "patch")]), 10);
+    assert_eq!(
+        overlay.read_file_with_path("songs/arr/song.xml").unwrap(),
+        b"patch"
+    );
+
+    // Paths differing only in case are shadowed by an archive ignoring case
+    let mut builder = PlaystationArchiveBuilder::new().ignore_case(true);
+    builder
+        .add_file("Songs/Arr/Song.xml", b"dlc".to_vec())
+        .unwrap();
+    let mut overlay = ArchiveOverlay::new();
+    overlay
+        .add_archive(archive(&[("songs/arr/song.xml", "base")]), 0)
+        .add_archive(
+            PlaystationArchive::parse(&builder.build().unwrap()).unwrap(),
+            10,
+        );
+
+    let xml_paths = overlay
+        .paths()
+        .into_iter()
+        .filter(|path| path.ends_with(".xml"))
+        .collect::<Vec<_>>();
+    assert_eq!(xml_paths, ["Songs/Arr/Song.xml"]);
+    assert_eq!(
+        overlay.glob("songs/*/song.xml"),
+        ["Songs/Arr/Song.xml", "songs/arr/song.xml"]
+    );
+}
+
+#[test]
+fn test_patch() {
+    let bytes = include_bytes!("./test.psarc");
+    let psarc = PlaystationArchive::parse(bytes).unwrap();
+
+    let mut patch = psarc.patch();
+    patch
+        .replace_file(
+            "manifests/songs_dlc_butitrainedsong/songs_dlc_butitrainedsong.hsan",
+            b"replaced".to_vec(),
+        )
+        .unwrap()
+        .add_file("new/file.txt", b"new".repeat(100000))
+        .unwrap()
+        .remove_file("audio/mac/song_butitrainedsong_preview.bnk")
+        .unwrap();
+    assert!(matches!(
+        patch.remove_file("missing"),
+        Err(ArchiveReadError::PathNotFound { .. })
+    ));
+    assert!(matches!(
+        patch.add_file("new/file.txt", Vec::new()),
+        Err(ArchiveReadError::DuplicatePath(_))
+    ));
+    assert!(matches!(
+        patch.add_file("///", Vec::new()),
+        Err(ArchiveReadError::InvalidPath(_))
+    ));
+
+    let patched_bytes = patch.build().unwrap();
+    let patched = PlaystationArchive::parse(&patched_bytes).unwrap();
+    assert!(patched.verify().is_empty());
+    assert_eq!(patched.archive_flags(), psarc.archive_flags());
+    assert_eq!(patched.len(), psarc.len());
+
+    assert_eq!(
+        patched
+            .read_file_with_path(
+                "manifests/songs_dlc_butitrainedsong/songs_dlc_butitrainedsong.hsan"
+            )
+            .unwrap(),
+        b"replaced"
+    );
+    assert_eq!(
+        patched.read_file_with_path("new/file.txt").unwrap(),
+        b"new".repeat(100000)
+    );
+    assert!(patched
+        .index_for_path("audio/mac/song_butitrainedsong_preview.bnk")
+        .is_none());
+
+    // Untouched files are copied as is
+    let wem = "audio/mac/1499529296.wem";
+    assert_eq!(
+        patched.read_file_with_path(wem).unwrap(),
+        psarc.read_file_with_path(wem).unwrap()
+    );
+    let stored_length = |archive: &PlaystationArchive| {
+        archive
+            .metadata(archive.index_for_path(wem).unwrap())
+            .unwrap()
+            .stored_length()
+    };
+    assert_eq!(stored_length(&patched), stored_length(&psarc));
+}
+
+/// Small encrypted archive with files spanning multiple blocks.
+fn fuzz_archive() -> Vec<u8> {
+    let mut builder = PlaystationArchiveBuilder::new()
+        .block_size(BlockSize::U16)
+        .encrypt_toc(true);
+    builder
+        .add_file("a.txt", b"abcdefgh".repeat(20000))
+        .unwrap()
+        .add_file(
+            "b/c.bin",
+            (0..100000u32).map(|v| (v * 7 % 251) as u8).collect(),
+        )
+        .unwrap()
+        .add_file("empty", Vec::new())
+        .unwrap();
+
+    builder.build().unwrap()
+}
+
+/// Read everything from an archive that might be malformed.
+fn read_everything(bytes: &[u8]) {
+    if let Ok(psarc) = PlaystationArchive::parse(bytes) {
+        for index in 0..psarc.len() {
+            let _ = psarc.read_file(index);
+
+            if let Ok(mut reader) = psarc.open_entry(index) {
+                let _ = reader.read_to_end(&mut Vec::new());
+            }
+        }
+
+        let _ = psarc.verify();
+        let _ = psarc.metadata_iter().count();
+    }
+}
+
+proptest! {
+    #[test]
+    fn truncated_doesnt_panic(length in any::<prop::sample::Index>()) {
+        let bytes = fuzz_archive();
+
+        read_everything(&bytes[..length.index(bytes.len())]);
+    }
+
+    #[test]
+    fn bit_flipped_doesnt_panic(bit in any::<prop::sample::Index>()) {
+        let mut bytes = fuzz_archive();
+        let bit = bit.index(bytes.len() * 8);
+        bytes[bit / 8] ^= 1 << (bit % 8);
+
+        read_everything(&bytes);
+    }
+
+    #[test]
+    fn truncated_header_doesnt_panic(length in 0..4096usize) {
+        read_everything(&include_bytes!("./test.psarc")[..length]);
+    }
+}
+
+/// Contents of a file in the generated fixtures, the same as in `generate_fixtures.py`.
+fn fixture_contents(path: &str, length: usize) -> Vec<u8> {
+    const TEXT_EXTENSIONS: [&str; 8] = [
+        "txt", "xml", "json", "hsan", "version", "appid", "flat", "xblock",
+    ];
+
+    if TEXT_EXTENSIONS.contains(&path.rsplit('.').next().unwrap_or_default()) {
+        let mut text = String::new();
+        let mut line = 0;
+        while text.len() < length {
+            text.push_str(&format!("{path} line {line}\n"));
+            line += 1;
+        }
+
+        return text.as_bytes()[..length].to_vec();
+    }
+
+    // Incompressible bytes from a xorshift32 generator seeded with the length
+    let mut state = length as u32;
+    (0..length)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+
+            state as u8
+        })
+        .collect()
 }
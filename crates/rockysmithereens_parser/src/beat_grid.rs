@@ -0,0 +1,137 @@
+use crate::song::Beat;
+
+/// Tempo map of a song, built from the beats.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BeatGrid {
+    /// Every beat with its position, sorted by time.
+    beats: Vec<GridBeat>,
+}
+
+impl BeatGrid {
+    /// Build the grid, beats without a measure number continue the previous measure.
+    pub fn new(beats: &[Beat]) -> Self {
+        let mut measure = 0;
+        let mut beat_in_measure = 0;
+
+        let beats = beats
+            .iter()
+            .map(|beat| {
+                match beat.measure {
+                    Some(number) => {
+                        measure = number;
+                        beat_in_measure = 0;
+                    }
+                    None => beat_in_measure += 1,
+                }
+
+                GridBeat {
+                    time: beat.time,
+                    position: Position {
+                        measure,
+                        beat: beat_in_measure,
+                        fraction: 0.0,
+                    },
+                }
+            })
+            .collect();
+
+        Self { beats }
+    }
+
+    /// All beats.
+    pub fn beats(&self) -> &[GridBeat] {
+        &self.beats
+    }
+
+    /// The first beat of every measure.
+    pub fn downbeats(&self) -> impl Iterator<Item = &GridBeat> {
+        self.beats.iter().filter(|beat| beat.is_downbeat())
+    }
+
+    /// Index of the beat playing at the time, `None` before the first beat.
+    pub fn beat_index_at(&self, time: f32) -> Option<usize> {
+        self.beats
+            .partition_point(|beat| beat.time <= time)
+            .checked_sub(1)
+    }
+
+    /// Length of a beat in seconds, the last beat is as long as the one before it.
+    pub fn beat_length(&self, index: usize) -> Option<f32> {
+        match (self.beats.get(index), self.beats.get(index + 1)) {
+            (Some(beat), Some(next)) => Some(next.time - beat.time),
+            (Some(_), None) => index
+                .checked_sub(1)
+                .and_then(|index| self.beat_length(index)),
+            _ => None,
+        }
+    }
+
+    /// Local tempo in beats per minute at the time.
+    pub fn bpm_at(&self, time: f32) -> Option<f32> {
+        self.beat_index_at(time)
+            .and_then(|index| self.beat_length(index))
+            .map(|length| 60.0 / length)
+    }
+
+    /// Average tempo in beats per minute over the whole song.
+    pub fn average_bpm(&self) -> Option<f32> {
+        match (self.beats.first(), self.beats.last()) {
+            (Some(first), Some(last)) if last.time > first.time => {
+                Some(60.0 * (self.beats.len() - 1) as f32 / (last.time - first.time))
+            }
+            _ => None,
+        }
+    }
+
+    /// Musical position of the time, `None` outside of the grid.
+    pub fn position_at(&self, time: f32) -> Option<Position> {
+        let index = self.beat_index_at(time)?;
+        let beat = &self.beats[index];
+
+        let fraction = (time - beat.time) / self.beat_length(index)?;
+        if !(0.0..1.0).contains(&fraction) {
+            return None;
+        }
+
+        Some(Position {
+            fraction,
+            ..beat.position
+        })
+    }
+
+    /// Time in seconds of a musical position, `None` when the beat doesn't exist.
+    pub fn time_at(&self, position: Position) -> Option<f32> {
+        let index = self.beats.iter().position(|beat| {
+            beat.position.measure == position.measure && beat.position.beat == position.beat
+        })?;
+
+        Some(self.beats[index].time + position.fraction * self.beat_length(index)?)
+    }
+}
+
+/// Single beat of the grid.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GridBeat {
+    /// Time in seconds.
+    pub time: f32,
+    /// Position of the beat, the fraction is always zero.
+    pub position: Position,
+}
+
+impl GridBeat {
+    /// Whether this is the first beat of a measure.
+    pub fn is_downbeat(&self) -> bool {
+        self.position.beat == 0
+    }
+}
+
+/// Musical position as bar, beat and fraction.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Position {
+    /// Measure number.
+    pub measure: u16,
+    /// Beat in the measure, `0` is the downbeat.
+    pub beat: u16,
+    /// How far it is towards the next beat, from `0.0` up to `1.0`.
+    pub fraction: f32,
+}
@@ -1,6 +1,7 @@
 use std::fmt::Debug;
 
 use bnk::BnkError;
+use nom_error::ParseError;
 use psarc::ArchiveReadError;
 use quick_xml::de::DeError;
 use rodio_wem::WemError;
@@ -28,4 +29,20 @@ pub enum RocksmithArchiveError {
     InvalidUrnPath(String),
     #[error("level with difficulty {0} not found")]
     NoLevelWithDifficulty(u8),
+    #[error("io error")]
+    Io(#[from] std::io::Error),
+    #[error("corrupt sng file, reason: {0}")]
+    CorruptSng(String),
+    /// The offset is relative to the start of the parsed bytes.
+    #[error("parsing {context} failed at byte {offset}")]
+    Parse { context: String, offset: usize },
+}
+
+impl From<ParseError> for RocksmithArchiveError {
+    fn from(err: ParseError) -> Self {
+        Self::Parse {
+            context: err.context,
+            offset: err.offset,
+        }
+    }
 }
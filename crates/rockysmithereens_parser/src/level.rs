@@ -1,4 +1,9 @@
-use crate::{note::Note, song_xml::XmlLevel};
+use crate::{
+    note::{self, Note},
+    sng::{self, Arrangement, Sng},
+    song::Song,
+    song_xml::{XmlAnchor, XmlHandShape, XmlLevel},
+};
 
 /// Information about the level of a song.
 #[derive(Debug, Default, Clone)]
@@ -7,6 +12,10 @@ pub struct Level {
     pub notes: Vec<Note>,
     /// The difficulty of this level.
     pub difficulty: u8,
+    /// Fret hand positions.
+    pub anchors: Vec<Anchor>,
+    /// Time ranges a chord or arpeggio shape is held.
+    pub hand_shapes: Vec<HandShape>,
 }
 
 impl Level {
@@ -25,11 +34,95 @@ impl Level {
     pub fn notes_iter(&self) -> impl Iterator<Item = &Note> {
         self.notes.iter()
     }
+
+    /// Convert a level from a binary arrangement.
+    pub(crate) fn from_sng(arrangement: &Arrangement, sng: &Sng) -> Self {
+        let notes = arrangement
+            .notes
+            .iter()
+            .flat_map(|note| Note::from_sng(note, sng))
+            .collect();
+
+        let anchors = arrangement.anchors.iter().map(Anchor::from).collect();
+
+        // Arpeggios are stored separately
+        let mut hand_shapes = arrangement
+            .hand_shapes
+            .iter()
+            .chain(arrangement.arpeggios.iter())
+            .map(HandShape::from)
+            .collect::<Vec<_>>();
+        hand_shapes.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+        Level {
+            notes,
+            difficulty: arrangement.difficulty as u8,
+            anchors,
+            hand_shapes,
+        }
+    }
+
+    /// Convert a level to a binary arrangement, chord templates are added to the SNG.
+    pub(crate) fn to_sng(&self, song: &Song, sng: &mut Sng) -> Arrangement {
+        let anchors = self
+            .anchors
+            .iter()
+            .enumerate()
+            .map(|(index, anchor)| sng::Anchor {
+                start_time: anchor.time,
+                end_time: self
+                    .anchors
+                    .get(index + 1)
+                    .map_or(song.song_length, |next| next.time),
+                fret: anchor.fret as i8,
+                width: anchor.width as i32,
+                phrase_iteration: song.phrase_iteration_at(anchor.time),
+                ..Default::default()
+            })
+            .collect();
+
+        let (arpeggios, hand_shapes) = self
+            .hand_shapes
+            .iter()
+            .map(|hand_shape| {
+                let is_arpeggio = song
+                    .chord_templates
+                    .get(hand_shape.chord)
+                    .is_some_and(|template| template.is_arpeggio());
+
+                (
+                    is_arpeggio,
+                    sng::HandShape {
+                        chord: hand_shape.chord as i32,
+                        start_time: hand_shape.start_time,
+                        end_time: hand_shape.end_time,
+                        ..Default::default()
+                    },
+                )
+            })
+            .partition::<Vec<_>, _>(|(is_arpeggio, _)| *is_arpeggio);
+
+        Arrangement {
+            difficulty: self.difficulty as i32,
+            anchors,
+            hand_shapes: hand_shapes.into_iter().map(|(_, shape)| shape).collect(),
+            arpeggios: arpeggios.into_iter().map(|(_, shape)| shape).collect(),
+            notes: note::to_sng(&self.notes, sng),
+            ..Default::default()
+        }
+    }
 }
 
 impl From<XmlLevel> for Level {
     fn from(xml: XmlLevel) -> Self {
-        let difficulty = xml.difficulty;
+        let difficulty = xml.difficulty as u8;
+        let anchors = xml.anchors.anchors.iter().map(Anchor::from).collect();
+        let hand_shapes = xml
+            .hand_shapes
+            .hand_shapes
+            .iter()
+            .map(HandShape::from)
+            .collect();
 
         // Combine all regular with chord notes and convert them to our type
         let (regular_notes_iter, chords_iter) = xml.into_iters();
@@ -39,6 +132,73 @@ impl From<XmlLevel> for Level {
             .flatten()
             .collect();
 
-        Level { notes, difficulty }
+        Level {
+            notes,
+            difficulty,
+            anchors,
+            hand_shapes,
+        }
+    }
+}
+
+/// Position of the fret hand, the camera follows it.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Anchor {
+    /// Time in seconds.
+    pub time: f32,
+    /// Lowest fret of the hand.
+    pub fret: u8,
+    /// Amount of frets the hand spans.
+    pub width: f32,
+}
+
+impl From<&XmlAnchor> for Anchor {
+    fn from(xml: &XmlAnchor) -> Self {
+        Self {
+            time: xml.time,
+            fret: xml.fret,
+            width: xml.width,
+        }
+    }
+}
+
+impl From<&sng::Anchor> for Anchor {
+    fn from(anchor: &sng::Anchor) -> Self {
+        Self {
+            time: anchor.start_time,
+            fret: anchor.fret.max(0) as u8,
+            width: anchor.width as f32,
+        }
+    }
+}
+
+/// Time range a chord or arpeggio shape is held.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HandShape {
+    /// Index of the chord template.
+    pub chord: usize,
+    /// Start time in seconds.
+    pub start_time: f32,
+    /// End time in seconds.
+    pub end_time: f32,
+}
+
+impl From<&XmlHandShape> for HandShape {
+    fn from(xml: &XmlHandShape) -> Self {
+        Self {
+            chord: xml.chord_id,
+            start_time: xml.start_time,
+            end_time: xml.end_time,
+        }
+    }
+}
+
+impl From<&sng::HandShape> for HandShape {
+    fn from(hand_shape: &sng::HandShape) -> Self {
+        Self {
+            chord: hand_shape.chord as usize,
+            start_time: hand_shape.start_time,
+            end_time: hand_shape.end_time,
+        }
     }
 }
@@ -1,16 +1,28 @@
+pub mod beat_grid;
 mod error;
 pub mod level;
+pub mod lyrics;
 pub mod manifest;
 pub mod note;
+pub mod pitch;
+pub mod show_lights;
+pub mod sng;
 pub mod song;
-mod song_xml;
+pub mod song_xml;
+pub mod urn;
 pub mod xblock;
 
-use manifest::Manifest;
-use psarc::{ArchiveReadError, PlaystationArchive};
+use std::io::{Read, Seek};
+
+use lyrics::Lyrics;
+use manifest::{Manifest, SongKey};
+use psarc::{ArchiveOverlay, EntryReader, PlaystationArchive};
 use rodio_wem::WemDecoder;
+use show_lights::ShowLights;
+use sng::{Platform, Sng};
 use song::Song;
-use song_xml::XmlSong;
+use song_xml::{XmlSong, XmlVocals};
+use urn::Urn;
 
 use crate::{
     error::{Result, RocksmithArchiveError},
@@ -20,76 +32,106 @@ use crate::{
 /// Parsed Rockmith 2014 .psarc file.
 #[derive(Debug)]
 pub struct SongFile {
-    pub entities: Vec<SimplifiedEntity>,
-    pub manifests: Vec<Manifest>,
-    /// Archive containing all the files.
-    pub archive: PlaystationArchive,
-    /// The path to the song file.
-    song_path: String,
+    /// Every song in the archive, song packs contain more than one.
+    pub songs: Vec<ArchiveSong>,
+    /// Archives containing all the files.
+    pub archive: ArchiveOverlay,
 }
 
 impl SongFile {
     /// Parse the Rocksmith archive file.
     pub fn parse(file: &[u8]) -> Result<Self> {
-        // Parse the playstation archive file
-        let archive = PlaystationArchive::parse(file)?;
-
-        // Get the xblock file
-        let xblock_indices = archive
-            .enumerated_file_paths_by_extension_iter(".xblock")
-            .map(|(i, _)| i)
-            .collect::<Vec<_>>();
-        if xblock_indices.is_empty() {
+        Self::from_archive(PlaystationArchive::parse(file)?)
+    }
+
+    /// Parse the Rocksmith archive from a seekable source, such as a file handle.
+    ///
+    /// Files from the archive are only read when they are needed.
+    pub fn from_reader<R>(reader: R) -> Result<Self>
+    where
+        R: Read + Seek + Send + 'static,
+    {
+        Self::from_archive(PlaystationArchive::from_reader(reader)?)
+    }
+
+    /// Parse the Rocksmith specific files from an already opened playstation archive.
+    pub fn from_archive(archive: PlaystationArchive) -> Result<Self> {
+        Self::from_overlay(ArchiveOverlay::from(archive))
+    }
+
+    /// Parse the Rocksmith specific files from multiple layered archives.
+    ///
+    /// Files referenced by the song can be in any of the archives, such as the base game.
+    pub fn from_overlay(archive: ArchiveOverlay) -> Result<Self> {
+        // Get the xblock files
+        let xblock_paths = archive.glob("**/*.xblock");
+        if xblock_paths.is_empty() {
             return Err(RocksmithArchiveError::NotARocksmitheFile);
         }
 
-        // TODO: handle multiple block files
-        let xblock = Xblock::parse(&archive.read_file_as_string(xblock_indices[0])?)?;
+        // Group the entities of all blocks by the song they play
+        let mut groups: Vec<(Urn, Vec<EntityManifest>)> = Vec::new();
+        let mut without_sound_bank = Vec::new();
+        for path in xblock_paths {
+            let xblock_entry = archive
+                .resolve(&path)
+                .ok_or(RocksmithArchiveError::NotARocksmitheFile)?;
+            let xblock = Xblock::parse(&xblock_entry.read_as_string()?)?;
+
+            for entity in xblock.simplified_entities_iter() {
+                // TODO: place this in a more logical place, with async loading
+                let manifest = entity_manifest(&archive, &entity)?;
 
-        // Get the required song properties
-        let entities = xblock.simplified_entities_iter().collect::<Vec<_>>();
-        if entities.is_empty() {
+                let sound_bank = match &entity.sound_bank {
+                    Some(sound_bank) => sound_bank.clone(),
+                    None => {
+                        without_sound_bank.push((entity, manifest));
+                        continue;
+                    }
+                };
+
+                match groups
+                    .iter_mut()
+                    .find(|(other_sound_bank, _)| *other_sound_bank == sound_bank)
+                {
+                    Some((_, entities)) => entities.push((entity, manifest)),
+                    None => groups.push((sound_bank, vec![(entity, manifest)])),
+                }
+            }
+        }
+        if groups.is_empty() {
             return Err(RocksmithArchiveError::MissingData(
-                "xblock entities".to_string(),
+                "xblock entities with a sound bank".to_string(),
             ));
         }
 
-        // TODO: place this in a more logical place, with async loading
-        let manifests = entities
-            .iter()
-            .filter_map(|entity| {
-                entity
-                    .manifest
-                    .as_ref()
-                    .map(|manifest_path| Manifest::parse(&archive, manifest_path))
-            })
-            .collect::<Result<Vec<_>>>()?;
-
-        // Get the song bank
-        let bnk_bytes = read_urn_file(
-            &archive,
-            &entities[0]
-                .sound_bank
-                .as_ref()
-                .ok_or_else(|| RocksmithArchiveError::MissingData("bnk file".to_string()))?,
-            "bnk",
-        )?;
+        // Entities without a sound bank belong to the song with the same key, otherwise they are
+        // skipped because there's no music to play them with
+        for (entity, manifest) in without_sound_bank {
+            let group = song_key(&manifest).and_then(|key| {
+                groups.iter_mut().find(|(_, entities)| {
+                    entities
+                        .iter()
+                        .any(|(_, other_manifest)| song_key(other_manifest) == Some(key))
+                })
+            });
 
-        // Get the wem filename from the bnk file
-        let wem_filenames = bnk::wem_filenames(&bnk_bytes)?;
-        if wem_filenames.is_empty() {
-            return Err(RocksmithArchiveError::MissingData("bnk".to_string()));
+            match group {
+                Some((_, entities)) => entities.push((entity, manifest)),
+                None => log::warn!(
+                    "skipping entity '{}' without a sound bank, no song has key {:?}",
+                    entity.name,
+                    song_key(&manifest).map(SongKey::as_str)
+                ),
+            }
         }
 
-        // Construct the full path
-        let song_path = archive.try_path_ending_with(&wem_filenames[0])?.to_string();
+        let songs = groups
+            .into_iter()
+            .map(|(_, entities)| ArchiveSong::new(&archive, entities))
+            .collect::<Result<Vec<_>>>()?;
 
-        Ok(Self {
-            manifests,
-            entities,
-            archive,
-            song_path,
-        })
+        Ok(Self { songs, archive })
     }
 
     /// Read a file from the archive.
@@ -97,76 +139,222 @@ impl SongFile {
         Ok(self.archive.read_file_with_path(path)?)
     }
 
-    /// Get the bytes from the music embedded with the chosen song.
-    pub fn wem(&self) -> Result<Vec<u8>> {
-        Ok(self.archive.read_file_with_path(self.song_path())?)
+    /// Get the bytes from the music embedded with the song.
+    pub fn wem(&self, song: &ArchiveSong) -> Result<Vec<u8>> {
+        Ok(self.archive.read_file_with_path(song.song_path())?)
     }
 
-    /// Get the bytes from the music embedded with the chosen song and recode it to a proper vorbis
-    /// decoder.
-    pub fn music_decoder(&self) -> Result<WemDecoder> {
-        Ok(WemDecoder::new(&self.wem()?)?)
+    /// Stream the music embedded with the song.
+    pub fn wem_reader(&self, song: &ArchiveSong) -> Result<EntryReader> {
+        Ok(self.archive.open_entry_with_path(song.song_path())?)
     }
 
-    /// Path for the album art file.
-    pub fn album_art_path(&self) -> Option<&str> {
-        self.archive
-            .path_ending_with("256.dds")
-            .or_else(|| self.archive.path_ending_with("128.dds"))
-            .or_else(|| self.archive.path_ending_with("64.dds"))
+    /// Stream the music embedded with the song and recode it to a proper vorbis decoder.
+    ///
+    /// The music is read from the archive while it's decoded.
+    pub fn music_decoder(&self, song: &ArchiveSong) -> Result<WemDecoder<EntryReader>> {
+        Ok(WemDecoder::from_reader(self.wem_reader(song)?)?)
     }
 
-    /// Path for the vorbis wem file.
-    pub fn song_path(&self) -> &str {
-        &self.song_path
+    /// Stream the short preview of the song, if it has one.
+    pub fn preview_decoder(&self, song: &ArchiveSong) -> Option<Result<WemDecoder<EntryReader>>> {
+        song.preview_path().map(|path| {
+            Ok(WemDecoder::from_reader(
+                self.archive.open_entry_with_path(path)?,
+            )?)
+        })
     }
 
-    /// Get the parsed song information for a section.
-    pub fn parse_song_info(&self, section_index: usize) -> Result<Song> {
-        let asset = &self.entities[section_index]
+    /// Get the parsed song information for a section of the song.
+    pub fn parse_song_info(&self, song: &ArchiveSong, section_index: usize) -> Result<Song> {
+        let asset = song.entities[section_index]
             .sng_asset
             .as_ref()
+            .map(Urn::as_str)
             .ok_or_else(|| RocksmithArchiveError::MissingData("sng file".to_string()))?;
 
+        // Prefer the binary arrangement, official songs don't ship the XML
+        match self.parse_sng(asset) {
+            Some(Ok(sng)) => return Ok(Song::from(sng)),
+            // Only report the error when there's nothing to fall back to
+            Some(Err(err)) if self.archive.resolve_urn(asset, "xml").is_none() => return Err(err),
+            _ => (),
+        }
+
         // Get the song XML
-        let xml_string = read_urn_file_string(&self.archive, asset, "xml")?;
+        let xml_string = self.archive.read_urn_file_as_string(asset, "xml")?;
 
         let xml = XmlSong::parse(&xml_string)?;
 
         Ok(Song::from(xml))
     }
-}
 
-/// Read a file as bytes from an urn file.
-fn read_urn_file(archive: &PlaystationArchive, urn: &str, extension: &str) -> Result<Vec<u8>> {
-    let urn_filename = urn_filename(urn)?;
+    /// Get the stage lighting of the song, all sections share it.
+    pub fn parse_show_lights(&self, song: &ArchiveSong) -> Result<ShowLights> {
+        let asset = song
+            .entities
+            .iter()
+            .find_map(|entity| entity.show_lights_xml_asset.as_ref())
+            .map(Urn::as_str)
+            .ok_or_else(|| RocksmithArchiveError::MissingData("showlights file".to_string()))?;
+
+        ShowLights::parse(&self.archive.read_urn_file_as_string(asset, "xml")?)
+    }
+
+    /// Get the lyrics of a vocals section of the song.
+    pub fn parse_lyrics(&self, song: &ArchiveSong, section_index: usize) -> Result<Lyrics> {
+        let entity = &song.entities[section_index];
+        let asset = entity
+            .sng_asset
+            .as_ref()
+            .map(Urn::as_str)
+            .ok_or_else(|| RocksmithArchiveError::MissingData("sng file".to_string()))?;
+
+        let mut lyrics = match self.parse_sng(asset) {
+            Some(Ok(sng)) => Lyrics::from(&sng),
+            Some(Err(err)) if self.archive.resolve_urn(asset, "xml").is_none() => return Err(err),
+            _ => Lyrics::from(XmlVocals::parse(
+                &self.archive.read_urn_file_as_string(asset, "xml")?,
+            )?),
+        };
+
+        // The texture path in the arrangement points to the game files, use the one in the archive so
+        // it can be loaded from there
+        if let Some(font) = &mut lyrics.font {
+            if let Some(entry) = entity
+                .lyric_art
+                .as_ref()
+                .and_then(|urn| self.archive.resolve_urn(urn.as_str(), "dds"))
+            {
+                font.texture = entry.path().to_string();
+            }
+        }
+
+        Ok(lyrics)
+    }
 
-    // Get the path of the file
-    let archive_path = archive.try_path_ending_with(&format!("{}.{}", urn_filename, extension))?;
+    /// Read and parse the binary arrangement of an urn, if it exists.
+    pub fn parse_sng(&self, urn: &str) -> Option<Result<Sng>> {
+        let entry = self.archive.resolve_urn(urn, "sng")?;
 
-    Ok(archive.read_file_with_path(archive_path)?)
+        Some(
+            entry
+                .read()
+                .map_err(RocksmithArchiveError::from)
+                .and_then(|bytes| Sng::parse(&bytes, Platform::from_path(entry.path()))),
+        )
+    }
 }
 
-/// Read a file as a string from an urn file.
-fn read_urn_file_string(
-    archive: &PlaystationArchive,
-    urn: &str,
-    extension: &str,
-) -> Result<String> {
-    let urn_filename = urn_filename(urn)?;
+/// Entity with its manifest, if it has one.
+type EntityManifest = (SimplifiedEntity, Option<Manifest>);
+
+/// Parse the manifest of an entity, if it has one.
+fn entity_manifest(
+    archive: &ArchiveOverlay,
+    entity: &SimplifiedEntity,
+) -> Result<Option<Manifest>> {
+    entity
+        .manifest
+        .as_ref()
+        .map(|urn| {
+            let mut manifest = Manifest::parse(archive, urn.as_str())?;
+            manifest.id = entity.id.clone();
 
-    // Get the path of the file
-    let archive_path = archive.try_path_ending_with(&format!("{}.{}", urn_filename, extension))?;
+            Ok(manifest)
+        })
+        .transpose()
+}
 
-    // TODO: clean up archive api
-    let index = archive.index_for_path(archive_path).unwrap();
+/// Key of the song an entity belongs to, read from its manifest.
+fn song_key(manifest: &Option<Manifest>) -> Option<&SongKey> {
+    manifest
+        .as_ref()
+        .and_then(Manifest::attributes)
+        .and_then(|attributes| attributes.song_key.as_ref())
+}
 
-    Ok(archive.read_file_as_string(index)?)
+/// Single song in the archive with all its arrangements.
+#[derive(Debug)]
+pub struct ArchiveSong {
+    /// Arrangements and other parts of the song.
+    pub entities: Vec<SimplifiedEntity>,
+    /// Manifest of every entity that has one.
+    pub manifests: Vec<Manifest>,
+    /// The path to the song file.
+    song_path: String,
+    /// The path to the short preview of the song.
+    preview_path: Option<String>,
+    /// The path to the album art, the largest one is preferred.
+    album_art_path: Option<String>,
 }
 
-/// Get the filename from an urn path.
-fn urn_filename(urn: &str) -> Result<&str> {
-    urn.split(":")
-        .last()
-        .ok_or_else(|| RocksmithArchiveError::InvalidUrnPath(urn.to_string()))
+impl ArchiveSong {
+    /// Group the entities of a song with their manifests and find its files.
+    fn new(archive: &ArchiveOverlay, entities: Vec<EntityManifest>) -> Result<Self> {
+        let (entities, manifests): (Vec<_>, Vec<_>) = entities.into_iter().unzip();
+        let manifests = manifests.into_iter().flatten().collect();
+
+        // Get the song bank
+        let song_path = entities
+            .iter()
+            .find_map(|entity| entity.sound_bank.as_ref())
+            .ok_or_else(|| RocksmithArchiveError::MissingData("bnk file".to_string()))
+            .and_then(|sound_bank| Self::wem_path(archive, sound_bank))?;
+
+        // The preview is optional
+        let preview_path = entities
+            .iter()
+            .find_map(|entity| entity.preview_sound_bank.as_ref())
+            .and_then(|sound_bank| Self::wem_path(archive, sound_bank).ok());
+
+        let album_art_path = entities.iter().find_map(|entity| {
+            [
+                &entity.album_art_large,
+                &entity.album_art_medium,
+                &entity.album_art_small,
+            ]
+            .into_iter()
+            .flatten()
+            .find_map(|urn| archive.resolve_urn(urn.as_str(), "dds"))
+            .map(|entry| entry.path().to_string())
+        });
+
+        Ok(Self {
+            entities,
+            manifests,
+            song_path,
+            preview_path,
+            album_art_path,
+        })
+    }
+
+    /// Path of the first wem file referenced by a sound bank.
+    fn wem_path(archive: &ArchiveOverlay, sound_bank: &Urn) -> Result<String> {
+        let bnk_bytes = archive.read_urn_file(sound_bank.as_str(), "bnk")?;
+
+        // Get the wem filename from the bnk file
+        let wem_filenames = bnk::wem_filenames(&bnk_bytes)?;
+        if wem_filenames.is_empty() {
+            return Err(RocksmithArchiveError::MissingData("bnk".to_string()));
+        }
+
+        // Construct the full path
+        Ok(archive.try_path_ending_with(&wem_filenames[0])?.to_string())
+    }
+
+    /// Path for the vorbis wem file.
+    pub fn song_path(&self) -> &str {
+        &self.song_path
+    }
+
+    /// Path for the vorbis wem file of the preview.
+    pub fn preview_path(&self) -> Option<&str> {
+        self.preview_path.as_deref()
+    }
+
+    /// Path for the album art file, the largest one is preferred.
+    pub fn album_art_path(&self) -> Option<&str> {
+        self.album_art_path.as_deref()
+    }
 }
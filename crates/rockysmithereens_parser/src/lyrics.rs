@@ -0,0 +1,202 @@
+use crate::{
+    sng::{self, Sng},
+    song_xml::{XmlVocal, XmlVocals},
+};
+
+/// Marker at the end of a syllable when the word continues in the next syllable.
+const WORD_CONTINUES: char = '-';
+/// Marker at the end of a syllable when it's the last one of the line.
+const LINE_END: char = '+';
+
+/// Sung lyrics of a vocals arrangement.
+#[derive(Debug, Default, Clone)]
+pub struct Lyrics {
+    /// Every sung syllable.
+    pub syllables: Vec<Syllable>,
+    /// Custom font, the default font of the game is used when not set.
+    pub font: Option<LyricFont>,
+}
+
+impl Lyrics {
+    /// Get the syllables of every line.
+    pub fn lines_iter(&self) -> impl Iterator<Item = &[Syllable]> {
+        self.syllables
+            .split_inclusive(|syllable| syllable.ends_line)
+    }
+
+    /// Get all syllables between the timerange.
+    pub fn syllables_between_time_iter(
+        &self,
+        start_time: f32,
+        end_time: f32,
+    ) -> impl Iterator<Item = &Syllable> {
+        self.syllables
+            .iter()
+            .filter(move |syllable| syllable.time >= start_time && syllable.time < end_time)
+    }
+
+    /// Text of a line with the syllables joined into words.
+    pub fn line_text(line: &[Syllable]) -> String {
+        line.iter().fold(String::new(), |mut text, syllable| {
+            text.push_str(&syllable.text);
+            if !syllable.continues_word && !syllable.ends_line {
+                text.push(' ');
+            }
+
+            text
+        })
+    }
+}
+
+impl From<&Sng> for Lyrics {
+    /// Convert the lyrics and font from a binary vocals arrangement.
+    fn from(sng: &Sng) -> Self {
+        let syllables = sng
+            .vocals
+            .iter()
+            .map(|vocal| Syllable::new(vocal.time, vocal.note, vocal.length, &vocal.lyric))
+            .collect();
+
+        // Only songs with a custom font have the glyphs
+        let font = sng.symbols_textures.first().map(|texture| LyricFont {
+            texture: texture.font.clone(),
+            width: texture.width.max(0) as u32,
+            height: texture.height.max(0) as u32,
+            glyphs: sng.symbol_definitions.iter().map(Glyph::from).collect(),
+        });
+
+        Self { syllables, font }
+    }
+}
+
+impl From<XmlVocals> for Lyrics {
+    fn from(xml: XmlVocals) -> Self {
+        Self {
+            syllables: xml.vocals.iter().map(Syllable::from).collect(),
+            font: None,
+        }
+    }
+}
+
+/// Single sung syllable.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Syllable {
+    /// Time in seconds.
+    pub time: f32,
+    /// MIDI note that should be sung.
+    pub note: u8,
+    /// Duration in seconds.
+    pub length: f32,
+    /// The syllable without the markers.
+    pub text: String,
+    /// Whether the word continues in the next syllable.
+    pub continues_word: bool,
+    /// Whether this is the last syllable of the line.
+    pub ends_line: bool,
+}
+
+impl Syllable {
+    /// Split the markers from the syllable.
+    fn new(time: f32, note: i32, length: f32, lyric: &str) -> Self {
+        let ends_line = lyric.ends_with(LINE_END);
+        let text = lyric.strip_suffix(LINE_END).unwrap_or(lyric);
+        let continues_word = text.ends_with(WORD_CONTINUES);
+        let text = text.strip_suffix(WORD_CONTINUES).unwrap_or(text);
+
+        Self {
+            time,
+            note: note.clamp(0, u8::MAX as i32) as u8,
+            length,
+            text: text.to_string(),
+            continues_word,
+            ends_line,
+        }
+    }
+}
+
+impl From<&XmlVocal> for Syllable {
+    fn from(xml: &XmlVocal) -> Self {
+        Self::new(xml.time, xml.note, xml.length, &xml.lyric)
+    }
+}
+
+/// Custom font sheet for the lyrics.
+///
+/// Only the layout of the glyphs is parsed, the DDS texture itself isn't decoded.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LyricFont {
+    /// Path of the DDS texture with all glyphs.
+    pub texture: String,
+    /// Width of the texture in pixels.
+    pub width: u32,
+    /// Height of the texture in pixels.
+    pub height: u32,
+    /// Location of every character in the texture.
+    pub glyphs: Vec<Glyph>,
+}
+
+impl LyricFont {
+    /// Find the glyph of a character.
+    pub fn glyph(&self, text: &str) -> Option<&Glyph> {
+        self.glyphs.iter().find(|glyph| glyph.text == text)
+    }
+}
+
+/// Single character of a custom font.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Glyph {
+    /// The character.
+    pub text: String,
+    /// Bounds in the texture including the spacing.
+    pub outer: Rect,
+    /// Bounds in the texture of the character itself.
+    pub inner: Rect,
+}
+
+impl From<&sng::SymbolDefinition> for Glyph {
+    fn from(definition: &sng::SymbolDefinition) -> Self {
+        Self {
+            text: definition.text.clone(),
+            outer: Rect::from(&definition.outer),
+            inner: Rect::from(&definition.inner),
+        }
+    }
+}
+
+/// Rectangle in texture coordinates from `0.0` to `1.0`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Rect {
+    /// Left.
+    pub x_min: f32,
+    /// Top.
+    pub y_min: f32,
+    /// Right.
+    pub x_max: f32,
+    /// Bottom.
+    pub y_max: f32,
+}
+
+impl Rect {
+    /// Bounds in pixels of a texture with the size.
+    pub fn to_pixels(self, width: u32, height: u32) -> [u32; 4] {
+        let (width, height) = (width as f32, height as f32);
+
+        [
+            (self.x_min * width) as u32,
+            (self.y_min * height) as u32,
+            (self.x_max * width) as u32,
+            (self.y_max * height) as u32,
+        ]
+    }
+}
+
+impl From<&sng::Rect> for Rect {
+    fn from(rect: &sng::Rect) -> Self {
+        Self {
+            x_min: rect.x_min,
+            y_min: rect.y_min,
+            x_max: rect.x_max,
+            y_max: rect.y_max,
+        }
+    }
+}
@@ -1,9 +1,16 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{Display, Formatter},
+};
 
-use psarc::PlaystationArchive;
-use serde::Deserialize;
+use psarc::ArchiveOverlay;
+use serde::{Deserialize, Deserializer};
 
-use crate::error::Result;
+use crate::{
+    error::Result,
+    song::ToneChange,
+    urn::{optional_urn, Urn},
+};
 
 /// The JSON manifest with the song information.
 #[derive(Debug, Deserialize)]
@@ -13,29 +20,43 @@ pub struct Manifest {
     pub model_name: String,
     pub iteration_version: u16,
     entries: HashMap<String, Entry>,
+    /// ID of the entity the manifest belongs to, empty when parsed on its own.
+    #[serde(skip)]
+    pub(crate) id: String,
 }
 
 impl Manifest {
-    pub fn parse(archive: &PlaystationArchive, path: &str) -> Result<Self> {
+    pub fn parse(archive: &ArchiveOverlay, path: &str) -> Result<Self> {
         // Read the file from the archive
-        let json = archive.read_rs_file_as_string(path, "json")?;
+        let json = archive.read_urn_file_as_string(path, "json")?;
 
         let manifest = serde_json::from_str(&json)?;
 
-        // TODO: Remove the vocal bit, we don't care about it
-
         Ok(manifest)
     }
 
-    /// Get the attributes, takes a lot of shortcuts.
-    pub fn attributes(&'_ self) -> &'_ Attributes {
-        &self
-            .entries
+    /// Get the attributes of the entry with the ID, the case of the ID is ignored.
+    pub fn entry(&self, id: &str) -> Option<&Attributes> {
+        self.entries
             .iter()
-            .next()
-            .expect("no attributes")
-            .1
-            .attributes
+            .find(|(key, _)| key.eq_ignore_ascii_case(id))
+            .map(|(_, entry)| &entry.attributes)
+    }
+
+    /// IDs of all entries.
+    pub fn entry_ids(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Get the attributes of the entity the manifest belongs to.
+    ///
+    /// Falls back to the only entry when the manifest wasn't parsed for an entity, `None` when no
+    /// entry belongs to the entity.
+    pub fn attributes(&self) -> Option<&Attributes> {
+        self.entry(&self.id).or_else(|| match self.entries.len() {
+            1 => self.entries.values().next().map(|entry| &entry.attributes),
+            _ => None,
+        })
     }
 }
 
@@ -61,7 +82,7 @@ pub struct Attributes {
     pub sections: Vec<Section>,
     pub techniques: HashMap<String, HashMap<String, Vec<u32>>>,
     pub arrangement_sort: u8,
-    pub arrangement_type: u8,
+    pub arrangement_type: ArrangementType,
     pub arrangement_name: String,
     pub full_name: String,
     pub artist_name: String,
@@ -77,7 +98,9 @@ pub struct Attributes {
     pub song_partition: i32,
     pub song_volume: f32,
     pub preview_volume: f32,
-    pub song_key: String,
+    /// Key of the song shared by all its arrangements, `None` when it's empty.
+    #[serde(deserialize_with = "optional_song_key")]
+    pub song_key: Option<SongKey>,
     pub last_conversion_date_time: String,
     #[serde(rename = "MasterID_PS3")]
     pub master_id_ps3: i32,
@@ -113,20 +136,35 @@ pub struct Attributes {
     pub persistent_id: String,
     pub shipping: bool,
     pub sku: String,
+    #[serde(rename = "Tone_A")]
     pub tone_a: String,
+    #[serde(rename = "Tone_B")]
     pub tone_b: String,
+    #[serde(rename = "Tone_Base")]
     pub tone_base: String,
+    #[serde(rename = "Tone_C")]
     pub tone_c: String,
-    pub block_asset: String,
+    #[serde(rename = "Tone_D")]
+    pub tone_d: String,
+    #[serde(rename = "Tone_Multiplayer")]
+    pub tone_multiplayer: String,
+    /// Every tone used by the arrangement with the whole signal chain.
+    pub tones: Vec<Tone>,
+    #[serde(deserialize_with = "optional_urn")]
+    pub block_asset: Option<Urn>,
     pub preview_bank_path: String,
-    #[serde(rename = "ShowlightsXML")]
-    pub show_lights_xml: String,
-    pub song_asset: String,
+    #[serde(rename = "ShowlightsXML", deserialize_with = "optional_urn")]
+    pub show_lights_xml: Option<Urn>,
+    #[serde(deserialize_with = "optional_urn")]
+    pub song_asset: Option<Urn>,
     pub song_bank: String,
     pub song_event: String,
-    pub song_xml: String,
-    pub album_art: String,
-    pub manifest_urn: String,
+    #[serde(deserialize_with = "optional_urn")]
+    pub song_xml: Option<Urn>,
+    #[serde(deserialize_with = "optional_urn")]
+    pub album_art: Option<Urn>,
+    #[serde(deserialize_with = "optional_urn")]
+    pub manifest_urn: Option<Urn>,
 }
 
 impl Attributes {
@@ -139,6 +177,57 @@ impl Attributes {
         }
     }
 
+    /// Which part of the song the arrangement plays.
+    pub fn path(&self) -> ArrangementPath {
+        let properties = &self.arrangement_properties;
+
+        match self.arrangement_type {
+            ArrangementType::Vocals => ArrangementPath::Vocals,
+            ArrangementType::ShowLights => ArrangementPath::ShowLights,
+            ArrangementType::Bass => ArrangementPath::Bass,
+            // Vocals of older songs don't set the type
+            _ if self.arrangement_name.ends_with("Vocals") => ArrangementPath::Vocals,
+            _ if properties.path_bass => ArrangementPath::Bass,
+            _ if properties.path_rhythm => ArrangementPath::Rhythm,
+            _ => ArrangementPath::Lead,
+        }
+    }
+
+    /// Whether this is a vocals arrangement, which only has lyrics.
+    pub fn is_vocals(&self) -> bool {
+        self.path() == ArrangementPath::Vocals
+    }
+
+    /// Find a tone by its key.
+    pub fn tone(&self, key: &str) -> Option<&Tone> {
+        self.tones.iter().find(|tone| tone.key == key)
+    }
+
+    /// Tone of a tone change, `0` is the first tone and `3` the fourth.
+    pub fn tone_for_id(&self, id: u8) -> Option<&Tone> {
+        let key = match id {
+            0 => &self.tone_a,
+            1 => &self.tone_b,
+            2 => &self.tone_c,
+            3 => &self.tone_d,
+            _ => return None,
+        };
+
+        self.tone(key)
+    }
+
+    /// Tone active at the time, the base tone is used before the first tone change.
+    pub fn tone_at(&self, tone_changes: &[ToneChange], time: f32) -> Option<&Tone> {
+        match tone_changes
+            .iter()
+            .take_while(|tone_change| tone_change.time <= time)
+            .last()
+        {
+            Some(tone_change) => self.tone_for_id(tone_change.id),
+            None => self.tone(&self.tone_base),
+        }
+    }
+
     /// Get the artist of the song.
     pub fn artist(&self) -> &str {
         if self.artist_name.is_empty() {
@@ -158,6 +247,32 @@ impl Attributes {
     }
 }
 
+/// Key of a song shared by all its arrangements, such as `"ButItRainedSong"`.
+///
+/// Entities of the same song are grouped by it, so it's compared exactly.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(transparent)]
+pub struct SongKey(String);
+
+impl SongKey {
+    /// The key as written in the manifest.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for SongKey {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for SongKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// Template for a chord.
 #[derive(Debug, Default, Deserialize)]
 #[serde(rename_all = "PascalCase")]
@@ -168,46 +283,120 @@ pub struct ChordTemplate {
     pub frets: Vec<i32>,
 }
 
-// TODO: use bool instead of u8
 /// Properties of this arrangement.
 ///
-/// The number is a boolean where 0 is false and 1 zero.
-#[derive(Debug, Default, Deserialize)]
+/// The JSON stores the booleans as `0` and `1`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
 #[serde(default, rename_all = "camelCase")]
 pub struct ArrangementProperties {
-    pub bonus_arr: u8,
-    #[serde(rename = "Metronome")]
-    pub metronome: u8,
-    pub path_lead: u8,
-    pub path_rhythm: u8,
-    pub path_bass: u8,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub bonus_arr: bool,
+    #[serde(rename = "Metronome", deserialize_with = "bool_from_number")]
+    pub metronome: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub path_lead: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub path_rhythm: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub path_bass: bool,
+    /// Bit mask of the paths, `1` is lead, `2` rhythm and `4` bass.
     pub route_mask: u8,
-    pub represent: u8,
-    pub standard_tuning: u8,
-    pub non_standard_chords: u8,
-    pub barre_chords: u8,
-    pub power_chords: u8,
-    pub drop_d_power: u8,
-    pub finger_picking: u8,
-    pub pick_direction: u8,
-    pub double_stops: u8,
-    pub palm_mutes: u8,
-    pub harmonics: u8,
-    pub pinch_harmonics: u8,
-    pub hopo: u8,
-    pub tremolo: u8,
-    pub slides: u8,
-    pub unpitched_slides: u8,
-    pub bends: u8,
-    pub tapping: u8,
-    pub vibrato: u8,
-    pub fret_hand_mutes: u8,
-    pub slap_pop: u8,
-    pub two_finger_picking: u8,
-    pub fifths_and_octaves: u8,
-    pub syncopation: u8,
-    pub bass_pick: u8,
-    pub sustain: u8,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub represent: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub standard_tuning: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub non_standard_chords: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub open_chords: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub barre_chords: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub power_chords: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub drop_d_power: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub finger_picking: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub pick_direction: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub double_stops: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub palm_mutes: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub harmonics: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub pinch_harmonics: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub hopo: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub tremolo: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub slides: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub unpitched_slides: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub bends: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub tapping: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub vibrato: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub fret_hand_mutes: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub slap_pop: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub two_finger_picking: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub fifths_and_octaves: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub syncopation: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub bass_pick: bool,
+    #[serde(deserialize_with = "bool_from_number")]
+    pub sustain: bool,
+}
+
+/// Kind of arrangement as stored in the manifest.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(from = "u8")]
+pub enum ArrangementType {
+    #[default]
+    Guitar,
+    Bass,
+    Vocals,
+    ShowLights,
+    /// Type without a known meaning.
+    Other(u8),
+}
+
+impl From<u8> for ArrangementType {
+    fn from(number: u8) -> Self {
+        match number {
+            0 => Self::Guitar,
+            1 => Self::Bass,
+            2 => Self::Vocals,
+            3 => Self::ShowLights,
+            number => Self::Other(number),
+        }
+    }
+}
+
+/// Part of the song an arrangement plays, see [`Attributes::path`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArrangementPath {
+    Lead,
+    Rhythm,
+    Bass,
+    Vocals,
+    ShowLights,
+}
+
+impl ArrangementPath {
+    /// Whether the arrangement has notes that can be played on an instrument.
+    pub fn is_playable(self) -> bool {
+        matches!(self, Self::Lead | Self::Rhythm | Self::Bass)
+    }
 }
 
 /// Different string tunings.
@@ -222,6 +411,82 @@ pub struct Tuning {
     pub string_5: i8,
 }
 
+/// Tone with the settings of all gear.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Tone {
+    /// Identifier referenced by the tone names of the arrangement.
+    pub key: String,
+    pub name: String,
+    pub name_separator: String,
+    pub is_custom: bool,
+    /// Volume in decibels.
+    #[serde(deserialize_with = "number_from_string")]
+    pub volume: f32,
+    /// Localized descriptions such as `"$[35722]DISTORTION"`.
+    pub tone_descriptors: Vec<String>,
+    pub sort_order: f32,
+    pub gear_list: GearList,
+}
+
+/// Gear in every slot of the signal chain, empty slots are `None`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct GearList {
+    pub pre_pedal1: Option<Gear>,
+    pub pre_pedal2: Option<Gear>,
+    pub pre_pedal3: Option<Gear>,
+    pub pre_pedal4: Option<Gear>,
+    pub amp: Option<Gear>,
+    pub post_pedal1: Option<Gear>,
+    pub post_pedal2: Option<Gear>,
+    pub post_pedal3: Option<Gear>,
+    pub post_pedal4: Option<Gear>,
+    pub cabinet: Option<Gear>,
+    pub rack1: Option<Gear>,
+    pub rack2: Option<Gear>,
+    pub rack3: Option<Gear>,
+    pub rack4: Option<Gear>,
+}
+
+impl GearList {
+    /// Get all used gear in the order of the signal chain.
+    pub fn iter(&self) -> impl Iterator<Item = &Gear> {
+        [
+            &self.pre_pedal1,
+            &self.pre_pedal2,
+            &self.pre_pedal3,
+            &self.pre_pedal4,
+            &self.amp,
+            &self.post_pedal1,
+            &self.post_pedal2,
+            &self.post_pedal3,
+            &self.post_pedal4,
+            &self.cabinet,
+            &self.rack1,
+            &self.rack2,
+            &self.rack3,
+            &self.rack4,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// Single amp, cabinet, pedal or rack unit.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Gear {
+    /// Kind of gear, such as `"Amps"` or `"Pedals"`.
+    #[serde(rename = "Type")]
+    pub kind: String,
+    /// Identifier of the gear, such as `"Amp_CA100"`.
+    pub key: String,
+    pub category: Option<String>,
+    /// Setting of every knob, the names start with the key of the gear.
+    pub knob_values: HashMap<String, f32>,
+}
+
 /// Information about the different phrases.
 #[derive(Debug, Default, Deserialize)]
 #[serde(default, rename_all = "PascalCase")]
@@ -256,3 +521,49 @@ pub struct Section {
     pub end_phrase_iteration_index: u8,
     pub is_solo: bool,
 }
+
+/// Some numbers are stored as strings.
+fn number_from_string<'de, D>(deserializer: D) -> std::result::Result<f32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(f32),
+        String(String),
+    }
+
+    match NumberOrString::deserialize(deserializer)? {
+        NumberOrString::Number(number) => Ok(number),
+        NumberOrString::String(string) => string.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Song keys that aren't set are stored as empty strings.
+fn optional_song_key<'de, D>(deserializer: D) -> std::result::Result<Option<SongKey>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let key = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+
+    Ok((!key.is_empty()).then_some(SongKey(key)))
+}
+
+/// Booleans are stored as `0` and `1`.
+fn bool_from_number<'de, D>(deserializer: D) -> std::result::Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrBool {
+        Number(u8),
+        Bool(bool),
+    }
+
+    match NumberOrBool::deserialize(deserializer)? {
+        NumberOrBool::Number(number) => Ok(number != 0),
+        NumberOrBool::Bool(boolean) => Ok(boolean),
+    }
+}
@@ -1,4 +1,7 @@
-use crate::song_xml::{XmlChord, XmlNote};
+use crate::{
+    sng::{BendValue, ChordNotes, ChordTemplate, NoteMask, Sng, SngNote},
+    song_xml::{XmlChord, XmlNote},
+};
 
 /// A single tone, can be part of a chord or a bend.
 #[derive(Debug, Clone)]
@@ -20,7 +23,9 @@ pub struct Note {
     /// To which fret to slide if applicable.
     ///
     /// Can only be used in combination with sustain.
-    pub slide_to_next: bool,
+    pub slide_to: Option<u8>,
+    /// To which fret to slide without a specific pitch if applicable.
+    pub slide_unpitch_to: Option<u8>,
     /// Whether this note should be muted.
     ///
     /// Also includes palm mutes.
@@ -29,6 +34,12 @@ pub struct Note {
     pub chord: Option<u8>,
     /// How long this note should be held.
     pub sustain: Option<f32>,
+    /// How this note should be played.
+    pub techniques: Techniques,
+    /// Which direction the string should be picked.
+    pub pick_direction: PickDirection,
+    /// Finger of the fret hand, `1` is the index finger and `0` the thumb.
+    pub finger: Option<u8>,
 }
 
 impl Note {
@@ -41,42 +52,105 @@ impl Note {
             show: true,
             mute: false,
             bend: None,
-            slide_to_next: false,
+            slide_to: None,
+            slide_unpitch_to: None,
             chord: None,
             sustain: None,
+            techniques: Techniques::empty(),
+            pick_direction: PickDirection::Down,
+            finger: None,
         }
     }
-}
-
-impl From<XmlNote> for Vec<Note> {
-    fn from(xml: XmlNote) -> Self {
-        let mut first = Note::new(xml.time, xml.fret, xml.string);
 
-        // We don't differentiate between 'mute' and 'palm mute'
-        first.mute = (xml.mute != Some(0) && xml.mute != None)
-            || (xml.palm_mute != Some(0) && xml.palm_mute != None);
+    /// Convert a note from a binary arrangement, chords are split into a note for every string.
+    pub(crate) fn from_sng(note: &SngNote, sng: &Sng) -> Vec<Self> {
+        if !note.is_chord() {
+            let mut single = Note::new(note.time, note.fret, note.string);
+            single.mute = is_muted(note.techniques());
+            if note.sustain > 0.0 {
+                single.sustain = Some(note.sustain);
+            }
+            single.slide_to = u8::try_from(note.slide_to).ok();
+            single.slide_unpitch_to = u8::try_from(note.slide_unpitch_to).ok();
+            single.techniques = Techniques::from_sng(note.techniques());
+            single.pick_direction = PickDirection::from_sng(note.pick_direction);
+            single.finger = u8::try_from(note.left_hand).ok();
+            if note.max_bend != 0.0 {
+                single.bend = Some((note.max_bend, 0.0));
+            }
 
-        if xml.sustain > Some(0.0) {
-            first.sustain = xml.sustain;
+            return single.with_bend_values(
+                note.max_bend,
+                note.bends.iter().map(|bend| (bend.time, bend.step)),
+            );
         }
 
-        first.slide_to_next = xml.slide_to > Some(0);
+        let template = match sng.chord_templates.get(note.chord as usize) {
+            Some(template) => template,
+            None => return Vec::new(),
+        };
+        // Not every chord has techniques for the individual notes
+        let techniques = usize::try_from(note.chord_notes)
+            .ok()
+            .and_then(|index| sng.chord_notes.get(index));
 
-        if xml.bend.is_some() && xml.bend != Some(0.0) {
-            first.bend = xml.bend.map(|bend_value| (bend_value, 0.0));
-        }
+        template
+            .frets
+            .iter()
+            .enumerate()
+            .filter(|(_, fret)| **fret >= 0)
+            .flat_map(|(string, fret)| {
+                // The techniques of the chord apply to every string
+                let mask = note.techniques()
+                    | techniques.map_or(NoteMask::empty(), |techniques| {
+                        NoteMask::from_bits_truncate(techniques.masks[string])
+                    });
+
+                let mut chord_note = Note::new(note.time, *fret, string as i8);
+                chord_note.chord = Some(note.chord as u8);
+                chord_note.mute = is_muted(mask);
+                chord_note.techniques = Techniques::from_sng(mask);
+                chord_note.pick_direction = PickDirection::from_sng(note.pick_direction);
+                chord_note.finger = u8::try_from(template.fingers[string]).ok();
+                if note.sustain > 0.0 {
+                    chord_note.sustain = Some(note.sustain);
+                }
+
+                let bends = techniques
+                    .map(|techniques| techniques.bends[string].as_slice())
+                    .unwrap_or_default();
+                let max_bend = bends.iter().map(|bend| bend.step).fold(0.0, f32::max);
+                if max_bend != 0.0 {
+                    chord_note.bend = Some((max_bend, 0.0));
+                }
+                chord_note.slide_to = techniques
+                    .and_then(|techniques| u8::try_from(techniques.slide_to[string]).ok());
+                chord_note.slide_unpitch_to = techniques
+                    .and_then(|techniques| u8::try_from(techniques.slide_unpitch_to[string]).ok());
+
+                chord_note
+                    .with_bend_values(max_bend, bends.iter().map(|bend| (bend.time, bend.step)))
+            })
+            .collect()
+    }
+
+    /// Add a hidden note for every bend value after this note, with a range from the previous
+    /// value.
+    fn with_bend_values<I>(self, initial_bend: f32, bend_values: I) -> Vec<Self>
+    where
+        I: Iterator<Item = (f32, f32)>,
+    {
+        let first = self;
 
         // The first one is always a note
         let mut notes = std::iter::once(first.clone())
             // After that come the optional bend values
             .chain(
-                xml.bend_values_iter()
+                bend_values
                     // Keep track of the previous bend value so every note has a range
-                    .scan(xml.bend.unwrap_or(0.0), |previous_value, bend_value| {
-                        let current_value = bend_value.step.unwrap_or(0.0);
-
+                    .scan(initial_bend, |previous_value, (time, current_value)| {
                         let note = Some(Note {
-                            time: bend_value.time,
+                            time,
                             show: false,
                             bend: Some((*previous_value, current_value)),
                             ..first
@@ -110,18 +184,378 @@ impl From<XmlNote> for Vec<Note> {
     }
 }
 
+/// Convert notes back to binary arrangement notes, this is the inverse of [`Note::from_sng`].
+///
+/// The chord templates and the techniques of chord notes are added to the SNG.
+pub(crate) fn to_sng(notes: &[Note], sng: &mut Sng) -> Vec<SngNote> {
+    // The hidden bend notes belong to the note before them
+    let mut shown_notes: Vec<(&Note, Vec<&Note>)> = Vec::new();
+    for note in notes {
+        match shown_notes.last_mut() {
+            Some((_, bends)) if !note.show => bends.push(note),
+            _ => shown_notes.push((note, Vec::new())),
+        }
+    }
+
+    let mut sng_notes = Vec::new();
+    let mut shown_notes_iter = shown_notes.into_iter().peekable();
+    while let Some((note, bends)) = shown_notes_iter.next() {
+        let chord = match note.chord {
+            Some(chord) => chord,
+            None => {
+                sng_notes.push(single_to_sng(note, &bends));
+                continue;
+            }
+        };
+
+        // Chords are split into a note for every string, combine them again
+        let mut strings = vec![(note, bends)];
+        while let Some(string) = shown_notes_iter
+            .next_if(|(next, _)| next.chord == Some(chord) && next.time == note.time)
+        {
+            strings.push(string);
+        }
+
+        sng_notes.push(chord_to_sng(chord, &strings, sng));
+    }
+
+    sng_notes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    sng_notes
+}
+
+/// Convert a single note with its bend notes.
+fn single_to_sng(note: &Note, bends: &[&Note]) -> SngNote {
+    let sustain = total_sustain(note, bends);
+
+    let mut mask = techniques(note, bends) | NoteMask::SINGLE;
+    mask.set(NoteMask::OPEN, note.fret == 0);
+    mask.set(NoteMask::SUSTAIN, sustain.is_some());
+
+    SngNote {
+        mask: mask.bits(),
+        string: note.string as i8,
+        fret: note.fret as i8,
+        slide_to: fret_or_none(note.slide_to),
+        slide_unpitch_to: fret_or_none(note.slide_unpitch_to),
+        left_hand: fret_or_none(note.finger),
+        pick_direction: note.pick_direction.to_sng(),
+        vibrato: vibrato(note),
+        sustain: sustain.unwrap_or_default(),
+        max_bend: note.bend.map_or(0.0, |(max_bend, _)| max_bend),
+        bends: bend_values(bends),
+        ..empty_sng_note(note.time)
+    }
+}
+
+/// Convert all strings of a chord, the chord template is updated with the frets.
+fn chord_to_sng(chord: u8, strings: &[(&Note, Vec<&Note>)], sng: &mut Sng) -> SngNote {
+    let (first, first_bends) = &strings[0];
+    let sustain = total_sustain(first, first_bends);
+
+    // Chord IDs are indices in the templates
+    let chord = chord as usize;
+    if sng.chord_templates.len() <= chord {
+        sng.chord_templates.resize(
+            chord + 1,
+            ChordTemplate {
+                frets: [-1; 6],
+                fingers: [-1; 6],
+                ..Default::default()
+            },
+        );
+    }
+
+    let mut chord_notes = ChordNotes {
+        slide_to: [-1; 6],
+        slide_unpitch_to: [-1; 6],
+        ..Default::default()
+    };
+    for (note, bends) in strings {
+        let string = note.string as usize;
+        if string >= 6 {
+            continue;
+        }
+
+        let template = &mut sng.chord_templates[chord];
+        template.frets[string] = note.fret as i8;
+        if note.finger.is_some() {
+            template.fingers[string] = fret_or_none(note.finger);
+        }
+
+        chord_notes.masks[string] = techniques(note, bends).bits();
+        chord_notes.bends[string] = bend_values(bends);
+        chord_notes.slide_to[string] = fret_or_none(note.slide_to);
+        chord_notes.slide_unpitch_to[string] = fret_or_none(note.slide_unpitch_to);
+        chord_notes.vibrato[string] = vibrato(note);
+    }
+
+    // Only store the techniques when there are any, identical ones are shared
+    let chord_notes_index = chord_notes.masks.iter().any(|mask| *mask != 0).then(|| {
+        sng.chord_notes
+            .iter()
+            .position(|other| *other == chord_notes)
+            .unwrap_or_else(|| {
+                sng.chord_notes.push(chord_notes);
+
+                sng.chord_notes.len() - 1
+            })
+    });
+
+    let mut mask = NoteMask::CHORD;
+    mask.set(NoteMask::SUSTAIN, sustain.is_some());
+    mask.set(NoteMask::CHORD_NOTES, chord_notes_index.is_some());
+
+    SngNote {
+        mask: mask.bits(),
+        chord: chord as i32,
+        chord_notes: chord_notes_index.map_or(-1, |index| index as i32),
+        pick_direction: first.pick_direction.to_sng(),
+        sustain: sustain.unwrap_or_default(),
+        ..empty_sng_note(first.time)
+    }
+}
+
+/// Note without any techniques.
+fn empty_sng_note(time: f32) -> SngNote {
+    SngNote {
+        time,
+        string: -1,
+        fret: -1,
+        chord: -1,
+        chord_notes: -1,
+        hand_shapes: [-1; 2],
+        next_iteration_note: -1,
+        previous_iteration_note: -1,
+        parent_previous_note: -1,
+        slide_to: -1,
+        slide_unpitch_to: -1,
+        left_hand: -1,
+        tap: -1,
+        slap: -1,
+        pluck: -1,
+        ..Default::default()
+    }
+}
+
+/// Techniques of a single note or a single string of a chord.
+fn techniques(note: &Note, bends: &[&Note]) -> NoteMask {
+    let mut mask = note.techniques.to_sng();
+    mask.set(NoteMask::SLIDE, note.slide_to.is_some());
+    mask.set(
+        NoteMask::SLIDE_UNPITCHED_TO,
+        note.slide_unpitch_to.is_some(),
+    );
+    mask.set(NoteMask::BEND, note.bend.is_some() || !bends.is_empty());
+
+    mask
+}
+
+/// Binary arrangements use `-1` for frets and fingers that aren't set.
+fn fret_or_none(fret: Option<u8>) -> i8 {
+    fret.map_or(-1, |fret| fret as i8)
+}
+
+/// The strength isn't known, use the default of the editors.
+fn vibrato(note: &Note) -> i16 {
+    if note.techniques.contains(Techniques::VIBRATO) {
+        80
+    } else {
+        0
+    }
+}
+
+/// Sustain of the whole note, the hidden bend notes split it up.
+fn total_sustain(note: &Note, bends: &[&Note]) -> Option<f32> {
+    let last = bends.last().copied().unwrap_or(note);
+
+    last.sustain.map(|sustain| last.time + sustain - note.time)
+}
+
+/// Bend values from the hidden bend notes.
+fn bend_values(bends: &[&Note]) -> Vec<BendValue> {
+    bends
+        .iter()
+        .map(|bend| BendValue {
+            time: bend.time,
+            step: bend.bend.map_or(0.0, |(_, step)| step),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Whether the note is muted, we don't differentiate between the different mutes.
+fn is_muted(techniques: NoteMask) -> bool {
+    techniques.intersects(NoteMask::MUTE | NoteMask::PALM_MUTE | NoteMask::FRET_HAND_MUTE)
+}
+
+impl From<XmlNote> for Vec<Note> {
+    fn from(xml: XmlNote) -> Self {
+        let mut first = Note::new(xml.time, xml.fret, xml.string);
+
+        // We don't differentiate between 'mute' and 'palm mute'
+        first.mute = xml.mute || xml.palm_mute;
+
+        if xml.sustain > Some(0.0) {
+            first.sustain = xml.sustain;
+        }
+
+        first.slide_to = xml.slide_to.and_then(|fret| u8::try_from(fret).ok());
+        first.slide_unpitch_to = xml
+            .slide_unpitch_to
+            .and_then(|fret| u8::try_from(fret).ok());
+        first.techniques = Techniques::from_xml(&xml);
+        if xml.pick_direction == Some(1) {
+            first.pick_direction = PickDirection::Up;
+        }
+        first.finger = xml.left_hand.and_then(|finger| u8::try_from(finger).ok());
+
+        if xml.bend.is_some() && xml.bend != Some(0.0) {
+            first.bend = xml.bend.map(|bend_value| (bend_value, 0.0));
+        }
+
+        first.with_bend_values(
+            xml.bend.unwrap_or(0.0),
+            xml.bend_values_iter()
+                .map(|bend_value| (bend_value.time, bend_value.step.unwrap_or(0.0))),
+        )
+    }
+}
+
 impl From<XmlChord> for Vec<Note> {
     fn from(xml: XmlChord) -> Self {
+        // The techniques of the chord apply to every string
+        let mut techniques = Techniques::empty();
+        techniques.set(Techniques::FRET_HAND_MUTE, xml.fret_hand_mute);
+        techniques.set(Techniques::PALM_MUTE, xml.palm_mute);
+        techniques.set(Techniques::ACCENT, xml.accent);
+        techniques.set(Techniques::LINK_NEXT, xml.link_next);
+
         xml.notes
             .into_iter()
-            .map(|chord_note| {
+            .flat_map(|chord_note| {
                 Vec::<Note>::from(chord_note).into_iter().map(|mut note| {
                     note.chord = Some(xml.chord_id as u8);
+                    note.mute |= xml.fret_hand_mute || xml.palm_mute;
+                    note.techniques |= techniques;
 
                     note
                 })
             })
-            .flatten()
             .collect()
     }
 }
+
+bitflags::bitflags! {
+    /// Techniques for playing a note.
+    #[derive(Default)]
+    pub struct Techniques: u32 {
+        const HAMMER_ON = 1 << 0;
+        const PULL_OFF = 1 << 1;
+        const HARMONIC = 1 << 2;
+        const PINCH_HARMONIC = 1 << 3;
+        const TAP = 1 << 4;
+        const SLAP = 1 << 5;
+        const POP = 1 << 6;
+        const VIBRATO = 1 << 7;
+        const TREMOLO = 1 << 8;
+        const ACCENT = 1 << 9;
+        /// The note continues into the next note without striking it again.
+        const LINK_NEXT = 1 << 10;
+        const PALM_MUTE = 1 << 11;
+        const FRET_HAND_MUTE = 1 << 12;
+    }
+}
+
+/// Techniques with the matching bit in the note mask of binary arrangements.
+const SNG_TECHNIQUES: [(Techniques, NoteMask); 13] = [
+    (Techniques::HAMMER_ON, NoteMask::HAMMER_ON),
+    (Techniques::PULL_OFF, NoteMask::PULL_OFF),
+    (Techniques::HARMONIC, NoteMask::HARMONIC),
+    (Techniques::PINCH_HARMONIC, NoteMask::PINCH_HARMONIC),
+    (Techniques::TAP, NoteMask::TAP),
+    (Techniques::SLAP, NoteMask::SLAP),
+    (Techniques::POP, NoteMask::PLUCK),
+    (Techniques::VIBRATO, NoteMask::VIBRATO),
+    (Techniques::TREMOLO, NoteMask::TREMOLO),
+    (Techniques::ACCENT, NoteMask::ACCENT),
+    (Techniques::LINK_NEXT, NoteMask::PARENT),
+    (Techniques::PALM_MUTE, NoteMask::PALM_MUTE),
+    // Notes are muted with the fretting hand with the mute bit, the other bit is for whole chords
+    (Techniques::FRET_HAND_MUTE, NoteMask::MUTE),
+];
+
+impl Techniques {
+    /// Techniques from the note mask of a binary arrangement.
+    pub(crate) fn from_sng(mask: NoteMask) -> Self {
+        let mut techniques = SNG_TECHNIQUES
+            .iter()
+            .filter(|(_, sng)| mask.contains(*sng))
+            .fold(Self::empty(), |techniques, (technique, _)| {
+                techniques | *technique
+            });
+        techniques.set(
+            Self::FRET_HAND_MUTE,
+            mask.intersects(NoteMask::MUTE | NoteMask::FRET_HAND_MUTE),
+        );
+
+        techniques
+    }
+
+    /// Note mask bits for a binary arrangement.
+    pub(crate) fn to_sng(self) -> NoteMask {
+        SNG_TECHNIQUES
+            .iter()
+            .filter(|(technique, _)| self.contains(*technique))
+            .fold(NoteMask::empty(), |mask, (_, sng)| mask | *sng)
+    }
+
+    /// Techniques from the attributes of an XML note.
+    fn from_xml(xml: &XmlNote) -> Self {
+        let mut techniques = Self::empty();
+        techniques.set(Self::HAMMER_ON, xml.hammer_on);
+        techniques.set(Self::PULL_OFF, xml.pull_off);
+        techniques.set(Self::HARMONIC, xml.harmonic);
+        techniques.set(Self::PINCH_HARMONIC, xml.harmonic_pinch);
+        techniques.set(Self::TAP, xml.tap.unwrap_or_default() > 0);
+        techniques.set(Self::SLAP, xml.slap.unwrap_or_default() > 0);
+        techniques.set(Self::POP, xml.pluck.unwrap_or_default() > 0);
+        techniques.set(Self::VIBRATO, xml.vibrato.unwrap_or_default() > 0);
+        techniques.set(Self::TREMOLO, xml.tremolo);
+        techniques.set(Self::ACCENT, xml.accent);
+        techniques.set(Self::LINK_NEXT, xml.link_next);
+        techniques.set(Self::PALM_MUTE, xml.palm_mute);
+        techniques.set(Self::FRET_HAND_MUTE, xml.mute);
+
+        techniques
+    }
+}
+
+/// Which direction the string should be picked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PickDirection {
+    /// Towards the floor, also used when it's not specified.
+    #[default]
+    Down,
+    /// Towards the ceiling.
+    Up,
+}
+
+impl PickDirection {
+    /// Direction from a binary arrangement note.
+    pub(crate) fn from_sng(pick_direction: i8) -> Self {
+        if pick_direction == 1 {
+            Self::Up
+        } else {
+            Self::Down
+        }
+    }
+
+    /// Direction for a binary arrangement note.
+    pub(crate) fn to_sng(self) -> i8 {
+        match self {
+            Self::Down => 0,
+            Self::Up => 1,
+        }
+    }
+}
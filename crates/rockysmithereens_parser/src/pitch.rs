@@ -0,0 +1,139 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{
+    manifest::{ArrangementPath, Attributes},
+    note::Note,
+};
+
+/// MIDI number of A4, which is tuned to 440 Hz.
+const A4_MIDI: f32 = 69.0;
+/// Frequency of A4 in Hz.
+const A4_FREQUENCY: f32 = 440.0;
+/// Names of the pitch classes starting at C.
+const NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Instrument an arrangement is played on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Instrument {
+    /// Six string guitar.
+    #[default]
+    Guitar,
+    /// Four string bass, the remaining strings are ignored.
+    Bass,
+}
+
+impl Instrument {
+    /// Amount of strings that can be played.
+    pub fn string_count(self) -> usize {
+        match self {
+            Self::Guitar => 6,
+            Self::Bass => 4,
+        }
+    }
+
+    /// MIDI numbers of the open strings in standard tuning, from the lowest string.
+    pub fn standard_tuning(self) -> &'static [u8] {
+        match self {
+            // E2 A2 D3 G3 B3 E4
+            Self::Guitar => &[40, 45, 50, 55, 59, 64],
+            // E1 A1 D2 G2
+            Self::Bass => &[28, 33, 38, 43],
+        }
+    }
+}
+
+/// Everything needed to know which pitch a string and fret sound.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Fretboard {
+    /// Instrument the arrangement is played on.
+    pub instrument: Instrument,
+    /// Offset of every string in half steps from standard tuning.
+    pub tuning: [i16; 6],
+    /// Capo fret, `0` when there's no capo.
+    pub capo: u8,
+    /// Offset in cents from A440 of the whole instrument.
+    pub cent_offset: f32,
+}
+
+impl Fretboard {
+    /// Fretboard from the manifest attributes of an arrangement.
+    pub fn from_attributes(attributes: &Attributes) -> Self {
+        let tuning = &attributes.tuning;
+
+        Self {
+            instrument: if attributes.path() == ArrangementPath::Bass {
+                Instrument::Bass
+            } else {
+                Instrument::Guitar
+            },
+            tuning: [
+                tuning.string_0,
+                tuning.string_1,
+                tuning.string_2,
+                tuning.string_3,
+                tuning.string_4,
+                tuning.string_5,
+            ]
+            .map(i16::from),
+            capo: attributes.capo_fret.max(0.0) as u8,
+            cent_offset: attributes.cent_offset,
+        }
+    }
+
+    /// Pitch of a string and fret, `None` when the instrument doesn't have the string.
+    ///
+    /// Fret `0` is the open string, which sounds at the capo when there is one.
+    pub fn pitch(&self, string: u8, fret: u8) -> Option<Pitch> {
+        let string = string as usize;
+        let open = *self.instrument.standard_tuning().get(string)? as i16;
+        let fret = if fret == 0 { self.capo } else { fret };
+
+        let midi = open + self.tuning[string] + fret as i16;
+
+        Some(Pitch {
+            midi: u8::try_from(midi).ok()?,
+            cents: self.cent_offset,
+        })
+    }
+
+    /// Pitch of a note, see [`Self::pitch`].
+    pub fn note_pitch(&self, note: &Note) -> Option<Pitch> {
+        self.pitch(note.string, note.fret)
+    }
+}
+
+/// Sounding pitch of a note.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Pitch {
+    /// MIDI note number, `60` is C4.
+    pub midi: u8,
+    /// Offset in cents from the equal tempered MIDI note.
+    pub cents: f32,
+}
+
+impl Pitch {
+    /// Frequency in Hz tuned to A440.
+    pub fn frequency(&self) -> f32 {
+        let semitones = self.midi as f32 + self.cents / 100.0 - A4_MIDI;
+
+        A4_FREQUENCY * 2f32.powf(semitones / 12.0)
+    }
+
+    /// Name of the pitch class without the octave, such as `"F#"`.
+    pub fn class_name(&self) -> &'static str {
+        NAMES[self.midi as usize % 12]
+    }
+
+    /// Octave in scientific pitch notation, middle C is in octave 4.
+    pub fn octave(&self) -> i8 {
+        (self.midi / 12) as i8 - 1
+    }
+}
+
+impl Display for Pitch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.class_name(), self.octave())
+    }
+}
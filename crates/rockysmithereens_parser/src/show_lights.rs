@@ -0,0 +1,148 @@
+use serde::Deserialize;
+
+use crate::error::Result;
+
+/// Lowest note of the fog colors.
+const FOG_MIN: u8 = 24;
+/// Highest note of the fog colors.
+const FOG_MAX: u8 = 35;
+/// Note that turns the beams off.
+const BEAMS_OFF: u8 = 42;
+/// Lowest note of the beam colors.
+const BEAM_MIN: u8 = 48;
+/// Highest note of the beam colors.
+const BEAM_MAX: u8 = 59;
+/// Note that turns the lasers off.
+const LASERS_OFF: u8 = 66;
+/// Note that turns the lasers on.
+const LASERS_ON: u8 = 67;
+
+/// Timeline of the stage lighting of a song.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ShowLights {
+    /// Every lighting change, sorted by time.
+    pub events: Vec<ShowLight>,
+}
+
+impl ShowLights {
+    /// Parse the showlights XML string.
+    pub fn parse(xml: &str) -> Result<Self> {
+        let xml: XmlShowLights = quick_xml::de::from_str(xml)?;
+
+        let mut events = xml
+            .show_lights
+            .iter()
+            .map(|show_light| ShowLight {
+                time: show_light.time,
+                note: show_light.note,
+            })
+            .collect::<Vec<_>>();
+        events.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        Ok(Self { events })
+    }
+
+    /// Get all changes between the timerange.
+    pub fn events_between_time_iter(
+        &self,
+        start_time: f32,
+        end_time: f32,
+    ) -> impl Iterator<Item = &ShowLight> {
+        self.events
+            .iter()
+            .filter(move |event| event.time >= start_time && event.time < end_time)
+    }
+
+    /// Color of the fog at the time, `None` before the first fog change.
+    pub fn fog_at(&self, time: f32) -> Option<u8> {
+        self.last_effect_before(time, |effect| match effect {
+            Effect::Fog(color) => Some(color),
+            _ => None,
+        })
+    }
+
+    /// Color of the beams at the time, `None` when they are off.
+    pub fn beam_at(&self, time: f32) -> Option<u8> {
+        self.last_effect_before(time, |effect| match effect {
+            Effect::Beam(color) => Some(Some(color)),
+            Effect::BeamsOff => Some(None),
+            _ => None,
+        })
+        .flatten()
+    }
+
+    /// Whether the lasers are on at the time.
+    pub fn lasers_at(&self, time: f32) -> bool {
+        self.last_effect_before(time, |effect| match effect {
+            Effect::Lasers(on) => Some(on),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+
+    /// Find the last effect of a kind that started before the time.
+    fn last_effect_before<T, F>(&self, time: f32, f: F) -> Option<T>
+    where
+        F: Fn(Effect) -> Option<T>,
+    {
+        self.events
+            .iter()
+            .take_while(|event| event.time <= time)
+            .filter_map(|event| f(event.effect()))
+            .last()
+    }
+}
+
+/// Single lighting change.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ShowLight {
+    /// Time in seconds.
+    pub time: f32,
+    /// Code of the effect, see [`Self::effect`].
+    pub note: u8,
+}
+
+impl ShowLight {
+    /// What this change does to the lighting.
+    pub fn effect(&self) -> Effect {
+        match self.note {
+            FOG_MIN..=FOG_MAX => Effect::Fog(self.note - FOG_MIN),
+            BEAMS_OFF => Effect::BeamsOff,
+            BEAM_MIN..=BEAM_MAX => Effect::Beam(self.note - BEAM_MIN),
+            LASERS_OFF => Effect::Lasers(false),
+            LASERS_ON => Effect::Lasers(true),
+            note => Effect::Other(note),
+        }
+    }
+}
+
+/// Lighting change decoded from the note.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    /// Change the fog to one of the twelve colors.
+    Fog(u8),
+    /// Change the beams to one of the twelve colors, also turns them on.
+    Beam(u8),
+    /// Turn the beams off.
+    BeamsOff,
+    /// Turn the lasers on or off.
+    Lasers(bool),
+    /// Note without a known meaning.
+    Other(u8),
+}
+
+/// Showlights XML file representation.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct XmlShowLights {
+    #[serde(rename = "showlight")]
+    show_lights: Vec<XmlShowLight>,
+}
+
+/// Single lighting change.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct XmlShowLight {
+    time: f32,
+    note: u8,
+}
@@ -0,0 +1,653 @@
+//! Binary Rocksmith 2014 arrangement files.
+//!
+//! Official DLC only ships these instead of the editable XML arrangements. The file is encrypted
+//! with a platform specific key and zlib compressed.
+
+mod parse;
+mod write;
+
+use std::io::{Read, Write};
+
+use aes::{
+    cipher::{KeyIvInit, StreamCipher},
+    Aes256,
+};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use nom::{error::context, number::complete::le_u32};
+use nom_error::WithOffset;
+
+use crate::error::{Result, RocksmithArchiveError};
+
+/// Decryption key for SNG files from the PC version.
+const PC_KEY: [u8; 32] =
+    hex_literal::hex!("CB648DF3D12A16BF71701414E69619EC171CCA5D2A142E3E59DE7ADDA18A3A30");
+/// Decryption key for SNG files from the Mac version.
+const MAC_KEY: [u8; 32] =
+    hex_literal::hex!("9821330E34B91F70D0A48CBD625993126970CEA09192C0E6CDA676CC9838289D");
+
+/// Magic number at the start of every SNG file.
+const MAGIC: u32 = 0x4A;
+/// Header flag for a zlib compressed payload.
+const FLAG_COMPRESSED: u32 = 1;
+/// Header flag for an encrypted payload.
+const FLAG_ENCRYPTED: u32 = 2;
+/// Size of the magic, flags and IV.
+const HEADER_SIZE: usize = 24;
+/// Beat mask bit for the first beat of a measure.
+pub const BEAT_MASK_FIRST: u32 = 1;
+/// Beat mask bit for the first beat of an even measure.
+pub const BEAT_MASK_EVEN_MEASURE: u32 = 2;
+/// Chord template mask bit for arpeggios.
+pub const CHORD_MASK_ARPEGGIO: u32 = 1;
+/// Chord template mask bit for chords that aren't played.
+pub const CHORD_MASK_NOP: u32 = 2;
+
+/// Size of the signature after the payload, the game doesn't check it.
+const SIGNATURE_SIZE: usize = 56;
+
+/// Which version of the game the file is for, they are encrypted with different keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    /// Files in `songs/bin/generic/`.
+    Pc,
+    /// Files in `songs/bin/macos/`.
+    Mac,
+}
+
+impl Platform {
+    /// Guess the platform from the path of the file in the archive.
+    pub fn from_path(path: &str) -> Self {
+        if path.contains("/macos/") {
+            Platform::Mac
+        } else {
+            Platform::Pc
+        }
+    }
+
+    /// AES key the files are encrypted with.
+    fn key(self) -> [u8; 32] {
+        match self {
+            Platform::Pc => PC_KEY,
+            Platform::Mac => MAC_KEY,
+        }
+    }
+}
+
+/// Parsed binary arrangement.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Sng {
+    /// Beat grid.
+    pub beats: Vec<Beat>,
+    /// Unique phrases, referenced by the phrase iterations.
+    pub phrases: Vec<Phrase>,
+    /// Chord shapes, referenced by the notes.
+    pub chord_templates: Vec<ChordTemplate>,
+    /// Techniques of the individual notes in chords.
+    pub chord_notes: Vec<ChordNotes>,
+    /// Lyrics, only used for vocal arrangements.
+    pub vocals: Vec<Vocal>,
+    /// Custom font headers, only present with vocals.
+    pub symbols_headers: Vec<SymbolsHeader>,
+    /// Custom font textures, only present with vocals.
+    pub symbols_textures: Vec<SymbolsTexture>,
+    /// Custom font glyphs, only present with vocals.
+    pub symbol_definitions: Vec<SymbolDefinition>,
+    /// Every time a phrase is played.
+    pub phrase_iterations: Vec<PhraseIteration>,
+    /// Per level information for phrases.
+    pub phrase_extra_info: Vec<PhraseExtraInfo>,
+    /// Phrases whose difficulty changes together.
+    pub linked_difficulties: Vec<LinkedDifficulty>,
+    /// Timed actions.
+    pub actions: Vec<Action>,
+    /// Timed events such as crowd reactions.
+    pub events: Vec<Event>,
+    /// Tone switches.
+    pub tone_changes: Vec<ToneChange>,
+    /// Timed DNA markers.
+    pub dnas: Vec<Dna>,
+    /// Song sections such as verse and chorus.
+    pub sections: Vec<Section>,
+    /// The notes for every difficulty level.
+    pub arrangements: Vec<Arrangement>,
+    /// Global information.
+    pub metadata: Metadata,
+}
+
+impl Sng {
+    /// Decrypt, decompress and parse the SNG file.
+    #[profiling::function]
+    pub fn parse(bytes: &[u8], platform: Platform) -> Result<Self> {
+        Self::parse_decrypted(&Self::decrypt(bytes, platform)?)
+    }
+
+    /// Parse an already decrypted and decompressed SNG payload.
+    #[profiling::function]
+    pub fn parse_decrypted(bytes: &[u8]) -> Result<Self> {
+        let (rest, sng) = parse::sng(bytes).offset_in(bytes)?;
+        if !rest.is_empty() {
+            return Err(RocksmithArchiveError::CorruptSng(format!(
+                "{} unexpected bytes after the metadata",
+                rest.len()
+            )));
+        }
+
+        Ok(sng)
+    }
+
+    /// Decrypt and decompress the SNG file, returning the payload.
+    #[profiling::function]
+    pub fn decrypt(bytes: &[u8], platform: Platform) -> Result<Vec<u8>> {
+        let (i, magic) = context("sng magic", le_u32)(bytes).offset_in(bytes)?;
+        if magic != MAGIC {
+            return Err(RocksmithArchiveError::CorruptSng(format!(
+                "magic 0x{:X} is not 0x{:X}",
+                magic, MAGIC
+            )));
+        }
+        let (_, flags) = context("sng flags", le_u32)(i).offset_in(bytes)?;
+
+        let iv = bytes
+            .get(8..HEADER_SIZE)
+            .ok_or_else(|| RocksmithArchiveError::MissingData("sng iv".to_string()))?;
+        let mut payload = bytes[HEADER_SIZE..].to_vec();
+
+        if flags & FLAG_ENCRYPTED != 0 {
+            let mut cipher = ctr::Ctr128BE::<Aes256>::new(&platform.key().into(), iv.into());
+            cipher.apply_keystream(&mut payload);
+        }
+
+        if flags & FLAG_COMPRESSED == 0 {
+            return Ok(payload);
+        }
+
+        // The payload starts with the uncompressed size
+        let (compressed, length) =
+            context("sng uncompressed length", le_u32)(payload.as_slice()).offset_in(&payload)?;
+
+        // Anything after the zlib stream is the signature
+        let mut decompressed = Vec::new();
+        ZlibDecoder::new(compressed)
+            .take(length as u64)
+            .read_to_end(&mut decompressed)
+            .map_err(|err| RocksmithArchiveError::CorruptSng(format!("decompressing: {}", err)))?;
+        if decompressed.len() != length as usize {
+            return Err(RocksmithArchiveError::CorruptSng(format!(
+                "decompressed to {} bytes instead of {}",
+                decompressed.len(),
+                length
+            )));
+        }
+
+        Ok(decompressed)
+    }
+
+    /// Serialize, compress and encrypt the SNG file for the platform.
+    #[profiling::function]
+    pub fn to_bytes(&self, platform: Platform) -> Result<Vec<u8>> {
+        Self::encrypt(&self.to_decrypted_bytes(), platform)
+    }
+
+    /// Serialize to an uncompressed and unencrypted payload.
+    #[profiling::function]
+    pub fn to_decrypted_bytes(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        write::sng(self, &mut payload);
+
+        payload
+    }
+
+    /// Compress and encrypt a payload, this is the inverse of [`Sng::decrypt`].
+    #[profiling::function]
+    pub fn encrypt(payload: &[u8], platform: Platform) -> Result<Vec<u8>> {
+        // The key is public anyway so a random IV doesn't add anything
+        let iv = [0; 16];
+
+        let mut encrypted = (payload.len() as u32).to_le_bytes().to_vec();
+        let mut encoder = ZlibEncoder::new(&mut encrypted, Compression::default());
+        encoder.write_all(payload)?;
+        encoder.finish()?;
+
+        let mut cipher = ctr::Ctr128BE::<Aes256>::new(&platform.key().into(), &iv.into());
+        cipher.apply_keystream(&mut encrypted);
+
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + encrypted.len() + SIGNATURE_SIZE);
+        bytes.extend(MAGIC.to_le_bytes());
+        bytes.extend((FLAG_COMPRESSED | FLAG_ENCRYPTED).to_le_bytes());
+        bytes.extend(iv);
+        bytes.extend(encrypted);
+        bytes.resize(bytes.len() + SIGNATURE_SIZE, 0);
+
+        Ok(bytes)
+    }
+}
+
+/// Single beat of the beat grid.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Beat {
+    /// Time in seconds.
+    pub time: f32,
+    /// Measure number, only set on the first beat of a measure.
+    pub measure: i16,
+    /// Beat number inside the measure.
+    pub beat: i16,
+    /// Phrase iteration the beat is in.
+    pub phrase_iteration: i32,
+    /// Whether it's the first beat of a measure and whether the measure is even, see
+    /// [`BEAT_MASK_FIRST`] and [`BEAT_MASK_EVEN_MEASURE`].
+    pub mask: u32,
+}
+
+/// Phrase, a reusable part of the song with it's own difficulty levels.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Phrase {
+    /// Whether the phrase is a solo.
+    pub solo: u8,
+    /// Whether the difficulty levels jump.
+    pub disparity: u8,
+    /// Whether the phrase is ignored for scoring.
+    pub ignore: u8,
+    pub(crate) padding: u8,
+    /// Highest difficulty level of this phrase.
+    pub max_difficulty: i32,
+    /// Amount of phrase iterations of this phrase.
+    pub phrase_iteration_links: i32,
+    /// Name of the phrase.
+    pub name: String,
+}
+
+/// Shape of a chord.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChordTemplate {
+    /// Whether it's an arpeggio or a nop chord, see [`CHORD_MASK_ARPEGGIO`] and
+    /// [`CHORD_MASK_NOP`].
+    pub mask: u32,
+    /// Fret for every string, `-1` when the string isn't played.
+    pub frets: [i8; 6],
+    /// Finger for every string, `-1` when the string isn't played.
+    pub fingers: [i8; 6],
+    /// MIDI note for every string.
+    pub notes: [i32; 6],
+    /// Name of the chord.
+    pub name: String,
+}
+
+/// Techniques for every note of a chord.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChordNotes {
+    /// Note mask for every string, see [`NoteMask`].
+    pub masks: [u32; 6],
+    /// Bends for every string.
+    pub bends: [Vec<BendValue>; 6],
+    /// Fret to slide to for every string, `-1` when not sliding.
+    pub slide_to: [i8; 6],
+    /// Fret to slide to without pitch for every string, `-1` when not sliding.
+    pub slide_unpitch_to: [i8; 6],
+    /// Vibrato strength for every string.
+    pub vibrato: [i16; 6],
+}
+
+/// Single point of a bend.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BendValue {
+    /// Time in seconds.
+    pub time: f32,
+    /// Amount of half steps bent.
+    pub step: f32,
+    pub(crate) unknown: [u8; 4],
+}
+
+/// Single syllable of the lyrics.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Vocal {
+    /// Time in seconds.
+    pub time: f32,
+    /// MIDI note of the syllable.
+    pub note: i32,
+    /// Duration in seconds.
+    pub length: f32,
+    /// The syllable.
+    pub lyric: String,
+}
+
+/// Header of a custom lyrics font.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SymbolsHeader {
+    pub(crate) unknown: [i32; 8],
+}
+
+/// Texture of a custom lyrics font.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SymbolsTexture {
+    /// Path of the font texture.
+    pub font: String,
+    /// Length of the path.
+    pub font_path_length: i32,
+    pub(crate) unknown: i32,
+    /// Width of the texture in pixels.
+    pub width: i32,
+    /// Height of the texture in pixels.
+    pub height: i32,
+}
+
+/// Single glyph of a custom lyrics font.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SymbolDefinition {
+    /// The character.
+    pub text: String,
+    /// Outer bounds in the texture.
+    pub outer: Rect,
+    /// Inner bounds in the texture.
+    pub inner: Rect,
+}
+
+/// Rectangle in texture coordinates.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Rect {
+    /// Top.
+    pub y_min: f32,
+    /// Left.
+    pub x_min: f32,
+    /// Bottom.
+    pub y_max: f32,
+    /// Right.
+    pub x_max: f32,
+}
+
+/// Single time a phrase is played.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PhraseIteration {
+    /// Index of the phrase.
+    pub phrase: i32,
+    /// Start time in seconds.
+    pub start_time: f32,
+    /// Start time of the next phrase iteration in seconds.
+    pub next_phrase_time: f32,
+    /// Difficulty level for easy, medium and hard.
+    pub difficulty: [i32; 3],
+}
+
+/// Information about a phrase on a specific level.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PhraseExtraInfo {
+    /// Index of the phrase.
+    pub phrase: i32,
+    /// Difficulty level.
+    pub difficulty: i32,
+    pub(crate) empty: i32,
+    /// Amount of levels jumped.
+    pub level_jump: u8,
+    /// Whether this level is the same as the one before.
+    pub redundant: i16,
+    pub(crate) padding: u8,
+}
+
+/// Phrases that change difficulty together.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LinkedDifficulty {
+    /// Level where the link breaks.
+    pub level_break: i32,
+    /// Indices of the linked phrases.
+    pub phrases: Vec<i32>,
+}
+
+/// Timed action.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Action {
+    /// Time in seconds.
+    pub time: f32,
+    /// Name of the action.
+    pub name: String,
+}
+
+/// Timed event.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Event {
+    /// Time in seconds.
+    pub time: f32,
+    /// Name of the event, such as `"B0"` for a crowd reaction.
+    pub name: String,
+}
+
+/// Switch to another tone.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ToneChange {
+    /// Time in seconds.
+    pub time: f32,
+    /// Index of the tone, `0` to `3`.
+    pub tone: i32,
+}
+
+/// Timed DNA marker.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Dna {
+    /// Time in seconds.
+    pub time: f32,
+    /// Identifier of the marker.
+    pub id: i32,
+}
+
+/// Section of the song such as verse and chorus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    /// Name of the section.
+    pub name: String,
+    /// How many times a section with this name has occurred.
+    pub number: i32,
+    /// Start time in seconds.
+    pub start_time: f32,
+    /// End time in seconds.
+    pub end_time: f32,
+    /// First phrase iteration in the section.
+    pub start_phrase_iteration: i32,
+    /// Last phrase iteration in the section.
+    pub end_phrase_iteration: i32,
+    /// Which strings are used on every level.
+    pub string_mask: [u8; 36],
+}
+
+/// All notes of a single difficulty level.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Arrangement {
+    /// Difficulty level.
+    pub difficulty: i32,
+    /// Fret hand positions.
+    pub anchors: Vec<Anchor>,
+    /// Fret hand positions during slides.
+    pub anchor_extensions: Vec<AnchorExtension>,
+    /// Time ranges a chord shape is held.
+    pub hand_shapes: Vec<HandShape>,
+    /// Time ranges an arpeggio shape is held.
+    pub arpeggios: Vec<HandShape>,
+    /// Notes and chords.
+    pub notes: Vec<SngNote>,
+    /// Average amount of notes for every phrase.
+    pub average_notes_per_iteration: Vec<f32>,
+    /// Amount of notes for every phrase iteration, ignoring ignored notes.
+    pub notes_in_iteration_unignored: Vec<i32>,
+    /// Amount of notes for every phrase iteration.
+    pub notes_in_iteration: Vec<i32>,
+}
+
+/// Position of the fret hand.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Anchor {
+    /// Start time in seconds.
+    pub start_time: f32,
+    /// End time in seconds.
+    pub end_time: f32,
+    /// Time of the first note in the anchor.
+    pub first_note_time: f32,
+    /// Time of the last note in the anchor.
+    pub last_note_time: f32,
+    /// Lowest fret of the hand.
+    pub fret: i8,
+    pub(crate) padding: [u8; 3],
+    /// Amount of frets the hand spans.
+    pub width: i32,
+    /// Phrase iteration the anchor is in.
+    pub phrase_iteration: i32,
+}
+
+/// Position of the fret hand while sliding.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AnchorExtension {
+    /// Time in seconds.
+    pub time: f32,
+    /// Fret of the hand.
+    pub fret: i8,
+    pub(crate) unknown: [u8; 7],
+}
+
+/// Time range a chord or arpeggio shape is held.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HandShape {
+    /// Index of the chord template.
+    pub chord: i32,
+    /// Start time in seconds.
+    pub start_time: f32,
+    /// End time in seconds.
+    pub end_time: f32,
+    /// Time of the first note in the shape.
+    pub first_note_time: f32,
+    /// Time of the last note in the shape.
+    pub last_note_time: f32,
+}
+
+/// Single note or chord.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SngNote {
+    /// Techniques, see [`NoteMask`].
+    pub mask: u32,
+    pub(crate) flags: u32,
+    pub(crate) hash: u32,
+    /// Time in seconds.
+    pub time: f32,
+    /// String, `-1` for chords.
+    pub string: i8,
+    /// Fret, `-1` for chords.
+    pub fret: i8,
+    /// Fret of the anchor at this time.
+    pub anchor_fret: i8,
+    /// Width of the anchor at this time.
+    pub anchor_width: i8,
+    /// Index of the chord template, `-1` for single notes.
+    pub chord: i32,
+    /// Index of the chord notes, `-1` when the chord has no techniques.
+    pub chord_notes: i32,
+    /// Phrase the note is in.
+    pub phrase: i32,
+    /// Phrase iteration the note is in.
+    pub phrase_iteration: i32,
+    /// Index of the hand shape and the arpeggio the note is in, `-1` if none.
+    pub hand_shapes: [i16; 2],
+    /// Index of the next note in the phrase iteration.
+    pub next_iteration_note: i16,
+    /// Index of the previous note in the phrase iteration.
+    pub previous_iteration_note: i16,
+    /// Index of the previous note linked to this one.
+    pub parent_previous_note: i16,
+    /// Fret to slide to, `-1` when not sliding.
+    pub slide_to: i8,
+    /// Fret to slide to without pitch, `-1` when not sliding.
+    pub slide_unpitch_to: i8,
+    /// Finger of the fret hand, `-1` when not specified.
+    pub left_hand: i8,
+    /// Tapping finger.
+    pub tap: i8,
+    /// Whether the string is picked up or down.
+    pub pick_direction: i8,
+    /// Slap strength.
+    pub slap: i8,
+    /// Pluck strength.
+    pub pluck: i8,
+    /// Vibrato strength.
+    pub vibrato: i16,
+    /// Duration in seconds.
+    pub sustain: f32,
+    /// Highest bend in half steps.
+    pub max_bend: f32,
+    /// Points of the bend.
+    pub bends: Vec<BendValue>,
+}
+
+impl SngNote {
+    /// Techniques of the note, unknown bits are ignored.
+    pub fn techniques(&self) -> NoteMask {
+        NoteMask::from_bits_truncate(self.mask)
+    }
+
+    /// Whether this is a chord instead of a single note.
+    pub fn is_chord(&self) -> bool {
+        self.chord >= 0
+    }
+}
+
+bitflags::bitflags! {
+    /// Techniques of a note.
+    #[derive(Default)]
+    pub struct NoteMask: u32 {
+        const CHORD = 0x02;
+        const OPEN = 0x04;
+        const FRET_HAND_MUTE = 0x08;
+        const TREMOLO = 0x10;
+        const HARMONIC = 0x20;
+        const PALM_MUTE = 0x40;
+        const SLAP = 0x80;
+        const PLUCK = 0x100;
+        const HAMMER_ON = 0x200;
+        const PULL_OFF = 0x400;
+        const SLIDE = 0x800;
+        const BEND = 0x1000;
+        const SUSTAIN = 0x2000;
+        const TAP = 0x4000;
+        const PINCH_HARMONIC = 0x8000;
+        const VIBRATO = 0x10000;
+        const MUTE = 0x20000;
+        const IGNORE = 0x40000;
+        const LEFT_HAND = 0x80000;
+        const RIGHT_HAND = 0x100000;
+        const HIGH_DENSITY = 0x200000;
+        const SLIDE_UNPITCHED_TO = 0x400000;
+        const SINGLE = 0x800000;
+        const CHORD_NOTES = 0x1000000;
+        const DOUBLE_STOP = 0x2000000;
+        const ACCENT = 0x4000000;
+        const PARENT = 0x8000000;
+        const CHILD = 0x10000000;
+        const ARPEGGIO = 0x20000000;
+        const STRUM = 0x80000000;
+    }
+}
+
+/// Global information about the arrangement.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Metadata {
+    /// Highest possible score.
+    pub max_score: f64,
+    /// Amount of notes and chords on the highest difficulty.
+    pub max_notes_and_chords: f64,
+    /// Amount of notes and chords on the highest difficulty, ignoring ignored notes.
+    pub max_notes_and_chords_real: f64,
+    /// Score for every note.
+    pub points_per_note: f64,
+    /// Length of the first beat in seconds.
+    pub first_beat_length: f32,
+    /// Start time in seconds.
+    pub start_time: f32,
+    /// Capo fret, `-1` or `0` when there's no capo.
+    pub capo: i8,
+    /// When the arrangement was converted.
+    pub last_conversion_date_time: String,
+    /// Which part of the instrument, such as lead or rhythm.
+    pub part: i16,
+    /// Length of the song in seconds.
+    pub song_length: f32,
+    /// Offset of every string in half steps from standard tuning.
+    pub tuning: Vec<i16>,
+    /// Time of the first note in seconds.
+    pub first_note_time: f32,
+    pub(crate) first_note_time_copy: f32,
+    /// Highest difficulty level.
+    pub max_difficulty: i32,
+}
@@ -0,0 +1,549 @@
+use nom::{
+    bytes::complete::take,
+    combinator::map,
+    error::{context, VerboseError},
+    multi::{count, length_count},
+    number::complete::{le_f32, le_f64, le_i16, le_i32, le_i8, le_u32, le_u8},
+    IResult,
+};
+
+use super::{
+    Action, Anchor, AnchorExtension, Arrangement, Beat, BendValue, ChordNotes, ChordTemplate, Dna,
+    Event, HandShape, LinkedDifficulty, Metadata, Phrase, PhraseExtraInfo, PhraseIteration, Rect,
+    Section, Sng, SngNote, SymbolDefinition, SymbolsHeader, SymbolsTexture, ToneChange, Vocal,
+};
+
+/// Maximum amount of points of a bend in the chord notes.
+const CHORD_NOTE_BEND_VALUES: usize = 32;
+
+type ParseResult<'a, O> = IResult<&'a [u8], O, VerboseError<&'a [u8]>>;
+
+/// Parse the whole decrypted payload.
+#[profiling::function]
+pub fn sng(i: &[u8]) -> ParseResult<'_, Sng> {
+    let (i, beats) = context("sng beats", array(beat))(i)?;
+    let (i, phrases) = context("sng phrases", array(phrase))(i)?;
+    let (i, chord_templates) = context("sng chord templates", array(chord_template))(i)?;
+    let (i, chord_notes) = context("sng chord notes", array(chord_notes))(i)?;
+    let (mut i, vocals) = context("sng vocals", array(vocal))(i)?;
+
+    // The font is only stored for vocal arrangements
+    let (mut symbols_headers, mut symbols_textures, mut symbol_definitions) =
+        (Vec::new(), Vec::new(), Vec::new());
+    if !vocals.is_empty() {
+        (i, symbols_headers) = context("sng symbols headers", array(symbols_header))(i)?;
+        (i, symbols_textures) = context("sng symbols textures", array(symbols_texture))(i)?;
+        (i, symbol_definitions) = context("sng symbol definitions", array(symbol_definition))(i)?;
+    }
+
+    let (i, phrase_iterations) = context("sng phrase iterations", array(phrase_iteration))(i)?;
+    let (i, phrase_extra_info) = context("sng phrase extra info", array(phrase_extra_info))(i)?;
+    let (i, linked_difficulties) = context("sng linked difficulties", array(linked_difficulty))(i)?;
+    let (i, actions) = context("sng actions", array(action))(i)?;
+    let (i, events) = context("sng events", array(event))(i)?;
+    let (i, tone_changes) = context("sng tone changes", array(tone_change))(i)?;
+    let (i, dnas) = context("sng dnas", array(dna))(i)?;
+    let (i, sections) = context("sng sections", array(section))(i)?;
+    let (i, arrangements) = context("sng arrangements", array(arrangement))(i)?;
+    let (i, metadata) = context("sng metadata", metadata)(i)?;
+
+    let sng = Sng {
+        beats,
+        phrases,
+        chord_templates,
+        chord_notes,
+        vocals,
+        symbols_headers,
+        symbols_textures,
+        symbol_definitions,
+        phrase_iterations,
+        phrase_extra_info,
+        linked_difficulties,
+        actions,
+        events,
+        tone_changes,
+        dnas,
+        sections,
+        arrangements,
+        metadata,
+    };
+
+    Ok((i, sng))
+}
+
+fn beat(i: &[u8]) -> ParseResult<'_, Beat> {
+    let (i, time) = le_f32(i)?;
+    let (i, measure) = le_i16(i)?;
+    let (i, beat) = le_i16(i)?;
+    let (i, phrase_iteration) = le_i32(i)?;
+    let (i, mask) = le_u32(i)?;
+
+    let beat = Beat {
+        time,
+        measure,
+        beat,
+        phrase_iteration,
+        mask,
+    };
+
+    Ok((i, beat))
+}
+
+fn phrase(i: &[u8]) -> ParseResult<'_, Phrase> {
+    let (i, solo) = le_u8(i)?;
+    let (i, disparity) = le_u8(i)?;
+    let (i, ignore) = le_u8(i)?;
+    let (i, padding) = le_u8(i)?;
+    let (i, max_difficulty) = le_i32(i)?;
+    let (i, phrase_iteration_links) = le_i32(i)?;
+    let (i, name) = string(32)(i)?;
+
+    let phrase = Phrase {
+        solo,
+        disparity,
+        ignore,
+        padding,
+        max_difficulty,
+        phrase_iteration_links,
+        name,
+    };
+
+    Ok((i, phrase))
+}
+
+fn chord_template(i: &[u8]) -> ParseResult<'_, ChordTemplate> {
+    let (i, mask) = le_u32(i)?;
+    let (i, frets) = fixed(le_i8)(i)?;
+    let (i, fingers) = fixed(le_i8)(i)?;
+    let (i, notes) = fixed(le_i32)(i)?;
+    let (i, name) = string(32)(i)?;
+
+    let chord_template = ChordTemplate {
+        mask,
+        frets,
+        fingers,
+        notes,
+        name,
+    };
+
+    Ok((i, chord_template))
+}
+
+fn chord_notes(i: &[u8]) -> ParseResult<'_, ChordNotes> {
+    let (i, masks) = fixed(le_u32)(i)?;
+    let (i, bends) = map(count(chord_note_bend_values, 6), |bends| {
+        bends.try_into().unwrap_or_default()
+    })(i)?;
+    let (i, slide_to) = fixed(le_i8)(i)?;
+    let (i, slide_unpitch_to) = fixed(le_i8)(i)?;
+    let (i, vibrato) = fixed(le_i16)(i)?;
+
+    let chord_notes = ChordNotes {
+        masks,
+        bends,
+        slide_to,
+        slide_unpitch_to,
+        vibrato,
+    };
+
+    Ok((i, chord_notes))
+}
+
+/// Bends of a chord note are stored in a fixed size array.
+fn chord_note_bend_values(i: &[u8]) -> ParseResult<'_, Vec<BendValue>> {
+    let (i, mut bend_values) = count(bend_value, CHORD_NOTE_BEND_VALUES)(i)?;
+    let (i, used_count) = le_u32(i)?;
+
+    bend_values.truncate(used_count as usize);
+
+    Ok((i, bend_values))
+}
+
+fn bend_value(i: &[u8]) -> ParseResult<'_, BendValue> {
+    let (i, time) = le_f32(i)?;
+    let (i, step) = le_f32(i)?;
+    let (i, unknown) = fixed(le_u8)(i)?;
+
+    let bend_value = BendValue {
+        time,
+        step,
+        unknown,
+    };
+
+    Ok((i, bend_value))
+}
+
+fn vocal(i: &[u8]) -> ParseResult<'_, Vocal> {
+    let (i, time) = le_f32(i)?;
+    let (i, note) = le_i32(i)?;
+    let (i, length) = le_f32(i)?;
+    let (i, lyric) = string(48)(i)?;
+
+    let vocal = Vocal {
+        time,
+        note,
+        length,
+        lyric,
+    };
+
+    Ok((i, vocal))
+}
+
+fn symbols_header(i: &[u8]) -> ParseResult<'_, SymbolsHeader> {
+    map(fixed(le_i32), |unknown| SymbolsHeader { unknown })(i)
+}
+
+fn symbols_texture(i: &[u8]) -> ParseResult<'_, SymbolsTexture> {
+    let (i, font) = string(128)(i)?;
+    let (i, font_path_length) = le_i32(i)?;
+    let (i, unknown) = le_i32(i)?;
+    let (i, width) = le_i32(i)?;
+    let (i, height) = le_i32(i)?;
+
+    let symbols_texture = SymbolsTexture {
+        font,
+        font_path_length,
+        unknown,
+        width,
+        height,
+    };
+
+    Ok((i, symbols_texture))
+}
+
+fn symbol_definition(i: &[u8]) -> ParseResult<'_, SymbolDefinition> {
+    let (i, text) = string(12)(i)?;
+    let (i, outer) = rect(i)?;
+    let (i, inner) = rect(i)?;
+
+    Ok((i, SymbolDefinition { text, outer, inner }))
+}
+
+fn rect(i: &[u8]) -> ParseResult<'_, Rect> {
+    let (i, y_min) = le_f32(i)?;
+    let (i, x_min) = le_f32(i)?;
+    let (i, y_max) = le_f32(i)?;
+    let (i, x_max) = le_f32(i)?;
+
+    let rect = Rect {
+        y_min,
+        x_min,
+        y_max,
+        x_max,
+    };
+
+    Ok((i, rect))
+}
+
+fn phrase_iteration(i: &[u8]) -> ParseResult<'_, PhraseIteration> {
+    let (i, phrase) = le_i32(i)?;
+    let (i, start_time) = le_f32(i)?;
+    let (i, next_phrase_time) = le_f32(i)?;
+    let (i, difficulty) = fixed(le_i32)(i)?;
+
+    let phrase_iteration = PhraseIteration {
+        phrase,
+        start_time,
+        next_phrase_time,
+        difficulty,
+    };
+
+    Ok((i, phrase_iteration))
+}
+
+fn phrase_extra_info(i: &[u8]) -> ParseResult<'_, PhraseExtraInfo> {
+    let (i, phrase) = le_i32(i)?;
+    let (i, difficulty) = le_i32(i)?;
+    let (i, empty) = le_i32(i)?;
+    let (i, level_jump) = le_u8(i)?;
+    let (i, redundant) = le_i16(i)?;
+    let (i, padding) = le_u8(i)?;
+
+    let phrase_extra_info = PhraseExtraInfo {
+        phrase,
+        difficulty,
+        empty,
+        level_jump,
+        redundant,
+        padding,
+    };
+
+    Ok((i, phrase_extra_info))
+}
+
+fn linked_difficulty(i: &[u8]) -> ParseResult<'_, LinkedDifficulty> {
+    let (i, level_break) = le_i32(i)?;
+    let (i, phrases) = array(le_i32)(i)?;
+
+    Ok((
+        i,
+        LinkedDifficulty {
+            level_break,
+            phrases,
+        },
+    ))
+}
+
+fn action(i: &[u8]) -> ParseResult<'_, Action> {
+    let (i, time) = le_f32(i)?;
+    let (i, name) = string(256)(i)?;
+
+    Ok((i, Action { time, name }))
+}
+
+fn event(i: &[u8]) -> ParseResult<'_, Event> {
+    let (i, time) = le_f32(i)?;
+    let (i, name) = string(256)(i)?;
+
+    Ok((i, Event { time, name }))
+}
+
+fn tone_change(i: &[u8]) -> ParseResult<'_, ToneChange> {
+    let (i, time) = le_f32(i)?;
+    let (i, tone) = le_i32(i)?;
+
+    Ok((i, ToneChange { time, tone }))
+}
+
+fn dna(i: &[u8]) -> ParseResult<'_, Dna> {
+    let (i, time) = le_f32(i)?;
+    let (i, id) = le_i32(i)?;
+
+    Ok((i, Dna { time, id }))
+}
+
+fn section(i: &[u8]) -> ParseResult<'_, Section> {
+    let (i, name) = string(32)(i)?;
+    let (i, number) = le_i32(i)?;
+    let (i, start_time) = le_f32(i)?;
+    let (i, end_time) = le_f32(i)?;
+    let (i, start_phrase_iteration) = le_i32(i)?;
+    let (i, end_phrase_iteration) = le_i32(i)?;
+    let (i, string_mask) = fixed(le_u8)(i)?;
+
+    let section = Section {
+        name,
+        number,
+        start_time,
+        end_time,
+        start_phrase_iteration,
+        end_phrase_iteration,
+        string_mask,
+    };
+
+    Ok((i, section))
+}
+
+fn arrangement(i: &[u8]) -> ParseResult<'_, Arrangement> {
+    let (i, difficulty) = le_i32(i)?;
+    let (i, anchors) = context("sng anchors", array(anchor))(i)?;
+    let (i, anchor_extensions) = context("sng anchor extensions", array(anchor_extension))(i)?;
+    let (i, hand_shapes) = context("sng hand shapes", array(hand_shape))(i)?;
+    let (i, arpeggios) = context("sng arpeggios", array(hand_shape))(i)?;
+    let (i, notes) = context("sng notes", array(note))(i)?;
+    let (i, average_notes_per_iteration) = array(le_f32)(i)?;
+    let (i, notes_in_iteration_unignored) = array(le_i32)(i)?;
+    let (i, notes_in_iteration) = array(le_i32)(i)?;
+
+    let arrangement = Arrangement {
+        difficulty,
+        anchors,
+        anchor_extensions,
+        hand_shapes,
+        arpeggios,
+        notes,
+        average_notes_per_iteration,
+        notes_in_iteration_unignored,
+        notes_in_iteration,
+    };
+
+    Ok((i, arrangement))
+}
+
+fn anchor(i: &[u8]) -> ParseResult<'_, Anchor> {
+    let (i, start_time) = le_f32(i)?;
+    let (i, end_time) = le_f32(i)?;
+    let (i, first_note_time) = le_f32(i)?;
+    let (i, last_note_time) = le_f32(i)?;
+    let (i, fret) = le_i8(i)?;
+    let (i, padding) = fixed(le_u8)(i)?;
+    let (i, width) = le_i32(i)?;
+    let (i, phrase_iteration) = le_i32(i)?;
+
+    let anchor = Anchor {
+        start_time,
+        end_time,
+        first_note_time,
+        last_note_time,
+        fret,
+        padding,
+        width,
+        phrase_iteration,
+    };
+
+    Ok((i, anchor))
+}
+
+fn anchor_extension(i: &[u8]) -> ParseResult<'_, AnchorExtension> {
+    let (i, time) = le_f32(i)?;
+    let (i, fret) = le_i8(i)?;
+    let (i, unknown) = fixed(le_u8)(i)?;
+
+    Ok((
+        i,
+        AnchorExtension {
+            time,
+            fret,
+            unknown,
+        },
+    ))
+}
+
+fn hand_shape(i: &[u8]) -> ParseResult<'_, HandShape> {
+    let (i, chord) = le_i32(i)?;
+    let (i, start_time) = le_f32(i)?;
+    let (i, end_time) = le_f32(i)?;
+    let (i, first_note_time) = le_f32(i)?;
+    let (i, last_note_time) = le_f32(i)?;
+
+    let hand_shape = HandShape {
+        chord,
+        start_time,
+        end_time,
+        first_note_time,
+        last_note_time,
+    };
+
+    Ok((i, hand_shape))
+}
+
+fn note(i: &[u8]) -> ParseResult<'_, SngNote> {
+    let (i, mask) = le_u32(i)?;
+    let (i, flags) = le_u32(i)?;
+    let (i, hash) = le_u32(i)?;
+    let (i, time) = le_f32(i)?;
+    let (i, string) = le_i8(i)?;
+    let (i, fret) = le_i8(i)?;
+    let (i, anchor_fret) = le_i8(i)?;
+    let (i, anchor_width) = le_i8(i)?;
+    let (i, chord) = le_i32(i)?;
+    let (i, chord_notes) = le_i32(i)?;
+    let (i, phrase) = le_i32(i)?;
+    let (i, phrase_iteration) = le_i32(i)?;
+    let (i, hand_shapes) = fixed(le_i16)(i)?;
+    let (i, next_iteration_note) = le_i16(i)?;
+    let (i, previous_iteration_note) = le_i16(i)?;
+    let (i, parent_previous_note) = le_i16(i)?;
+    let (i, slide_to) = le_i8(i)?;
+    let (i, slide_unpitch_to) = le_i8(i)?;
+    let (i, left_hand) = le_i8(i)?;
+    let (i, tap) = le_i8(i)?;
+    let (i, pick_direction) = le_i8(i)?;
+    let (i, slap) = le_i8(i)?;
+    let (i, pluck) = le_i8(i)?;
+    let (i, vibrato) = le_i16(i)?;
+    let (i, sustain) = le_f32(i)?;
+    let (i, max_bend) = le_f32(i)?;
+    let (i, bends) = array(bend_value)(i)?;
+
+    let note = SngNote {
+        mask,
+        flags,
+        hash,
+        time,
+        string,
+        fret,
+        anchor_fret,
+        anchor_width,
+        chord,
+        chord_notes,
+        phrase,
+        phrase_iteration,
+        hand_shapes,
+        next_iteration_note,
+        previous_iteration_note,
+        parent_previous_note,
+        slide_to,
+        slide_unpitch_to,
+        left_hand,
+        tap,
+        pick_direction,
+        slap,
+        pluck,
+        vibrato,
+        sustain,
+        max_bend,
+        bends,
+    };
+
+    Ok((i, note))
+}
+
+fn metadata(i: &[u8]) -> ParseResult<'_, Metadata> {
+    let (i, max_score) = le_f64(i)?;
+    let (i, max_notes_and_chords) = le_f64(i)?;
+    let (i, max_notes_and_chords_real) = le_f64(i)?;
+    let (i, points_per_note) = le_f64(i)?;
+    let (i, first_beat_length) = le_f32(i)?;
+    let (i, start_time) = le_f32(i)?;
+    let (i, capo) = le_i8(i)?;
+    let (i, last_conversion_date_time) = string(32)(i)?;
+    let (i, part) = le_i16(i)?;
+    let (i, song_length) = le_f32(i)?;
+    let (i, tuning) = context("sng tuning", array(le_i16))(i)?;
+    let (i, first_note_time) = le_f32(i)?;
+    let (i, first_note_time_copy) = le_f32(i)?;
+    let (i, max_difficulty) = le_i32(i)?;
+
+    let metadata = Metadata {
+        max_score,
+        max_notes_and_chords,
+        max_notes_and_chords_real,
+        points_per_note,
+        first_beat_length,
+        start_time,
+        capo,
+        last_conversion_date_time,
+        part,
+        song_length,
+        tuning,
+        first_note_time,
+        first_note_time_copy,
+        max_difficulty,
+    };
+
+    Ok((i, metadata))
+}
+
+/// Array prefixed with the amount of items.
+fn array<'a, O, F>(f: F) -> impl FnMut(&'a [u8]) -> ParseResult<'a, Vec<O>>
+where
+    F: FnMut(&'a [u8]) -> ParseResult<'a, O>,
+{
+    length_count(le_u32, f)
+}
+
+/// Array with a fixed amount of items.
+fn fixed<'a, O, F, const N: usize>(mut f: F) -> impl FnMut(&'a [u8]) -> ParseResult<'a, [O; N]>
+where
+    O: Default + Copy,
+    F: FnMut(&'a [u8]) -> ParseResult<'a, O>,
+{
+    move |mut i| {
+        let mut values = [O::default(); N];
+        for value in values.iter_mut() {
+            (i, *value) = f(i)?;
+        }
+
+        Ok((i, values))
+    }
+}
+
+/// Fixed size string padded with zeros.
+fn string<'a>(length: usize) -> impl FnMut(&'a [u8]) -> ParseResult<'a, String> {
+    map(take(length), |bytes: &[u8]| {
+        let end = bytes.iter().position(|c| *c == 0).unwrap_or(bytes.len());
+
+        String::from_utf8_lossy(&bytes[..end]).into_owned()
+    })
+}
@@ -0,0 +1,300 @@
+use super::{
+    Action, Anchor, AnchorExtension, Arrangement, Beat, BendValue, ChordNotes, ChordTemplate, Dna,
+    Event, HandShape, LinkedDifficulty, Metadata, Phrase, PhraseExtraInfo, PhraseIteration, Rect,
+    Section, Sng, SngNote, SymbolDefinition, SymbolsHeader, SymbolsTexture, ToneChange, Vocal,
+};
+
+/// Maximum amount of points of a bend in the chord notes.
+const CHORD_NOTE_BEND_VALUES: usize = 32;
+
+/// Value that can be written as little endian bytes.
+trait Field {
+    fn write(&self, out: &mut Vec<u8>);
+}
+
+macro_rules! impl_field {
+    ($($ty:ty),*) => {
+        $(
+            impl Field for $ty {
+                fn write(&self, out: &mut Vec<u8>) {
+                    out.extend(self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_field!(u8, i8, i16, u32, i32, f32, f64);
+
+impl<T: Field, const N: usize> Field for [T; N] {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.iter().for_each(|value| value.write(out));
+    }
+}
+
+/// Write the whole payload, this is the inverse of `parse::sng`.
+#[profiling::function]
+pub fn sng(sng: &Sng, out: &mut Vec<u8>) {
+    array(&sng.beats, out, beat);
+    array(&sng.phrases, out, phrase);
+    array(&sng.chord_templates, out, chord_template);
+    array(&sng.chord_notes, out, chord_notes);
+    array(&sng.vocals, out, vocal);
+
+    // The font is only stored for vocal arrangements
+    if !sng.vocals.is_empty() {
+        array(&sng.symbols_headers, out, symbols_header);
+        array(&sng.symbols_textures, out, symbols_texture);
+        array(&sng.symbol_definitions, out, symbol_definition);
+    }
+
+    array(&sng.phrase_iterations, out, phrase_iteration);
+    array(&sng.phrase_extra_info, out, phrase_extra_info);
+    array(&sng.linked_difficulties, out, linked_difficulty);
+    array(&sng.actions, out, action);
+    array(&sng.events, out, event);
+    array(&sng.tone_changes, out, tone_change);
+    array(&sng.dnas, out, dna);
+    array(&sng.sections, out, section);
+    array(&sng.arrangements, out, arrangement);
+    metadata(&sng.metadata, out);
+}
+
+fn beat(beat: &Beat, out: &mut Vec<u8>) {
+    beat.time.write(out);
+    beat.measure.write(out);
+    beat.beat.write(out);
+    beat.phrase_iteration.write(out);
+    beat.mask.write(out);
+}
+
+fn phrase(phrase: &Phrase, out: &mut Vec<u8>) {
+    phrase.solo.write(out);
+    phrase.disparity.write(out);
+    phrase.ignore.write(out);
+    phrase.padding.write(out);
+    phrase.max_difficulty.write(out);
+    phrase.phrase_iteration_links.write(out);
+    string(&phrase.name, 32, out);
+}
+
+fn chord_template(chord_template: &ChordTemplate, out: &mut Vec<u8>) {
+    chord_template.mask.write(out);
+    chord_template.frets.write(out);
+    chord_template.fingers.write(out);
+    chord_template.notes.write(out);
+    string(&chord_template.name, 32, out);
+}
+
+fn chord_notes(chord_notes: &ChordNotes, out: &mut Vec<u8>) {
+    chord_notes.masks.write(out);
+    chord_notes
+        .bends
+        .iter()
+        .for_each(|bend_values| chord_note_bend_values(bend_values, out));
+    chord_notes.slide_to.write(out);
+    chord_notes.slide_unpitch_to.write(out);
+    chord_notes.vibrato.write(out);
+}
+
+/// Bends of a chord note are stored in a fixed size array, extra points are dropped.
+fn chord_note_bend_values(bend_values: &[BendValue], out: &mut Vec<u8>) {
+    let used_count = bend_values.len().min(CHORD_NOTE_BEND_VALUES);
+
+    bend_values[..used_count]
+        .iter()
+        .for_each(|value| bend_value(value, out));
+    (used_count..CHORD_NOTE_BEND_VALUES).for_each(|_| bend_value(&BendValue::default(), out));
+
+    (used_count as u32).write(out);
+}
+
+fn bend_value(bend_value: &BendValue, out: &mut Vec<u8>) {
+    bend_value.time.write(out);
+    bend_value.step.write(out);
+    bend_value.unknown.write(out);
+}
+
+fn vocal(vocal: &Vocal, out: &mut Vec<u8>) {
+    vocal.time.write(out);
+    vocal.note.write(out);
+    vocal.length.write(out);
+    string(&vocal.lyric, 48, out);
+}
+
+fn symbols_header(symbols_header: &SymbolsHeader, out: &mut Vec<u8>) {
+    symbols_header.unknown.write(out);
+}
+
+fn symbols_texture(symbols_texture: &SymbolsTexture, out: &mut Vec<u8>) {
+    string(&symbols_texture.font, 128, out);
+    symbols_texture.font_path_length.write(out);
+    symbols_texture.unknown.write(out);
+    symbols_texture.width.write(out);
+    symbols_texture.height.write(out);
+}
+
+fn symbol_definition(symbol_definition: &SymbolDefinition, out: &mut Vec<u8>) {
+    string(&symbol_definition.text, 12, out);
+    rect(&symbol_definition.outer, out);
+    rect(&symbol_definition.inner, out);
+}
+
+fn rect(rect: &Rect, out: &mut Vec<u8>) {
+    rect.y_min.write(out);
+    rect.x_min.write(out);
+    rect.y_max.write(out);
+    rect.x_max.write(out);
+}
+
+fn phrase_iteration(phrase_iteration: &PhraseIteration, out: &mut Vec<u8>) {
+    phrase_iteration.phrase.write(out);
+    phrase_iteration.start_time.write(out);
+    phrase_iteration.next_phrase_time.write(out);
+    phrase_iteration.difficulty.write(out);
+}
+
+fn phrase_extra_info(phrase_extra_info: &PhraseExtraInfo, out: &mut Vec<u8>) {
+    phrase_extra_info.phrase.write(out);
+    phrase_extra_info.difficulty.write(out);
+    phrase_extra_info.empty.write(out);
+    phrase_extra_info.level_jump.write(out);
+    phrase_extra_info.redundant.write(out);
+    phrase_extra_info.padding.write(out);
+}
+
+fn linked_difficulty(linked_difficulty: &LinkedDifficulty, out: &mut Vec<u8>) {
+    linked_difficulty.level_break.write(out);
+    array(&linked_difficulty.phrases, out, i32::write);
+}
+
+fn action(action: &Action, out: &mut Vec<u8>) {
+    action.time.write(out);
+    string(&action.name, 256, out);
+}
+
+fn event(event: &Event, out: &mut Vec<u8>) {
+    event.time.write(out);
+    string(&event.name, 256, out);
+}
+
+fn tone_change(tone_change: &ToneChange, out: &mut Vec<u8>) {
+    tone_change.time.write(out);
+    tone_change.tone.write(out);
+}
+
+fn dna(dna: &Dna, out: &mut Vec<u8>) {
+    dna.time.write(out);
+    dna.id.write(out);
+}
+
+fn section(section: &Section, out: &mut Vec<u8>) {
+    string(&section.name, 32, out);
+    section.number.write(out);
+    section.start_time.write(out);
+    section.end_time.write(out);
+    section.start_phrase_iteration.write(out);
+    section.end_phrase_iteration.write(out);
+    section.string_mask.write(out);
+}
+
+fn arrangement(arrangement: &Arrangement, out: &mut Vec<u8>) {
+    arrangement.difficulty.write(out);
+    array(&arrangement.anchors, out, anchor);
+    array(&arrangement.anchor_extensions, out, anchor_extension);
+    array(&arrangement.hand_shapes, out, hand_shape);
+    array(&arrangement.arpeggios, out, hand_shape);
+    array(&arrangement.notes, out, note);
+    array(&arrangement.average_notes_per_iteration, out, f32::write);
+    array(&arrangement.notes_in_iteration_unignored, out, i32::write);
+    array(&arrangement.notes_in_iteration, out, i32::write);
+}
+
+fn anchor(anchor: &Anchor, out: &mut Vec<u8>) {
+    anchor.start_time.write(out);
+    anchor.end_time.write(out);
+    anchor.first_note_time.write(out);
+    anchor.last_note_time.write(out);
+    anchor.fret.write(out);
+    anchor.padding.write(out);
+    anchor.width.write(out);
+    anchor.phrase_iteration.write(out);
+}
+
+fn anchor_extension(anchor_extension: &AnchorExtension, out: &mut Vec<u8>) {
+    anchor_extension.time.write(out);
+    anchor_extension.fret.write(out);
+    anchor_extension.unknown.write(out);
+}
+
+fn hand_shape(hand_shape: &HandShape, out: &mut Vec<u8>) {
+    hand_shape.chord.write(out);
+    hand_shape.start_time.write(out);
+    hand_shape.end_time.write(out);
+    hand_shape.first_note_time.write(out);
+    hand_shape.last_note_time.write(out);
+}
+
+fn note(note: &SngNote, out: &mut Vec<u8>) {
+    note.mask.write(out);
+    note.flags.write(out);
+    note.hash.write(out);
+    note.time.write(out);
+    note.string.write(out);
+    note.fret.write(out);
+    note.anchor_fret.write(out);
+    note.anchor_width.write(out);
+    note.chord.write(out);
+    note.chord_notes.write(out);
+    note.phrase.write(out);
+    note.phrase_iteration.write(out);
+    note.hand_shapes.write(out);
+    note.next_iteration_note.write(out);
+    note.previous_iteration_note.write(out);
+    note.parent_previous_note.write(out);
+    note.slide_to.write(out);
+    note.slide_unpitch_to.write(out);
+    note.left_hand.write(out);
+    note.tap.write(out);
+    note.pick_direction.write(out);
+    note.slap.write(out);
+    note.pluck.write(out);
+    note.vibrato.write(out);
+    note.sustain.write(out);
+    note.max_bend.write(out);
+    array(&note.bends, out, bend_value);
+}
+
+fn metadata(metadata: &Metadata, out: &mut Vec<u8>) {
+    metadata.max_score.write(out);
+    metadata.max_notes_and_chords.write(out);
+    metadata.max_notes_and_chords_real.write(out);
+    metadata.points_per_note.write(out);
+    metadata.first_beat_length.write(out);
+    metadata.start_time.write(out);
+    metadata.capo.write(out);
+    string(&metadata.last_conversion_date_time, 32, out);
+    metadata.part.write(out);
+    metadata.song_length.write(out);
+    array(&metadata.tuning, out, i16::write);
+    metadata.first_note_time.write(out);
+    metadata.first_note_time_copy.write(out);
+    metadata.max_difficulty.write(out);
+}
+
+/// Array prefixed with the amount of items.
+fn array<T, F>(items: &[T], out: &mut Vec<u8>, mut f: F)
+where
+    F: FnMut(&T, &mut Vec<u8>),
+{
+    (items.len() as u32).write(out);
+    items.iter().for_each(|item| f(item, out));
+}
+
+/// Fixed size string padded with zeros, longer strings are cut off.
+fn string(value: &str, length: usize, out: &mut Vec<u8>) {
+    let bytes = &value.as_bytes()[..value.len().min(length)];
+
+    out.extend(bytes);
+    out.resize(out.len() + length - bytes.len(), 0);
+}
@@ -1,39 +1,875 @@
-use crate::{level::Level, note::Note, song_xml::XmlSong};
+use crate::{
+    beat_grid::BeatGrid,
+    level::Level,
+    manifest::ArrangementProperties,
+    note::Note,
+    sng::{self, Metadata, Sng},
+    song_xml::{
+        XmlArrangementProperties, XmlBeat, XmlChordTemplate, XmlEvent, XmlLinkedDiff,
+        XmlNewLinkedDiff, XmlPhrase, XmlPhraseIteration, XmlPhraseProperty, XmlSection, XmlSong,
+        XmlTone,
+    },
+};
 
 /// The whole song with the different levels.
-#[derive(Debug, Clone)]
+#[derive(Debug, Default, Clone)]
 pub struct Song {
     /// All the levels.
     pub levels: Vec<Level>,
+    /// Beat grid.
+    pub beats: Vec<Beat>,
+    /// Unique phrases, referenced by the phrase iterations.
+    pub phrases: Vec<Phrase>,
+    /// Every time a phrase is played.
+    pub phrase_iterations: Vec<PhraseIteration>,
+    /// Phrases whose difficulty changes together.
+    pub linked_difficulties: Vec<LinkedDifficulty>,
+    /// Old style links between phrases, only stored in XML files.
+    pub phrase_links: Vec<PhraseLink>,
+    /// Per level information for phrases.
+    pub phrase_properties: Vec<PhraseProperty>,
+    /// Song sections such as verse and chorus.
+    pub sections: Vec<Section>,
+    /// Chord shapes, referenced by the chords and hand shapes.
+    pub chord_templates: Vec<ChordTemplate>,
+    /// Chord shapes for fret hand mutes, only stored in XML files.
+    pub fret_hand_mute_templates: Vec<ChordTemplate>,
+    /// Timed events such as time signature changes and crowd reactions.
+    pub events: Vec<Event>,
+    /// Tone switches.
+    pub tone_changes: Vec<ToneChange>,
+    /// Offset of every string in half steps from standard tuning.
+    pub tuning: [i16; 6],
+    /// Capo fret, `0` when there's no capo.
+    pub capo: u8,
+    /// Length of the song in seconds.
+    pub song_length: f32,
+    /// Techniques and the path of the arrangement, only stored in XML files.
+    pub arrangement_properties: ArrangementProperties,
 }
 
 impl Song {
-    /// Get all notes for a certain difficulty between two timestamps.
+    /// Get all notes played at a difficulty between two timestamps.
+    ///
+    /// See [`Self::notes_for_difficulty_iter`] for how the difficulty is applied. The levels are
+    /// resolved again on every call, use [`Self::notes_for_difficulty`] to look up notes often.
     pub fn notes_between_time_iter(
         &self,
         start_time: f32,
         end_time: f32,
         difficulty: u8,
     ) -> impl Iterator<Item = &Note> {
-        self.levels
-            .iter()
-            .filter(move |level| level.difficulty <= difficulty)
-            .flat_map(move |level| level.notes_between_time_iter(start_time, end_time))
+        self.notes_for_difficulty_iter(difficulty)
+            .filter(move |note| note.time >= start_time && note.time < end_time)
+    }
+
+    /// Resolve the notes played at a difficulty once, sorted by time.
+    ///
+    /// See [`Self::notes_for_difficulty_iter`] for how the difficulty is applied.
+    pub fn notes_for_difficulty(&self, difficulty: u8) -> DifficultyNotes {
+        let mut notes = self
+            .notes_for_difficulty_iter(difficulty)
+            .cloned()
+            .collect::<Vec<_>>();
+        notes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        DifficultyNotes { difficulty, notes }
+    }
+
+    /// Get all notes played at a difficulty.
+    ///
+    /// Every phrase iteration uses the highest level not above the difficulty and the highest
+    /// level of its phrase.
+    pub fn notes_for_difficulty_iter(&self, difficulty: u8) -> impl Iterator<Item = &Note> {
+        self.notes_for_phrase_difficulties_iter(move |_phrase| difficulty)
+    }
+
+    /// Get all notes played with a separate difficulty for every phrase.
+    ///
+    /// The closure receives the index of the phrase and returns the chosen difficulty for it,
+    /// which is capped by the highest level of the phrase.
+    pub fn notes_for_phrase_difficulties_iter<F>(
+        &self,
+        mut phrase_difficulty: F,
+    ) -> impl Iterator<Item = &Note>
+    where
+        F: FnMut(usize) -> u8,
+    {
+        // Without phrases there's nothing to limit the levels with
+        let ranges = if self.phrase_iterations.is_empty() {
+            vec![(
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+                self.level_at_most(u8::MAX),
+            )]
+        } else {
+            self.phrase_iterations
+                .iter()
+                .enumerate()
+                .map(|(index, iteration)| {
+                    // Notes before the first phrase iteration belong to it
+                    let start_time = if index == 0 {
+                        f32::NEG_INFINITY
+                    } else {
+                        iteration.time
+                    };
+                    let end_time = self
+                        .phrase_iterations
+                        .get(index + 1)
+                        .map_or(f32::INFINITY, |next| next.time);
+
+                    let level =
+                        self.phrase_level(iteration.phrase, phrase_difficulty(iteration.phrase));
+
+                    (start_time, end_time, level)
+                })
+                .collect()
+        };
+
+        ranges
+            .into_iter()
+            .filter_map(|(start_time, end_time, level)| {
+                level.map(|level| level.notes_between_time_iter(start_time, end_time))
+            })
+            .flatten()
     }
 
     /// Get all notes.
     pub fn notes_iter(&self) -> impl Iterator<Item = &Note> {
         self.levels.iter().flat_map(move |level| level.notes_iter())
     }
+
+    /// Tempo map from the beats.
+    pub fn beat_grid(&self) -> BeatGrid {
+        BeatGrid::new(&self.beats)
+    }
+
+    /// Level played for a phrase, capped by the highest level of the phrase.
+    fn phrase_level(&self, phrase: usize, difficulty: u8) -> Option<&Level> {
+        let max_difficulty = self
+            .phrases
+            .get(phrase)
+            .map_or(u8::MAX, |phrase| phrase.max_difficulty);
+
+        self.level_at_most(difficulty.min(max_difficulty))
+    }
+
+    /// Hardest level that's not above the difficulty.
+    fn level_at_most(&self, difficulty: u8) -> Option<&Level> {
+        self.levels
+            .iter()
+            .filter(|level| level.difficulty <= difficulty)
+            .max_by_key(|level| level.difficulty)
+    }
+
+    /// Index of the phrase iteration playing at the time, `-1` before the first one.
+    pub(crate) fn phrase_iteration_at(&self, time: f32) -> i32 {
+        self.phrase_iterations
+            .iter()
+            .take_while(|iteration| iteration.time <= time)
+            .count() as i32
+            - 1
+    }
+}
+
+/// Notes played at a single difficulty, sorted by time.
+#[derive(Debug, Default, Clone)]
+pub struct DifficultyNotes {
+    /// Difficulty the notes are resolved for.
+    pub difficulty: u8,
+    /// The notes sorted by time.
+    pub notes: Vec<Note>,
+}
+
+impl DifficultyNotes {
+    /// Get the notes between two timestamps.
+    pub fn between(&self, start_time: f32, end_time: f32) -> &[Note] {
+        let start = self.notes.partition_point(|note| note.time < start_time);
+        let end = self.notes.partition_point(|note| note.time < end_time);
+
+        &self.notes[start..end.max(start)]
+    }
+}
+
+/// Single beat of the beat grid.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Beat {
+    /// Time in seconds.
+    pub time: f32,
+    /// Measure number, only set on the first beat of a measure.
+    pub measure: Option<u16>,
+}
+
+/// Phrase, a reusable part of the song with it's own difficulty levels.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Phrase {
+    /// Name of the phrase.
+    pub name: String,
+    /// Highest difficulty level of this phrase.
+    pub max_difficulty: u8,
+    /// Whether the difficulty levels jump.
+    pub disparity: bool,
+    /// Whether the phrase is ignored for scoring.
+    pub ignore: bool,
+    /// Whether the phrase is a solo.
+    pub solo: bool,
+}
+
+/// Single time a phrase is played.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PhraseIteration {
+    /// Start time in seconds.
+    pub time: f32,
+    /// Index of the phrase.
+    pub phrase: usize,
+    /// Difficulty levels for the easy, medium and hard presets.
+    pub hero_levels: [u8; 3],
+}
+
+/// Phrases that change difficulty together.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LinkedDifficulty {
+    /// Level where the link breaks, `None` when it never breaks.
+    pub level_break: Option<u8>,
+    /// Indices of the linked phrases.
+    pub phrases: Vec<usize>,
+}
+
+/// Phrase whose difficulty follows another phrase.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PhraseLink {
+    /// Index of the phrase that follows.
+    pub child: usize,
+    /// Index of the phrase that is followed.
+    pub parent: usize,
+}
+
+/// Information about a phrase on a specific level.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PhraseProperty {
+    /// Index of the phrase.
+    pub phrase: usize,
+    /// Difficulty level.
+    pub difficulty: u8,
+    /// Whether this level is the same as the one before.
+    pub redundant: bool,
+    /// Amount of levels jumped.
+    pub level_jump: u8,
+    /// Whether the phrase is empty on this level.
+    pub empty: bool,
+}
+
+/// Section of the song such as verse and chorus.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Section {
+    /// Name of the section.
+    pub name: String,
+    /// How many times a section with this name has occurred.
+    pub number: u32,
+    /// Start time in seconds.
+    pub start_time: f32,
+}
+
+/// Shape of a chord.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChordTemplate {
+    /// Name of the chord.
+    pub name: String,
+    /// Name shown in the game, ends with `"-arp"` for arpeggios.
+    pub display_name: String,
+    /// Fret for every string, `None` when the string isn't played.
+    pub frets: [Option<u8>; 6],
+    /// Finger for every string, `None` when the string isn't played or it's open.
+    pub fingers: [Option<u8>; 6],
+}
+
+impl ChordTemplate {
+    /// Whether the shape is played as an arpeggio instead of a chord.
+    pub fn is_arpeggio(&self) -> bool {
+        self.display_name.ends_with("-arp")
+    }
+}
+
+/// Timed event.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Event {
+    /// Time in seconds.
+    pub time: f32,
+    /// What happens, such as `"TS:3/4"` for a time signature change.
+    pub code: String,
+}
+
+/// Switch to another tone.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ToneChange {
+    /// Time in seconds.
+    pub time: f32,
+    /// Index of the tone, `0` for the first to `3` for the fourth tone.
+    pub id: u8,
 }
 
 impl From<XmlSong> for Song {
     fn from(xml: XmlSong) -> Self {
-        let levels = xml
-            .into_levels_iter()
-            .map(|xml_level| Level::from(xml_level))
+        let beats = xml.ebeats.beats.iter().map(Beat::from).collect();
+        let phrases = xml.phrases.phrases.iter().map(Phrase::from).collect();
+        let phrase_iterations = xml
+            .phrase_iterations
+            .phrase_iterations
+            .iter()
+            .map(PhraseIteration::from)
+            .collect();
+        let linked_difficulties = xml
+            .new_linked_diffs
+            .new_linked_diffs
+            .iter()
+            .map(LinkedDifficulty::from)
             .collect();
+        let phrase_links = xml
+            .linked_diffs
+            .linked_diffs
+            .iter()
+            .map(PhraseLink::from)
+            .collect();
+        let phrase_properties = xml
+            .phrase_properties
+            .phrase_properties
+            .iter()
+            .map(PhraseProperty::from)
+            .collect();
+        let sections = xml.sections.sections.iter().map(Section::from).collect();
+        let chord_templates = xml
+            .chord_templates
+            .chord_templates
+            .iter()
+            .map(ChordTemplate::from)
+            .collect();
+        let fret_hand_mute_templates = xml
+            .fret_hand_mute_templates
+            .fret_hand_mute_templates
+            .iter()
+            .map(ChordTemplate::from)
+            .collect();
+        let events = xml.events.events.iter().map(Event::from).collect();
+        let tone_changes = xml.tones.tones.iter().map(ToneChange::from).collect();
+        let tuning = xml.tuning.to_array();
+        let capo = xml.capo.max(0) as u8;
+        let song_length = xml.song_length;
+        let arrangement_properties = ArrangementProperties::from(&xml.arrangement_properties);
+
+        let levels = xml.into_levels_iter().map(Level::from).collect();
+
+        Self {
+            levels,
+            beats,
+            phrases,
+            phrase_iterations,
+            linked_difficulties,
+            phrase_links,
+            phrase_properties,
+            sections,
+            chord_templates,
+            fret_hand_mute_templates,
+            events,
+            tone_changes,
+            tuning,
+            capo,
+            song_length,
+            arrangement_properties,
+        }
+    }
+}
+
+impl From<Sng> for Song {
+    fn from(sng: Sng) -> Self {
+        let levels = sng
+            .arrangements
+            .iter()
+            .map(|arrangement| Level::from_sng(arrangement, &sng))
+            .collect();
+
+        let mut tuning = [0; 6];
+        tuning
+            .iter_mut()
+            .zip(sng.metadata.tuning.iter())
+            .for_each(|(string, offset)| *string = *offset);
+
+        Self {
+            levels,
+            beats: sng.beats.iter().map(Beat::from).collect(),
+            phrases: sng.phrases.iter().map(Phrase::from).collect(),
+            phrase_iterations: sng
+                .phrase_iterations
+                .iter()
+                .map(PhraseIteration::from)
+                .collect(),
+            linked_difficulties: sng
+                .linked_difficulties
+                .iter()
+                .map(LinkedDifficulty::from)
+                .collect(),
+            phrase_properties: sng
+                .phrase_extra_info
+                .iter()
+                .map(PhraseProperty::from)
+                .collect(),
+            sections: sng.sections.iter().map(Section::from).collect(),
+            chord_templates: sng
+                .chord_templates
+                .iter()
+                .map(ChordTemplate::from)
+                .collect(),
+            events: sng.events.iter().map(Event::from).collect(),
+            tone_changes: sng.tone_changes.iter().map(ToneChange::from).collect(),
+            tuning,
+            capo: sng.metadata.capo.max(0) as u8,
+            song_length: sng.metadata.song_length,
+            // Only stored in XML files
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&Song> for Sng {
+    /// Information that isn't in the song, such as the note hashes and the fingerprints, is left
+    /// empty.
+    fn from(song: &Song) -> Self {
+        let mut sng = Sng {
+            beats: beats_to_sng(song),
+            phrases: song
+                .phrases
+                .iter()
+                .enumerate()
+                .map(|(index, phrase)| sng::Phrase {
+                    solo: phrase.solo as u8,
+                    disparity: phrase.disparity as u8,
+                    ignore: phrase.ignore as u8,
+                    max_difficulty: phrase.max_difficulty as i32,
+                    phrase_iteration_links: song
+                        .phrase_iterations
+                        .iter()
+                        .filter(|iteration| iteration.phrase == index)
+                        .count() as i32,
+                    name: phrase.name.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            chord_templates: song
+                .chord_templates
+                .iter()
+                .map(|template| sng::ChordTemplate {
+                    mask: if template.is_arpeggio() {
+                        sng::CHORD_MASK_ARPEGGIO
+                    } else if template.display_name.ends_with("-nop") {
+                        sng::CHORD_MASK_NOP
+                    } else {
+                        0
+                    },
+                    frets: template
+                        .frets
+                        .map(|fret| fret.map_or(-1, |fret| fret as i8)),
+                    fingers: template
+                        .fingers
+                        .map(|finger| finger.map_or(-1, |finger| finger as i8)),
+                    name: template.name.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            phrase_iterations: song
+                .phrase_iterations
+                .iter()
+                .enumerate()
+                .map(|(index, iteration)| sng::PhraseIteration {
+                    phrase: iteration.phrase as i32,
+                    start_time: iteration.time,
+                    next_phrase_time: song
+                        .phrase_iterations
+                        .get(index + 1)
+                        .map_or(song.song_length, |next| next.time),
+                    difficulty: iteration.hero_levels.map(|level| level as i32),
+                })
+                .collect(),
+            phrase_extra_info: song
+                .phrase_properties
+                .iter()
+                .map(|property| sng::PhraseExtraInfo {
+                    phrase: property.phrase as i32,
+                    difficulty: property.difficulty as i32,
+                    empty: property.empty as i32,
+                    level_jump: property.level_jump,
+                    redundant: property.redundant as i16,
+                    padding: 0,
+                })
+                .collect(),
+            linked_difficulties: song
+                .linked_difficulties
+                .iter()
+                .map(|linked| sng::LinkedDifficulty {
+                    level_break: linked.level_break.map_or(-1, i32::from),
+                    phrases: linked.phrases.iter().map(|phrase| *phrase as i32).collect(),
+                })
+                .collect(),
+            events: song
+                .events
+                .iter()
+                .map(|event| sng::Event {
+                    time: event.time,
+                    name: event.code.clone(),
+                })
+                .collect(),
+            tone_changes: song
+                .tone_changes
+                .iter()
+                .map(|tone_change| sng::ToneChange {
+                    time: tone_change.time,
+                    tone: tone_change.id as i32,
+                })
+                .collect(),
+            sections: song
+                .sections
+                .iter()
+                .enumerate()
+                .map(|(index, section)| {
+                    let end_time = song
+                        .sections
+                        .get(index + 1)
+                        .map_or(song.song_length, |next| next.start_time);
+
+                    sng::Section {
+                        name: section.name.clone(),
+                        number: section.number as i32,
+                        start_time: section.start_time,
+                        end_time,
+                        start_phrase_iteration: song.phrase_iteration_at(section.start_time),
+                        end_phrase_iteration: song.phrase_iteration_at(end_time),
+                        string_mask: [0; 36],
+                    }
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let arrangements = song
+            .levels
+            .iter()
+            .map(|level| level.to_sng(song, &mut sng))
+            .collect::<Vec<_>>();
+        sng.arrangements = arrangements;
+
+        let hardest = sng
+            .arrangements
+            .iter()
+            .max_by_key(|arrangement| arrangement.difficulty);
+        let note_count = hardest.map_or(0, |arrangement| arrangement.notes.len());
+        let first_note_time = song
+            .notes_iter()
+            .map(|note| note.time)
+            .reduce(f32::min)
+            .unwrap_or_default();
+
+        sng.metadata = Metadata {
+            max_score: 100_000.0,
+            max_notes_and_chords: note_count as f64,
+            max_notes_and_chords_real: note_count as f64,
+            points_per_note: 100_000.0 / note_count.max(1) as f64,
+            capo: song.capo as i8,
+            song_length: song.song_length,
+            tuning: song.tuning.to_vec(),
+            first_note_time,
+            first_note_time_copy: first_note_time,
+            max_difficulty: hardest.map_or(0, |arrangement| arrangement.difficulty),
+            ..Default::default()
+        };
+
+        sng
+    }
+}
+
+/// Convert the beats, the SNG stores the position in the measure for every beat.
+fn beats_to_sng(song: &Song) -> Vec<sng::Beat> {
+    let mut measure = 0;
+    let mut beat_in_measure = 0;
+
+    song.beats
+        .iter()
+        .map(|beat| {
+            let mut mask = 0;
+            match beat.measure {
+                Some(number) => {
+                    measure = number as i16;
+                    beat_in_measure = 0;
+
+                    mask |= sng::BEAT_MASK_FIRST;
+                    if measure % 2 == 0 {
+                        mask |= sng::BEAT_MASK_EVEN_MEASURE;
+                    }
+                }
+                None => beat_in_measure += 1,
+            }
+
+            sng::Beat {
+                time: beat.time,
+                measure,
+                beat: beat_in_measure,
+                phrase_iteration: song.phrase_iteration_at(beat.time).max(0),
+                mask,
+            }
+        })
+        .collect()
+}
+
+impl From<&XmlBeat> for Beat {
+    fn from(xml: &XmlBeat) -> Self {
+        Self {
+            time: xml.time,
+            measure: xml.measure.and_then(|measure| u16::try_from(measure).ok()),
+        }
+    }
+}
+
+impl From<&sng::Beat> for Beat {
+    fn from(beat: &sng::Beat) -> Self {
+        Self {
+            time: beat.time,
+            measure: (beat.mask & sng::BEAT_MASK_FIRST != 0)
+                .then_some(beat.measure)
+                .and_then(|measure| u16::try_from(measure).ok()),
+        }
+    }
+}
+
+impl From<&XmlPhrase> for Phrase {
+    fn from(xml: &XmlPhrase) -> Self {
+        Self {
+            name: xml.name.clone(),
+            max_difficulty: xml.max_difficulty,
+            disparity: xml.disparity,
+            ignore: xml.ignore,
+            solo: xml.solo,
+        }
+    }
+}
+
+impl From<&sng::Phrase> for Phrase {
+    fn from(phrase: &sng::Phrase) -> Self {
+        Self {
+            name: phrase.name.clone(),
+            max_difficulty: phrase.max_difficulty as u8,
+            disparity: phrase.disparity != 0,
+            ignore: phrase.ignore != 0,
+            solo: phrase.solo != 0,
+        }
+    }
+}
+
+impl From<&XmlPhraseIteration> for PhraseIteration {
+    fn from(xml: &XmlPhraseIteration) -> Self {
+        let mut hero_levels = [0; 3];
+        for hero_level in xml.hero_levels.hero_levels.iter() {
+            if let Some(level) = hero_levels.get_mut((hero_level.hero as usize).wrapping_sub(1)) {
+                *level = hero_level.difficulty;
+            }
+        }
+
+        Self {
+            time: xml.time,
+            phrase: xml.phrase_id,
+            hero_levels,
+        }
+    }
+}
+
+impl From<&sng::PhraseIteration> for PhraseIteration {
+    fn from(iteration: &sng::PhraseIteration) -> Self {
+        Self {
+            time: iteration.start_time,
+            phrase: iteration.phrase as usize,
+            hero_levels: iteration.difficulty.map(|level| level as u8),
+        }
+    }
+}
+
+impl From<&XmlNewLinkedDiff> for LinkedDifficulty {
+    fn from(xml: &XmlNewLinkedDiff) -> Self {
+        Self {
+            level_break: u8::try_from(xml.level_break).ok(),
+            phrases: xml.phrases.iter().map(|phrase| phrase.id).collect(),
+        }
+    }
+}
+
+impl From<&sng::LinkedDifficulty> for LinkedDifficulty {
+    fn from(linked: &sng::LinkedDifficulty) -> Self {
+        Self {
+            level_break: u8::try_from(linked.level_break).ok(),
+            phrases: linked
+                .phrases
+                .iter()
+                .map(|phrase| *phrase as usize)
+                .collect(),
+        }
+    }
+}
+
+impl From<&XmlLinkedDiff> for PhraseLink {
+    fn from(xml: &XmlLinkedDiff) -> Self {
+        Self {
+            child: xml.child_id,
+            parent: xml.parent_id,
+        }
+    }
+}
+
+impl From<&XmlPhraseProperty> for PhraseProperty {
+    fn from(xml: &XmlPhraseProperty) -> Self {
+        Self {
+            phrase: xml.phrase_id,
+            difficulty: xml.difficulty,
+            redundant: xml.redundant,
+            level_jump: xml.level_jump,
+            empty: xml.empty,
+        }
+    }
+}
+
+impl From<&sng::PhraseExtraInfo> for PhraseProperty {
+    fn from(info: &sng::PhraseExtraInfo) -> Self {
+        Self {
+            phrase: info.phrase as usize,
+            difficulty: info.difficulty as u8,
+            redundant: info.redundant != 0,
+            level_jump: info.level_jump,
+            empty: info.empty != 0,
+        }
+    }
+}
+
+impl From<&XmlSection> for Section {
+    fn from(xml: &XmlSection) -> Self {
+        Self {
+            name: xml.name.clone(),
+            number: xml.number,
+            start_time: xml.start_time,
+        }
+    }
+}
+
+impl From<&sng::Section> for Section {
+    fn from(section: &sng::Section) -> Self {
+        Self {
+            name: section.name.clone(),
+            number: section.number as u32,
+            start_time: section.start_time,
+        }
+    }
+}
+
+impl From<&XmlChordTemplate> for ChordTemplate {
+    fn from(xml: &XmlChordTemplate) -> Self {
+        let positive = |value: Option<i8>| value.and_then(|value| u8::try_from(value).ok());
+
+        Self {
+            name: xml.chord_name.clone(),
+            display_name: xml.display_name.clone(),
+            frets: xml.frets().map(positive),
+            fingers: xml.fingers().map(positive),
+        }
+    }
+}
+
+impl From<&sng::ChordTemplate> for ChordTemplate {
+    fn from(template: &sng::ChordTemplate) -> Self {
+        let positive = |value: i8| u8::try_from(value).ok();
+
+        // The display name isn't stored
+        let display_name = if template.mask & sng::CHORD_MASK_ARPEGGIO != 0 {
+            format!("{}-arp", template.name)
+        } else if template.mask & sng::CHORD_MASK_NOP != 0 {
+            format!("{}-nop", template.name)
+        } else {
+            template.name.clone()
+        };
+
+        Self {
+            name: template.name.clone(),
+            display_name,
+            frets: template.frets.map(positive),
+            fingers: template.fingers.map(positive),
+        }
+    }
+}
+
+impl From<&XmlEvent> for Event {
+    fn from(xml: &XmlEvent) -> Self {
+        Self {
+            time: xml.time,
+            code: xml.code.clone(),
+        }
+    }
+}
+
+impl From<&sng::Event> for Event {
+    fn from(event: &sng::Event) -> Self {
+        Self {
+            time: event.time,
+            code: event.name.clone(),
+        }
+    }
+}
+
+impl From<&XmlTone> for ToneChange {
+    fn from(xml: &XmlTone) -> Self {
+        Self {
+            time: xml.time,
+            id: xml.id,
+        }
+    }
+}
+
+impl From<&sng::ToneChange> for ToneChange {
+    fn from(tone_change: &sng::ToneChange) -> Self {
+        Self {
+            time: tone_change.time,
+            id: tone_change.tone as u8,
+        }
+    }
+}
 
-        Self { levels }
+impl From<&XmlArrangementProperties> for ArrangementProperties {
+    fn from(xml: &XmlArrangementProperties) -> Self {
+        Self {
+            bonus_arr: xml.bonus_arr,
+            metronome: xml.metronome,
+            path_lead: xml.path_lead,
+            path_rhythm: xml.path_rhythm,
+            path_bass: xml.path_bass,
+            route_mask: xml.route_mask,
+            represent: xml.represent,
+            standard_tuning: xml.standard_tuning,
+            non_standard_chords: xml.non_standard_chords,
+            open_chords: xml.open_chords,
+            barre_chords: xml.barre_chords,
+            power_chords: xml.power_chords,
+            drop_d_power: xml.drop_d_power,
+            finger_picking: xml.finger_picking,
+            pick_direction: xml.pick_direction,
+            double_stops: xml.double_stops,
+            palm_mutes: xml.palm_mutes,
+            harmonics: xml.harmonics,
+            pinch_harmonics: xml.pinch_harmonics,
+            hopo: xml.hopo,
+            tremolo: xml.tremolo,
+            slides: xml.slides,
+            unpitched_slides: xml.unpitched_slides,
+            bends: xml.bends,
+            tapping: xml.tapping,
+            vibrato: xml.vibrato,
+            fret_hand_mutes: xml.fret_hand_mutes,
+            slap_pop: xml.slap_pop,
+            two_finger_picking: xml.two_finger_picking,
+            fifths_and_octaves: xml.fifths_and_octaves,
+            syncopation: xml.syncopation,
+            bass_pick: xml.bass_pick,
+            sustain: xml.sustain,
+        }
     }
 }
@@ -1,19 +1,101 @@
+//! Editable Rocksmith 2014 arrangement XML files.
+//!
+//! Only custom songs ship these, official songs only have the binary [`crate::sng`] files.
+
 use serde::Deserialize;
 
 use crate::error::{Result, RocksmithArchiveError};
 
 /// Parsed song information.
 #[derive(Debug, Default, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct XmlSong {
-    version: String,
-    title: String,
-    levels: XmlLevels,
+    /// Version of the format.
+    pub version: String,
+    /// Name of the song.
+    pub title: String,
+    /// Name of the arrangement, such as `"Lead"`.
+    pub arrangement: String,
+    /// Which part of the instrument, such as lead or rhythm.
+    pub part: i32,
+    /// Offset of the song in seconds.
+    pub offset: f32,
+    /// Offset of the tuning in cents from A440.
+    pub cent_offset: f32,
+    /// Length of the song in seconds.
+    pub song_length: f32,
+    /// Name of the song used for sorting.
+    pub song_name_sort: String,
+    /// Time of the first beat in seconds.
+    pub start_beat: f32,
+    /// Average beats per minute.
+    pub average_tempo: f32,
+    /// Offset of every string in half steps from standard tuning.
+    pub tuning: XmlTuning,
+    /// Capo fret, `0` when there's no capo.
+    pub capo: i8,
+    /// Name of the artist.
+    pub artist_name: String,
+    /// Name of the artist used for sorting.
+    pub artist_name_sort: String,
+    /// Name of the album.
+    pub album_name: String,
+    /// Name of the album used for sorting.
+    pub album_name_sort: String,
+    /// Year the album was released.
+    pub album_year: String,
+    /// How enthusiastic the crowd is.
+    pub crowd_speed: i32,
+    /// Techniques and the path of the arrangement.
+    pub arrangement_properties: XmlArrangementProperties,
+    /// When the arrangement was converted.
+    pub last_conversion_date_time: String,
+    /// Unique phrases, referenced by the phrase iterations.
+    pub phrases: XmlPhrases,
+    /// Every time a phrase is played.
+    pub phrase_iterations: XmlPhraseIterations,
+    /// Phrases whose difficulty changes together.
+    pub new_linked_diffs: XmlNewLinkedDiffs,
+    /// Old style links between phrases whose difficulty changes together.
+    pub linked_diffs: XmlLinkedDiffs,
+    /// Per level information for phrases.
+    pub phrase_properties: XmlPhraseProperties,
+    /// Chord shapes, referenced by the chords and hand shapes.
+    pub chord_templates: XmlChordTemplates,
+    /// Chord shapes for fret hand mutes.
+    pub fret_hand_mute_templates: XmlFretHandMuteTemplates,
+    /// Beat grid.
+    pub ebeats: XmlBeats,
+    /// Name of the tone the song starts with.
+    #[serde(rename = "tonebase")]
+    pub tone_base: Option<String>,
+    /// Name of the first tone.
+    #[serde(rename = "tonea")]
+    pub tone_a: Option<String>,
+    /// Name of the second tone.
+    #[serde(rename = "toneb")]
+    pub tone_b: Option<String>,
+    /// Name of the third tone.
+    #[serde(rename = "tonec")]
+    pub tone_c: Option<String>,
+    /// Name of the fourth tone.
+    #[serde(rename = "toned")]
+    pub tone_d: Option<String>,
+    /// Tone switches.
+    pub tones: XmlTones,
+    /// Song sections such as verse and chorus.
+    pub sections: XmlSections,
+    /// Timed events such as time signature changes and crowd reactions.
+    pub events: XmlEvents,
+    /// All notes without difficulty levels.
+    pub transcription_track: XmlLevel,
+    /// The notes for every difficulty level.
+    pub levels: XmlLevels,
 }
+
 impl XmlSong {
     /// Parse the XML string into this object.
     pub fn parse(xml: &str) -> Result<Self> {
-        std::fs::write("/tmp/song.xml", xml).unwrap();
         Ok(quick_xml::de::from_str(xml)?)
     }
 
@@ -22,7 +104,7 @@ impl XmlSong {
         self.levels
             .levels
             .iter()
-            .map(|level| level.difficulty)
+            .map(|level| level.difficulty as u8)
             .max()
     }
 
@@ -31,8 +113,8 @@ impl XmlSong {
         self.levels
             .levels
             .into_iter()
-            .find(|level| level.difficulty == difficulty)
-            .ok_or_else(|| RocksmithArchiveError::NoLevelWithDifficulty(difficulty))
+            .find(|level| level.difficulty == difficulty as i8)
+            .ok_or(RocksmithArchiveError::NoLevelWithDifficulty(difficulty))
     }
 
     /// Get all levels as an iterator.
@@ -41,26 +123,356 @@ impl XmlSong {
     }
 }
 
+/// Offset of every string in half steps from standard tuning.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct XmlTuning {
+    pub string0: i16,
+    pub string1: i16,
+    pub string2: i16,
+    pub string3: i16,
+    pub string4: i16,
+    pub string5: i16,
+}
+
+impl XmlTuning {
+    /// Offsets as an array, starting with the lowest string.
+    pub fn to_array(&self) -> [i16; 6] {
+        [
+            self.string0,
+            self.string1,
+            self.string2,
+            self.string3,
+            self.string4,
+            self.string5,
+        ]
+    }
+}
+
+/// Techniques and the path of the arrangement.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct XmlArrangementProperties {
+    pub represent: bool,
+    pub bonus_arr: bool,
+    pub standard_tuning: bool,
+    pub non_standard_chords: bool,
+    pub barre_chords: bool,
+    pub power_chords: bool,
+    pub drop_d_power: bool,
+    pub open_chords: bool,
+    pub finger_picking: bool,
+    pub pick_direction: bool,
+    pub double_stops: bool,
+    pub palm_mutes: bool,
+    pub harmonics: bool,
+    pub pinch_harmonics: bool,
+    pub hopo: bool,
+    pub tremolo: bool,
+    pub slides: bool,
+    pub unpitched_slides: bool,
+    pub bends: bool,
+    pub tapping: bool,
+    pub vibrato: bool,
+    pub fret_hand_mutes: bool,
+    pub slap_pop: bool,
+    pub two_finger_picking: bool,
+    pub fifths_and_octaves: bool,
+    pub syncopation: bool,
+    pub bass_pick: bool,
+    pub sustain: bool,
+    #[serde(rename = "Metronome")]
+    pub metronome: bool,
+    pub path_lead: bool,
+    pub path_rhythm: bool,
+    pub path_bass: bool,
+    pub route_mask: u8,
+}
+
+/// Newtype for the phrases.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlPhrases {
+    #[serde(rename = "phrase", default)]
+    pub phrases: Vec<XmlPhrase>,
+}
+
+/// Phrase, a reusable part of the song with it's own difficulty levels.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct XmlPhrase {
+    /// Name of the phrase.
+    pub name: String,
+    /// Highest difficulty level of this phrase.
+    pub max_difficulty: u8,
+    /// Whether the difficulty levels jump.
+    pub disparity: bool,
+    /// Whether the phrase is ignored for scoring.
+    pub ignore: bool,
+    /// Whether the phrase is a solo.
+    pub solo: bool,
+}
+
+/// Newtype for the phrase iterations.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlPhraseIterations {
+    #[serde(rename = "phraseIteration", default)]
+    pub phrase_iterations: Vec<XmlPhraseIteration>,
+}
+
+/// Single time a phrase is played.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct XmlPhraseIteration {
+    /// Start time in seconds.
+    pub time: f32,
+    /// Index of the phrase.
+    pub phrase_id: usize,
+    /// Name of the variation.
+    pub variation: String,
+    /// Difficulty levels for the easy, medium and hard presets.
+    pub hero_levels: XmlHeroLevels,
+}
+
+/// Newtype for the hero levels.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlHeroLevels {
+    #[serde(rename = "heroLevel", default)]
+    pub hero_levels: Vec<XmlHeroLevel>,
+}
+
+/// Difficulty level for one of the presets.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct XmlHeroLevel {
+    /// Preset, `1` for easy, `2` for medium and `3` for hard.
+    pub hero: u8,
+    /// Difficulty level.
+    pub difficulty: u8,
+}
+
+/// Newtype for the linked difficulties.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlNewLinkedDiffs {
+    #[serde(rename = "newLinkedDiff", default)]
+    pub new_linked_diffs: Vec<XmlNewLinkedDiff>,
+}
+
+/// Phrases that change difficulty together.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct XmlNewLinkedDiff {
+    /// Level where the link breaks, `-1` when it never breaks.
+    pub level_break: i32,
+    /// Ratio of the linked phrases.
+    pub ratio: String,
+    /// Amount of linked phrases.
+    pub phrase_count: usize,
+    /// The linked phrases.
+    #[serde(rename = "nld_phrase")]
+    pub phrases: Vec<XmlLinkedPhrase>,
+}
+
+/// Reference to a phrase.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct XmlLinkedPhrase {
+    /// Index of the phrase.
+    pub id: usize,
+}
+
+/// Newtype for the old style linked difficulties.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlLinkedDiffs {
+    #[serde(rename = "linkedDiff", default)]
+    pub linked_diffs: Vec<XmlLinkedDiff>,
+}
+
+/// Phrase whose difficulty follows another phrase.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct XmlLinkedDiff {
+    /// Index of the phrase that follows.
+    pub child_id: usize,
+    /// Index of the phrase that is followed.
+    pub parent_id: usize,
+}
+
+/// Newtype for the phrase properties.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlPhraseProperties {
+    #[serde(rename = "phraseProperty", default)]
+    pub phrase_properties: Vec<XmlPhraseProperty>,
+}
+
+/// Information about a phrase on a specific level.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct XmlPhraseProperty {
+    /// Index of the phrase.
+    pub phrase_id: usize,
+    /// Difficulty level.
+    pub difficulty: u8,
+    /// Whether this level is the same as the one before.
+    pub redundant: bool,
+    /// Amount of levels jumped.
+    pub level_jump: u8,
+    /// Whether the phrase is empty on this level.
+    pub empty: bool,
+}
+
+/// Newtype for the chord templates.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlChordTemplates {
+    #[serde(rename = "chordTemplate", default)]
+    pub chord_templates: Vec<XmlChordTemplate>,
+}
+
+/// Newtype for the fret hand mute templates.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlFretHandMuteTemplates {
+    #[serde(rename = "fretHandMuteTemplate", default)]
+    pub fret_hand_mute_templates: Vec<XmlChordTemplate>,
+}
+
+/// Shape of a chord, strings that aren't played are missing.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct XmlChordTemplate {
+    /// Name of the chord.
+    pub chord_name: String,
+    /// Name shown in the game, ends with `"-arp"` for arpeggios.
+    pub display_name: String,
+    pub fret0: Option<i8>,
+    pub fret1: Option<i8>,
+    pub fret2: Option<i8>,
+    pub fret3: Option<i8>,
+    pub fret4: Option<i8>,
+    pub fret5: Option<i8>,
+    pub finger0: Option<i8>,
+    pub finger1: Option<i8>,
+    pub finger2: Option<i8>,
+    pub finger3: Option<i8>,
+    pub finger4: Option<i8>,
+    pub finger5: Option<i8>,
+}
+
+impl XmlChordTemplate {
+    /// Fret for every string, `None` or `-1` when the string isn't played.
+    pub fn frets(&self) -> [Option<i8>; 6] {
+        [
+            self.fret0, self.fret1, self.fret2, self.fret3, self.fret4, self.fret5,
+        ]
+    }
+
+    /// Finger for every string, `None` or `-1` when the string isn't played.
+    pub fn fingers(&self) -> [Option<i8>; 6] {
+        [
+            self.finger0,
+            self.finger1,
+            self.finger2,
+            self.finger3,
+            self.finger4,
+            self.finger5,
+        ]
+    }
+}
+
+/// Newtype for the beats.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlBeats {
+    #[serde(rename = "ebeat", default)]
+    pub beats: Vec<XmlBeat>,
+}
+
+/// Single beat of the beat grid.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct XmlBeat {
+    /// Time in seconds.
+    pub time: f32,
+    /// Measure number, only set on the first beat of a measure.
+    ///
+    /// Some tools write `-1` for the other beats.
+    pub measure: Option<i16>,
+}
+
+/// Newtype for the tone switches.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlTones {
+    #[serde(rename = "tone", default)]
+    pub tones: Vec<XmlTone>,
+}
+
+/// Switch to another tone.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct XmlTone {
+    /// Time in seconds.
+    pub time: f32,
+    /// Index of the tone, `0` for `tonea` to `3` for `toned`.
+    pub id: u8,
+    /// Name of the tone.
+    pub name: String,
+}
+
+/// Newtype for the sections.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlSections {
+    #[serde(rename = "section", default)]
+    pub sections: Vec<XmlSection>,
+}
+
+/// Section of the song such as verse and chorus.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct XmlSection {
+    /// Name of the section.
+    pub name: String,
+    /// How many times a section with this name has occurred.
+    pub number: u32,
+    /// Start time in seconds.
+    pub start_time: f32,
+}
+
+/// Newtype for the events.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlEvents {
+    #[serde(rename = "event", default)]
+    pub events: Vec<XmlEvent>,
+}
+
+/// Timed event.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct XmlEvent {
+    /// Time in seconds.
+    pub time: f32,
+    /// What happens, such as `"TS:3/4"` for a time signature change.
+    pub code: String,
+}
+
 /// Newtype for levels with different difficulties.
 #[derive(Debug, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct XmlLevels {
-    #[serde(rename = "level")]
-    levels: Vec<XmlLevel>,
+    #[serde(rename = "level", default)]
+    pub levels: Vec<XmlLevel>,
 }
 
 /// Information for a single level.
 #[derive(Debug, Default, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct XmlLevel {
-    /// Difficulty rating of this level.
-    pub difficulty: u8,
-    /// Camera positions.
-    //anchors: XmlAnchors,
+    /// Difficulty rating of this level, `-1` for the transcription track.
+    pub difficulty: i8,
     /// Notes.
-    notes: XmlNotes,
+    pub notes: XmlNotes,
     /// Chords.
-    chords: XmlChords,
+    pub chords: XmlChords,
+    /// Fret hand positions.
+    pub anchors: XmlAnchors,
+    /// Time ranges a chord or arpeggio shape is held.
+    pub hand_shapes: XmlHandShapes,
 }
 
 impl XmlLevel {
@@ -75,24 +487,43 @@ impl XmlLevel {
     }
 }
 
-/// Where the camera should be.
+/// Where the fret hand should be.
 #[derive(Debug, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct XmlAnchors {
-    #[serde(rename = "anchor")]
-    anchors: Vec<XmlAnchor>,
+    #[serde(rename = "anchor", default)]
+    pub anchors: Vec<XmlAnchor>,
 }
 
-/// Single camera position in time.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// Single fret hand position in time.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct XmlAnchor {
-    /// When the camera should be placed at the location.
-    time: f32,
-    /// At which fret the camera should zoom in.
-    fret: u8,
-    /// How much the camera should be zoomed in.
-    width: f32,
+    /// When the hand should be placed at the location.
+    pub time: f32,
+    /// Lowest fret of the hand, where the camera zooms in.
+    pub fret: u8,
+    /// Amount of frets the hand spans, how much the camera should be zoomed in.
+    pub width: f32,
+}
+
+/// Newtype for the hand shapes.
+#[derive(Debug, Default, Deserialize)]
+pub struct XmlHandShapes {
+    #[serde(rename = "handShape", default)]
+    pub hand_shapes: Vec<XmlHandShape>,
+}
+
+/// Time range a chord or arpeggio shape is held.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct XmlHandShape {
+    /// Index of the chord template.
+    pub chord_id: usize,
+    /// Start time in seconds.
+    pub start_time: f32,
+    /// End time in seconds.
+    pub end_time: f32,
 }
 
 /// All the notes for this section.
@@ -100,12 +531,12 @@ pub struct XmlAnchor {
 #[serde(rename_all = "camelCase")]
 pub struct XmlNotes {
     #[serde(rename = "note", default)]
-    notes: Vec<XmlNote>,
+    pub notes: Vec<XmlNote>,
 }
 
 /// A singe note in time.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct XmlNote {
     /// When the note should be struck.
     pub time: f32,
@@ -113,8 +544,12 @@ pub struct XmlNote {
     pub fret: i8,
     /// Which string to play this note on.
     pub string: i8,
+    /// How much this note needs to be sustained.
+    pub sustain: Option<f32>,
     /// To which fret to slide if applicable.
     pub slide_to: Option<i8>,
+    /// To which fret to slide without a pitch if applicable.
+    pub slide_unpitch_to: Option<i8>,
     /// Whether it should bend.
     ///
     /// Means the bend values array will be filled.
@@ -122,45 +557,41 @@ pub struct XmlNote {
     /// The values when `bend == 1`.
     pub bend_values: Option<XmlBendValues>,
     /// Whether this note should be muted.
-    pub mute: Option<i8>,
+    pub mute: bool,
     /// Whether this note should be muted with the palm.
-    pub palm_mute: Option<i8>,
-    /// How much this note needs to be sustained.
-    pub sustain: Option<f32>,
-    /// Whether this is a vibrato note.
-    pub vibrato: Option<i8>,
-    /*
-    /// Whether this note should be played with the left hand.
-    left_hand: i8,
-    /// Whether this note should be played with the right hand.
-    right_hand: i8,
-    /// Which direction the string should be picked.
-    pick_direction: i8,
-    // TODO: find out what it does
-    link_next: i8,
-    // TODO: find out what it does
-    slide_unpitch_to: i8,
+    pub palm_mute: bool,
+    /// Vibrato strength.
+    pub vibrato: Option<i16>,
     /// Whether this note is a hammer-on.
-    hammer_on: i8,
-    /// Whether this note is a harmonic note.
-    harmonic: i8,
-    /// Whether this note should be plucked.
-    pluck: i8,
-    /// Whether this note should be pulled off.
-    pull_off: i8,
-    /// How hard this should be slapped.
-    slap: i8,
-    /// Whether this should be tapped.
-    tap: i8,
-    /// Whether this is a vibrato note.
-    vibrato: i8,
-    // TODO: find out what it does
-    harmonic_pinch: i8,
-    // TODO: find out what it does
-    hopo: i8,
-    // TODO: find out what it does
-    ignore: i8,
-    */
+    pub hammer_on: bool,
+    /// Whether this note is pulled off.
+    pub pull_off: bool,
+    /// Whether this note is a hammer-on or a pull-off.
+    pub hopo: bool,
+    /// Whether this note is a harmonic.
+    pub harmonic: bool,
+    /// Whether this note is a pinch harmonic.
+    pub harmonic_pinch: bool,
+    /// Whether the string is picked very rapidly.
+    pub tremolo: bool,
+    /// Whether this note is accented.
+    pub accent: bool,
+    /// Whether this note continues into the next note.
+    pub link_next: bool,
+    /// Whether this note is ignored for scoring.
+    pub ignore: bool,
+    /// Finger of the fret hand.
+    pub left_hand: Option<i8>,
+    /// Finger of the picking hand.
+    pub right_hand: Option<i8>,
+    /// Which direction the string should be picked, `1` for upwards.
+    pub pick_direction: Option<i8>,
+    /// Tapping finger.
+    pub tap: Option<i8>,
+    /// Slap strength.
+    pub slap: Option<i8>,
+    /// Pluck (pop) strength.
+    pub pluck: Option<i8>,
 }
 
 impl XmlNote {
@@ -173,23 +604,23 @@ impl XmlNote {
 }
 
 /// All the bend values for this note.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct XmlBendValues {
-    #[serde(rename = "bendValue")]
-    bend_values: Vec<XmlBendValue>,
+    #[serde(rename = "bendValue", default)]
+    pub bend_values: Vec<XmlBendValue>,
 }
 
 /// A singe bend_value in time.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct XmlBendValue {
     /// When the bend part should be struck.
     pub time: f32,
-    // TODO: find out what it does
+    /// Amount of half steps bent.
     pub step: Option<f32>,
     // TODO: find out what it does
-    unk5: Option<i32>,
+    pub unk5: Option<i32>,
 }
 
 /// All the chords for this section.
@@ -197,20 +628,68 @@ pub struct XmlBendValue {
 #[serde(rename_all = "camelCase")]
 pub struct XmlChords {
     #[serde(rename = "chord", default)]
-    chords: Vec<XmlChord>,
+    pub chords: Vec<XmlChord>,
 }
 
 /// A single chord in time.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct XmlChord {
     /// When the chord will be struck.
-    time: f32,
+    pub time: f32,
     /// Which cord it is.
     ///
     /// The name and other information can be found with this ID.
     pub chord_id: i16,
+    /// Whether all strings should be muted with the fret hand.
+    pub fret_hand_mute: bool,
+    /// Whether all strings should be muted with the palm.
+    pub palm_mute: bool,
+    /// Whether the chord is a repetition of the chord before, only the first is shown fully.
+    pub high_density: bool,
+    /// Whether the chord is a hammer-on or a pull-off.
+    pub hopo: bool,
+    /// Whether this chord is accented.
+    pub accent: bool,
+    /// Whether this chord continues into the next chord.
+    pub link_next: bool,
+    /// Whether this chord is ignored for scoring.
+    pub ignore: bool,
+    /// Which direction the strings should be strummed, `"up"` or `"down"`.
+    pub strum: Option<String>,
     /// Notes for this chord.
-    #[serde(rename = "chordNote", default)]
+    #[serde(rename = "chordNote")]
     pub notes: Vec<XmlNote>,
 }
+
+/// Parsed vocals arrangement.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct XmlVocals {
+    /// Amount of syllables.
+    pub count: usize,
+    /// Every sung syllable.
+    #[serde(rename = "vocal")]
+    pub vocals: Vec<XmlVocal>,
+}
+
+impl XmlVocals {
+    /// Parse the XML string into this object.
+    pub fn parse(xml: &str) -> Result<Self> {
+        Ok(quick_xml::de::from_str(xml)?)
+    }
+}
+
+/// Single sung syllable.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct XmlVocal {
+    /// Time in seconds.
+    pub time: f32,
+    /// MIDI note of the syllable.
+    pub note: i32,
+    /// Duration in seconds.
+    pub length: f32,
+    /// The syllable, ends with `'-'` when the word continues and `'+'` at the end of a line.
+    pub lyric: String,
+}
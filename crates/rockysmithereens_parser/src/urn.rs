@@ -0,0 +1,111 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::error::{Result, RocksmithArchiveError};
+
+/// Prefix of every urn.
+const PREFIX: &str = "urn:";
+
+/// Reference to a file in the archive, such as `"urn:image:dds:album_song_256"`.
+///
+/// The last part is the name of the file without the extension.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Urn {
+    /// The whole urn including the prefix.
+    urn: String,
+}
+
+impl Urn {
+    /// Parse an urn, it needs at least a kind and a name.
+    pub fn parse(urn: &str) -> Result<Self> {
+        let parts = urn
+            .strip_prefix(PREFIX)
+            .map(|rest| rest.split(':').collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        if parts.len() < 2 || parts.iter().any(|part| part.is_empty()) {
+            return Err(RocksmithArchiveError::InvalidUrnPath(urn.to_string()));
+        }
+
+        Ok(Self {
+            urn: urn.to_string(),
+        })
+    }
+
+    /// The whole urn.
+    pub fn as_str(&self) -> &str {
+        &self.urn
+    }
+
+    /// Kind of resource, such as `"image"` or `"application"`.
+    pub fn kind(&self) -> &str {
+        self.parts().next().unwrap_or_default()
+    }
+
+    /// Format of the file, such as `"dds"` or `"xml"`, `None` when the urn only has a kind.
+    pub fn format(&self) -> Option<&str> {
+        let parts = self.parts().collect::<Vec<_>>();
+
+        (parts.len() > 2).then(|| parts[1])
+    }
+
+    /// Name of the file without the extension.
+    pub fn name(&self) -> &str {
+        self.parts().last().unwrap_or_default()
+    }
+
+    /// Every part after the prefix.
+    fn parts(&self) -> impl Iterator<Item = &str> {
+        self.urn[PREFIX.len()..].split(':')
+    }
+}
+
+impl FromStr for Urn {
+    type Err = RocksmithArchiveError;
+
+    fn from_str(urn: &str) -> Result<Self> {
+        Self::parse(urn)
+    }
+}
+
+impl AsRef<str> for Urn {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Display for Urn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.urn)
+    }
+}
+
+impl<'de> Deserialize<'de> for Urn {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let urn = String::deserialize(deserializer)?;
+
+        Self::parse(&urn).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Urns that aren't set are stored as empty strings.
+///
+/// Malformed urns are treated as not set, the same as in the xblock.
+pub(crate) fn optional_urn<'de, D>(deserializer: D) -> std::result::Result<Option<Urn>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let urn = Option::<String>::deserialize(deserializer)?.unwrap_or_default();
+    if urn.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Urn::parse(&urn).ok())
+}
@@ -2,47 +2,52 @@ use std::collections::HashMap;
 
 use serde::Deserialize;
 
-use crate::error::Result;
+use crate::{error::Result, urn::Urn};
 
 /// Simplified entity with a lot of assumptions.
+///
+/// Properties that aren't set or aren't a valid urn are `None`.
 #[derive(Debug, Clone)]
 pub struct SimplifiedEntity {
     pub id: String,
     pub name: String,
-    pub(crate) model_name: String,
-    pub(crate) iterations: usize,
-    pub(crate) sound_bank: Option<String>,
-    pub(crate) manifest: Option<String>,
-    pub(crate) lyric_art: Option<String>,
-    pub(crate) album_art_small: Option<String>,
-    pub(crate) album_art_medium: Option<String>,
-    pub(crate) album_art_large: Option<String>,
-    pub(crate) preview_sound_bank: Option<String>,
-    pub(crate) header: Option<String>,
-    pub(crate) show_lights_xml_asset: Option<String>,
-    pub(crate) sng_asset: Option<String>,
+    /// Kind of entity, such as `"RSEnumerable_Song"`.
+    pub model_name: String,
+    pub iterations: usize,
+    pub sound_bank: Option<Urn>,
+    pub manifest: Option<Urn>,
+    pub lyric_art: Option<Urn>,
+    pub album_art_small: Option<Urn>,
+    pub album_art_medium: Option<Urn>,
+    pub album_art_large: Option<Urn>,
+    pub preview_sound_bank: Option<Urn>,
+    pub header: Option<Urn>,
+    pub show_lights_xml_asset: Option<Urn>,
+    pub sng_asset: Option<Urn>,
 }
 
 impl From<&Entity> for SimplifiedEntity {
     fn from(entity: &Entity) -> Self {
         // TODO: don't have so many memory allocations
         let properties = entity.properties_map();
+        // Malformed urns are treated as not set
+        let urn = |name: &str| properties.get(name).and_then(|urn| Urn::parse(urn).ok());
 
         Self {
             id: entity.id.clone(),
             model_name: entity.model_name.clone(),
             name: entity.name.clone(),
             iterations: entity.iterations,
-            sound_bank: properties.get("SoundBank").cloned(),
-            manifest: properties.get("Manifest").cloned(),
-            lyric_art: properties.get("LyricArt").cloned(),
-            album_art_small: properties.get("AlbumArtSmall").cloned(),
-            album_art_medium: properties.get("AlbumArtMedium").cloned(),
-            album_art_large: properties.get("AlbumArtLarge").cloned(),
-            preview_sound_bank: properties.get("PreviewSoundBank").cloned(),
-            header: properties.get("Header").cloned(),
-            show_lights_xml_asset: properties.get("ShowLightsXMLAsset").cloned(),
-            sng_asset: properties.get("SngAsset").cloned(),
+            sound_bank: urn("SoundBank"),
+            manifest: urn("Manifest"),
+            lyric_art: urn("LyricArt"),
+            album_art_small: urn("AlbumArtSmall"),
+            album_art_medium: urn("AlbumArtMedium"),
+            album_art_large: urn("AlbumArtLarge"),
+            preview_sound_bank: urn("PreviewSoundBank"),
+            header: urn("Header"),
+            show_lights_xml_asset: urn("ShowLightsXMLAsset"),
+            sng_asset: urn("SngAsset"),
         }
     }
 }
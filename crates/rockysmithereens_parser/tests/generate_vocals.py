@@ -0,0 +1,135 @@
+#!/usr/bin/env python3
+"""Generate the vocals arrangement test fixtures.
+
+The binary arrangement is written straight from the file format, without using the
+`rockysmithereens_parser` crate, so the tests don't only check that the parser agrees with the
+writer. The payload is compressed with Python's zlib and encrypted with the PC key.
+
+Run from this directory: `python3 generate_vocals.py`, requires the `cryptography` package.
+"""
+
+import struct
+import zlib
+
+from cryptography.hazmat.primitives.ciphers import Cipher, algorithms, modes
+
+PC_KEY = bytes.fromhex("CB648DF3D12A16BF71701414E69619EC171CCA5D2A142E3E59DE7ADDA18A3A30")
+IV = bytes.fromhex("8D1C6A2F90B3E47155A0D2C93E6B4F18")
+SIGNATURE_SIZE = 56
+
+FONT = "assets/ui/lyrics/testsong/lyrics_testsong.dds"
+
+# Time, MIDI note or 254 when it's not pitched, length and syllable
+VOCALS = [
+    (12.5, 57, 0.3, "Ev-"),
+    (12.8, 57, 0.25, "ery"),
+    (13.1, 59, 0.6, "night+"),
+    (15.0, 254, 0.4, "I"),
+    (15.5, 60, 0.2, "sing-"),
+    (15.7, 62, 1.25, "ing+"),
+    (20.25, 64, 2.0, "Ö+"),
+]
+
+# Character, outer and inner bounds as left, top, right and bottom
+GLYPHS = [
+    ("E", (0.0, 0.0, 0.0625, 0.125), (0.005, 0.01, 0.0575, 0.115)),
+    ("v", (0.0625, 0.0, 0.125, 0.125), (0.0675, 0.01, 0.12, 0.115)),
+    ("Ö", (0.125, 0.0, 0.1875, 0.125), (0.13, 0.01, 0.1825, 0.115)),
+]
+
+
+def string(text, length):
+    """Fixed size string padded with zeros."""
+    encoded = text.encode()
+    assert len(encoded) < length
+
+    return encoded + bytes(length - len(encoded))
+
+
+def array(items):
+    return struct.pack("<I", len(items)) + b"".join(items)
+
+
+def rect(left, top, right, bottom):
+    # The SNG stores the top before the left
+    return struct.pack("<4f", top, left, bottom, right)
+
+
+def payload():
+    empty = array([])
+
+    vocals = array(
+        [
+            struct.pack("<fif", time, note, length) + string(lyric, 48)
+            for time, note, length, lyric in VOCALS
+        ]
+    )
+    symbols_headers = array([struct.pack("<8i", 0, 0, 0, 0, 0, 0, 0, 0)])
+    symbols_textures = array(
+        [string(FONT, 128) + struct.pack("<4i", len(FONT), 0, 1024, 512)]
+    )
+    symbol_definitions = array(
+        [string(text, 12) + rect(*outer) + rect(*inner) for text, outer, inner in GLYPHS]
+    )
+
+    metadata = (
+        struct.pack("<4d", 0.0, 0.0, 0.0, 0.0)
+        # First beat length and start time
+        + struct.pack("<ff", 0.5, 0.0)
+        # No capo
+        + struct.pack("<b", -1)
+        + string("6-13-14 15:02", 32)
+        # Part
+        + struct.pack("<h", 0)
+        # Song length
+        + struct.pack("<f", 180.0)
+        + array([struct.pack("<h", 0)] * 6)
+        # First note time and its copy
+        + struct.pack("<ff", 12.5, 12.5)
+        # Highest difficulty
+        + struct.pack("<i", 0)
+    )
+
+    return (
+        # Beats, phrases, chord templates and chord notes
+        empty * 4
+        + vocals
+        + symbols_headers
+        + symbols_textures
+        + symbol_definitions
+        # Phrase iterations, phrase extra info, linked difficulties, actions, events, tone
+        # changes, DNAs, sections and arrangements
+        + empty * 9
+        + metadata
+    )
+
+
+def sng():
+    data = payload()
+    compressed = struct.pack("<I", len(data)) + zlib.compress(data, 9)
+
+    encryptor = Cipher(algorithms.AES(PC_KEY), modes.CTR(IV)).encryptor()
+    encrypted = encryptor.update(compressed) + encryptor.finalize()
+
+    # Magic, compressed and encrypted flags
+    header = struct.pack("<II", 0x4A, 3) + IV
+
+    return header + encrypted + bytes(SIGNATURE_SIZE)
+
+
+def xml():
+    lines = ['<?xml version="1.0" encoding="utf-8"?>', f'<vocals count="{len(VOCALS)}">']
+    for time, note, length, lyric in VOCALS:
+        lines.append(
+            f'  <vocal time="{time:.3f}" note="{note}" length="{length:.3f}" lyric="{lyric}" />'
+        )
+    lines.append("</vocals>")
+
+    return "\r\n".join(lines) + "\r\n"
+
+
+if __name__ == "__main__":
+    with open("vocals.sng", "wb") as f:
+        f.write(sng())
+    with open("vocals.xml", "w", encoding="utf-8", newline="") as f:
+        f.write(xml())
@@ -0,0 +1,698 @@
+use psarc::PlaystationArchive;
+use rockysmithereens_parser::{
+    level::Level,
+    lyrics::Lyrics,
+    manifest::{ArrangementPath, ArrangementType, Manifest, SongKey},
+    note::Techniques,
+    pitch::{Fretboard, Instrument},
+    show_lights::Effect,
+    sng::{self, Platform, Sng},
+    song::{Beat, LinkedDifficulty, PhraseLink, PhraseProperty, Song},
+    song_xml::{XmlSong, XmlVocals},
+    urn::Urn,
+    SongFile,
+};
+use rodio_wem::WemDecoder;
+
+const PSARC: &[u8] = include_bytes!("../../psarc/tests/test.psarc");
+const SNG_PATH: &str = "songs/bin/macos/butitrainedsong_lead.sng";
+const XML_PATH: &str = "songs/arr/butitrainedsong_lead.xml";
+const MANIFEST_PATH: &str = "manifests/songs_dlc_butitrainedsong/butitrainedsong_lead.json";
+
+/// Parse the first arrangement of the first song.
+fn parse_song(bytes: &[u8]) -> Song {
+    let song_file = SongFile::parse(bytes).unwrap();
+
+    song_file.parse_song_info(&song_file.songs[0], 0).unwrap()
+}
+
+#[test]
+fn test_sng() {
+    let song_file = SongFile::parse(PSARC).unwrap();
+    let sng = Sng::parse(&song_file.read_file(SNG_PATH).unwrap(), Platform::Mac).unwrap();
+
+    assert_eq!(sng.arrangements.len(), 18);
+    assert_eq!(sng.metadata.tuning, [0; 6]);
+    assert_eq!(sng.metadata.max_difficulty, 17);
+    assert!((sng.metadata.song_length - 282.967).abs() < 0.001);
+
+    // Every difficulty has the same amount of notes and chords as the XML
+    let counts = sng
+        .arrangements
+        .iter()
+        .map(|arrangement| arrangement.notes.len())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        counts,
+        [30, 70, 120, 154, 211, 250, 321, 371, 381, 198, 190, 207, 232, 174, 110, 52, 56, 59]
+    );
+
+    // Decrypting with the wrong key fails
+    assert!(Sng::parse(&song_file.read_file(SNG_PATH).unwrap(), Platform::Pc).is_err());
+}
+
+#[test]
+fn test_song_from_sng() {
+    let from_sng = parse_song(PSARC);
+
+    // Without the SNG the XML is used
+    let psarc = PlaystationArchive::parse(PSARC).unwrap();
+    let mut patch = psarc.patch();
+    patch.remove_file(SNG_PATH).unwrap();
+    let from_xml = parse_song(&patch.build().unwrap());
+
+    assert_eq!(from_sng.beats, from_xml.beats);
+    assert_eq!(from_sng.phrases, from_xml.phrases);
+    assert_eq!(from_sng.phrase_iterations, from_xml.phrase_iterations);
+    assert_eq!(from_sng.linked_difficulties, from_xml.linked_difficulties);
+    assert_eq!(from_sng.phrase_properties, from_xml.phrase_properties);
+    assert_eq!(from_sng.sections, from_xml.sections);
+    assert_eq!(from_sng.chord_templates, from_xml.chord_templates);
+    assert_eq!(from_sng.events, from_xml.events);
+    assert_eq!(from_sng.tone_changes, from_xml.tone_changes);
+    assert_eq!(from_sng.tuning, from_xml.tuning);
+    assert_eq!(from_sng.capo, from_xml.capo);
+    assert_eq!(from_sng.song_length, from_xml.song_length);
+
+    assert_eq!(from_sng.levels.len(), from_xml.levels.len());
+    for (sng_level, xml_level) in from_sng.levels.iter().zip(from_xml.levels.iter()) {
+        assert_eq!(sng_level.difficulty, xml_level.difficulty);
+        assert_eq!(sng_level.anchors, xml_level.anchors);
+        assert_eq!(sng_level.hand_shapes, xml_level.hand_shapes);
+
+        // The XML lists the chords after the single notes
+        let sorted = |level: &Level| {
+            let mut notes = level.notes.clone();
+            notes.sort_by(|a, b| a.time.total_cmp(&b.time).then(a.string.cmp(&b.string)));
+
+            notes
+        };
+        let (sng_notes, xml_notes) = (sorted(sng_level), sorted(xml_level));
+
+        assert_eq!(sng_notes.len(), xml_notes.len());
+        for (sng_note, xml_note) in sng_notes.iter().zip(xml_notes.iter()) {
+            assert!((sng_note.time - xml_note.time).abs() < 0.001);
+            assert_eq!(
+                (
+                    sng_note.string,
+                    sng_note.fret,
+                    sng_note.show,
+                    sng_note.mute,
+                    sng_note.chord
+                ),
+                (
+                    xml_note.string,
+                    xml_note.fret,
+                    xml_note.show,
+                    xml_note.mute,
+                    xml_note.chord
+                )
+            );
+            assert_eq!(sng_note.sustain.is_some(), xml_note.sustain.is_some());
+            assert_eq!(
+                (
+                    sng_note.slide_to,
+                    sng_note.slide_unpitch_to,
+                    sng_note.techniques,
+                    sng_note.pick_direction,
+                    sng_note.finger
+                ),
+                (
+                    xml_note.slide_to,
+                    xml_note.slide_unpitch_to,
+                    xml_note.techniques,
+                    xml_note.pick_direction,
+                    xml_note.finger
+                )
+            );
+        }
+    }
+}
+
+#[test]
+fn test_fret_hand_mute() {
+    let from_sng = parse_song(PSARC);
+    let psarc = PlaystationArchive::parse(PSARC).unwrap();
+    let mut patch = psarc.patch();
+    patch.remove_file(SNG_PATH).unwrap();
+    let from_xml = parse_song(&patch.build().unwrap());
+
+    // Muted single notes are muted with the fretting hand in both formats
+    let muted = |song: &Song| {
+        song.levels
+            .iter()
+            .flat_map(|level| &level.notes)
+            .filter(|note| {
+                note.chord.is_none() && note.techniques.contains(Techniques::FRET_HAND_MUTE)
+            })
+            .count()
+    };
+    assert_eq!(muted(&from_sng), 82);
+    assert_eq!(muted(&from_xml), 82);
+
+    // The mutes are kept when the XML is written as a binary arrangement
+    let bytes = Sng::from(&from_xml).to_bytes(Platform::Pc).unwrap();
+    let round_trip = Song::from(Sng::parse(&bytes, Platform::Pc).unwrap());
+    assert_eq!(muted(&round_trip), 82);
+}
+
+#[test]
+fn test_sng_round_trip() {
+    let song_file = SongFile::parse(PSARC).unwrap();
+    let bytes = song_file.read_file(SNG_PATH).unwrap();
+    let sng = Sng::parse(&bytes, Platform::Mac).unwrap();
+
+    // The serialized payload is identical to the original
+    assert_eq!(
+        sng.to_decrypted_bytes(),
+        Sng::decrypt(&bytes, Platform::Mac).unwrap()
+    );
+
+    for platform in [Platform::Pc, Platform::Mac] {
+        let written = sng.to_bytes(platform).unwrap();
+        assert_eq!(Sng::parse(&written, platform).unwrap(), sng);
+    }
+}
+
+#[test]
+fn test_song_to_sng() {
+    let song = parse_song(PSARC);
+
+    let bytes = Sng::from(&song).to_bytes(Platform::Pc).unwrap();
+    let sng = Sng::parse(&bytes, Platform::Pc).unwrap();
+    assert_eq!(sng.metadata.max_difficulty, 17);
+    let round_trip = Song::from(sng);
+
+    assert_eq!(song.beats, round_trip.beats);
+    assert_eq!(song.phrases, round_trip.phrases);
+    assert_eq!(song.phrase_iterations, round_trip.phrase_iterations);
+    assert_eq!(song.linked_difficulties, round_trip.linked_difficulties);
+    assert_eq!(song.phrase_properties, round_trip.phrase_properties);
+    assert_eq!(song.sections, round_trip.sections);
+    assert_eq!(song.chord_templates, round_trip.chord_templates);
+    assert_eq!(song.events, round_trip.events);
+    assert_eq!(song.tone_changes, round_trip.tone_changes);
+
+    assert_eq!(song.levels.len(), round_trip.levels.len());
+    for (level, round_trip_level) in song.levels.iter().zip(round_trip.levels.iter()) {
+        assert_eq!(level.difficulty, round_trip_level.difficulty);
+        assert_eq!(level.anchors, round_trip_level.anchors);
+        assert_eq!(level.hand_shapes, round_trip_level.hand_shapes);
+
+        assert_eq!(level.notes.len(), round_trip_level.notes.len());
+        for (note, round_trip_note) in level.notes.iter().zip(round_trip_level.notes.iter()) {
+            assert!((note.time - round_trip_note.time).abs() < 0.001);
+            assert_eq!(
+                (note.string, note.fret, note.show, note.mute, note.chord),
+                (
+                    round_trip_note.string,
+                    round_trip_note.fret,
+                    round_trip_note.show,
+                    round_trip_note.mute,
+                    round_trip_note.chord
+                )
+            );
+            assert_eq!(
+                (
+                    note.slide_to,
+                    note.slide_unpitch_to,
+                    note.techniques,
+                    note.pick_direction,
+                    note.finger
+                ),
+                (
+                    round_trip_note.slide_to,
+                    round_trip_note.slide_unpitch_to,
+                    round_trip_note.techniques,
+                    round_trip_note.pick_direction,
+                    round_trip_note.finger
+                )
+            );
+            assert_eq!(note.bend, round_trip_note.bend);
+            match (note.sustain, round_trip_note.sustain) {
+                (Some(a), Some(b)) => assert!((a - b).abs() < 0.001),
+                (a, b) => assert_eq!(a, b),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_linked_difficulties() {
+    let xml = XmlSong::parse(
+        r#"<song version="7">
+            <arrangementProperties pathLead="0" pathBass="1" routeMask="4" fretHandMutes="1" />
+            <newLinkedDiffs count="1">
+                <newLinkedDiff levelBreak="-1" ratio="1.000" phrases="2">
+                    <nld_phrase id="1" />
+                    <nld_phrase id="3" />
+                </newLinkedDiff>
+                <newLinkedDiff levelBreak="4" ratio="1.000" phrases="1">
+                    <nld_phrase id="2" />
+                </newLinkedDiff>
+            </newLinkedDiffs>
+            <linkedDiffs count="1">
+                <linkedDiff childId="3" parentId="1" />
+            </linkedDiffs>
+            <phraseProperties count="1">
+                <phraseProperty phraseId="2" redundant="0" levelJump="1" empty="1" difficulty="5" />
+            </phraseProperties>
+            <fretHandMuteTemplates count="1">
+                <fretHandMuteTemplate chordName="" displayName="fhm" fret0="-1" fret1="0" />
+            </fretHandMuteTemplates>
+        </song>"#,
+    )
+    .unwrap();
+    let song = Song::from(xml);
+
+    assert_eq!(
+        song.linked_difficulties,
+        [
+            LinkedDifficulty {
+                level_break: None,
+                phrases: vec![1, 3]
+            },
+            LinkedDifficulty {
+                level_break: Some(4),
+                phrases: vec![2]
+            }
+        ]
+    );
+    assert_eq!(
+        song.phrase_links,
+        [PhraseLink {
+            child: 3,
+            parent: 1
+        }]
+    );
+    assert_eq!(
+        song.phrase_properties,
+        [PhraseProperty {
+            phrase: 2,
+            difficulty: 5,
+            redundant: false,
+            level_jump: 1,
+            empty: true
+        }]
+    );
+    assert_eq!(song.fret_hand_mute_templates.len(), 1);
+    assert_eq!(song.fret_hand_mute_templates[0].frets[1], Some(0));
+    assert!(song.arrangement_properties.path_bass);
+    assert!(song.arrangement_properties.fret_hand_mutes);
+    assert_eq!(song.arrangement_properties.route_mask, 4);
+
+    // The binary arrangement stores the links and properties too
+    let sng = Sng::from(&song);
+    assert_eq!(sng.linked_difficulties[0].level_break, -1);
+    assert_eq!(sng.linked_difficulties[1].phrases, [2]);
+    let bytes = sng.to_bytes(Platform::Pc).unwrap();
+    let round_trip = Song::from(Sng::parse(&bytes, Platform::Pc).unwrap());
+    assert_eq!(round_trip.linked_difficulties, song.linked_difficulties);
+    assert_eq!(round_trip.phrase_properties, song.phrase_properties);
+}
+
+#[test]
+fn test_difficulty() {
+    let song = parse_song(PSARC);
+
+    // Every phrase has the easiest level
+    assert_eq!(
+        song.notes_for_difficulty_iter(0).count(),
+        song.levels[0].notes.len()
+    );
+    assert_eq!(
+        song.notes_for_phrase_difficulties_iter(|_| 0).count(),
+        song.levels[0].notes.len()
+    );
+
+    // Phrases don't go above their own highest level
+    let hardest = song.notes_for_difficulty_iter(17).count();
+    assert_eq!(hardest, 1443);
+    assert_eq!(song.notes_for_difficulty_iter(u8::MAX).count(), hardest);
+
+    // Levels aren't mixed together, bends can start at the same time as their note
+    let mut unique = song
+        .notes_for_difficulty_iter(17)
+        .filter(|note| note.show)
+        .map(|note| (note.time.to_bits(), note.string))
+        .collect::<Vec<_>>();
+    let shown = unique.len();
+    unique.sort_unstable();
+    unique.dedup();
+    assert_eq!(unique.len(), shown);
+
+    // Resolving once gives the same notes as filtering every time
+    let resolved = song.notes_for_difficulty(10);
+    assert_eq!(
+        resolved.notes.len(),
+        song.notes_for_difficulty_iter(10).count()
+    );
+    for (start_time, end_time) in [(0.0, 10.0), (30.5, 33.0), (100.0, 100.0), (280.0, 1000.0)] {
+        let mut expected = song
+            .notes_between_time_iter(start_time, end_time, 10)
+            .collect::<Vec<_>>();
+        expected.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let between = resolved.between(start_time, end_time);
+        assert_eq!(between.len(), expected.len());
+        assert!(between
+            .iter()
+            .zip(expected)
+            .all(|(a, b)| (a.time, a.string, a.fret) == (b.time, b.string, b.fret)));
+    }
+}
+
+#[test]
+fn test_beat_grid() {
+    let song = parse_song(PSARC);
+    let grid = song.beat_grid();
+
+    assert_eq!(grid.beats().len(), song.beats.len());
+    assert_eq!(
+        grid.downbeats()
+            .take(4)
+            .map(|beat| beat.position.measure)
+            .collect::<Vec<_>>(),
+        [0, 1, 2, 3]
+    );
+
+    // Three beats per measure at the start
+    let position = grid.position_at(3.0).unwrap();
+    assert_eq!((position.measure, position.beat), (2, 1));
+    assert!((grid.time_at(position).unwrap() - 3.0).abs() < 0.001);
+    assert!((grid.bpm_at(3.0).unwrap() - 87.5).abs() < 0.5);
+
+    // The 6/8 part has a beat for every eighth note
+    assert!((grid.average_bpm().unwrap() - 131.25).abs() < 0.1);
+
+    assert!(grid.position_at(-1.0).is_none());
+
+    // Negative measure numbers in the SNG aren't measures
+    let beat = Beat::from(&sng::Beat {
+        time: 0.0,
+        measure: -1,
+        beat: 0,
+        phrase_iteration: 0,
+        mask: sng::BEAT_MASK_FIRST,
+    });
+    assert_eq!(beat.measure, None);
+}
+
+#[test]
+fn test_pitch() {
+    let song_file = SongFile::parse(PSARC).unwrap();
+    let guitar = Fretboard::from_attributes(song_file.songs[0].manifests[0].attributes().unwrap());
+    assert_eq!(guitar.instrument, Instrument::Guitar);
+
+    let low_e = guitar.pitch(0, 0).unwrap();
+    assert_eq!((low_e.midi, low_e.to_string()), (40, "E2".to_string()));
+    assert!((low_e.frequency() - 82.407).abs() < 0.01);
+    let a4 = guitar.pitch(5, 5).unwrap();
+    assert!((a4.frequency() - 440.0).abs() < 0.01);
+
+    // Drop D with a capo on the second fret
+    let drop_d = Fretboard {
+        tuning: [-2, 0, 0, 0, 0, 0],
+        capo: 2,
+        ..guitar.clone()
+    };
+    assert_eq!(drop_d.pitch(0, 0).unwrap().to_string(), "E2");
+    assert_eq!(drop_d.pitch(1, 4).unwrap().to_string(), "C#3");
+
+    // Tuned a quarter tone down
+    let flat = Fretboard {
+        cent_offset: -50.0,
+        ..guitar
+    };
+    assert!((flat.pitch(5, 5).unwrap().frequency() - 427.47).abs() < 0.01);
+
+    let bass = Fretboard {
+        instrument: Instrument::Bass,
+        ..Default::default()
+    };
+    assert_eq!(bass.pitch(3, 2).unwrap().to_string(), "A2");
+    assert!(bass.pitch(4, 0).is_none());
+}
+
+#[test]
+fn test_lyrics() {
+    let sng = Sng::parse(include_bytes!("./vocals.sng"), Platform::Pc).unwrap();
+    let lyrics = Lyrics::from(&sng);
+
+    assert_eq!(
+        lyrics
+            .lines_iter()
+            .map(Lyrics::line_text)
+            .collect::<Vec<_>>(),
+        ["Every night", "I singing", "Ö"]
+    );
+    assert_eq!(lyrics.syllables[0].note, 57);
+    assert!(lyrics.syllables[0].continues_word);
+    assert!(lyrics.syllables[2].ends_line);
+    assert_eq!(lyrics.syllables[3].note, 254);
+    assert_eq!(lyrics.syllables_between_time_iter(15.0, 16.0).count(), 3);
+
+    let font = lyrics.font.as_ref().unwrap();
+    assert_eq!(
+        font.texture,
+        "assets/ui/lyrics/testsong/lyrics_testsong.dds"
+    );
+    assert_eq!(font.glyphs.len(), 3);
+    assert_eq!(
+        font.glyph("Ö")
+            .unwrap()
+            .outer
+            .to_pixels(font.width, font.height),
+        [128, 0, 192, 64]
+    );
+
+    // The XML of the same arrangement has the same syllables but no font
+    let from_xml = Lyrics::from(XmlVocals::parse(include_str!("./vocals.xml")).unwrap());
+    assert_eq!(from_xml.syllables, lyrics.syllables);
+    assert!(from_xml.font.is_none());
+
+    // Without the binary arrangement the XML is used
+    let psarc = PlaystationArchive::parse(PSARC).unwrap();
+    let mut patch = psarc.patch();
+    patch.remove_file(SNG_PATH).unwrap();
+    patch
+        .replace_file(XML_PATH, include_bytes!("./vocals.xml").to_vec())
+        .unwrap();
+    let song_file = SongFile::parse(&patch.build().unwrap()).unwrap();
+    let from_archive = song_file.parse_lyrics(&song_file.songs[0], 0).unwrap();
+    assert_eq!(from_archive.syllables, lyrics.syllables);
+}
+
+#[test]
+fn test_show_lights() {
+    let song_file = SongFile::parse(PSARC).unwrap();
+    let show_lights = song_file.parse_show_lights(&song_file.songs[0]).unwrap();
+
+    assert_eq!(show_lights.events.len(), 29);
+    assert_eq!(show_lights.events[0].time, 10.0);
+    assert_eq!(show_lights.events[0].effect(), Effect::Fog(0));
+    assert_eq!(show_lights.events[1].effect(), Effect::Beam(0));
+
+    assert_eq!(show_lights.fog_at(5.0), None);
+    assert_eq!(show_lights.fog_at(30.0), Some(0));
+    assert_eq!(show_lights.beam_at(84.5), Some(6));
+    assert_eq!(show_lights.events_between_time_iter(80.0, 90.0).count(), 4);
+}
+
+#[test]
+fn test_tones() {
+    let song_file = SongFile::parse(PSARC).unwrap();
+    let song = song_file.parse_song_info(&song_file.songs[0], 0).unwrap();
+    let attributes = song_file.songs[0].manifests[0].attributes().unwrap();
+
+    assert_eq!(attributes.tones.len(), 2);
+    let distortion = attributes.tone("Distortion").unwrap();
+    assert!((distortion.volume + 18.07).abs() < 0.001);
+    let amp = distortion.gear_list.amp.as_ref().unwrap();
+    assert_eq!(amp.key, "Amp_CA100");
+    assert_eq!(amp.knob_values["Amp_CA100_Gain"], 91.0);
+    assert_eq!(
+        distortion
+            .gear_list
+            .iter()
+            .map(|gear| gear.key.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "Pedal_Compression",
+            "Amp_CA100",
+            "Cab_CA412C_57_Cone",
+            "Rack_StudioEQ"
+        ]
+    );
+
+    // Tone changes switch between the tones
+    let tone_at = |time| {
+        attributes
+            .tone_at(&song.tone_changes, time)
+            .map(|tone| tone.key.as_str())
+    };
+    assert_eq!(tone_at(0.0), Some("Distortion"));
+    assert_eq!(tone_at(60.0), Some("Acoustic"));
+    assert_eq!(tone_at(120.0), Some("Distortion"));
+}
+
+#[test]
+fn test_manifest() {
+    let song_file = SongFile::parse(PSARC).unwrap();
+    let song = &song_file.songs[0];
+    let manifest = &song.manifests[0];
+
+    // Entries are found by the ID of the entity regardless of case
+    let id = &song.entities[0].id;
+    assert_eq!(id, "00498e9999cd470bb7d310575bb85cab");
+    let attributes = manifest.entry(id).unwrap();
+    assert!(std::ptr::eq(attributes, manifest.attributes().unwrap()));
+    assert!(manifest.entry("missing").is_none());
+
+    assert_eq!(attributes.arrangement_type, ArrangementType::Guitar);
+    assert_eq!(attributes.path(), ArrangementPath::Lead);
+    assert!(attributes.path().is_playable());
+    assert!(attributes.arrangement_properties.path_lead);
+    assert!(!attributes.arrangement_properties.path_bass);
+    assert!(attributes.arrangement_properties.open_chords);
+    assert!(!attributes.arrangement_properties.metronome);
+    assert_eq!(
+        attributes.song_key.as_ref().map(SongKey::as_str),
+        Some("ButItRainedSong")
+    );
+
+    let album_art = attributes.album_art.as_ref().unwrap();
+    assert_eq!(album_art.kind(), "image");
+    assert_eq!(album_art.format(), Some("dds"));
+    assert_eq!(album_art.name(), "album_butitrainedsong");
+    let block_asset = attributes.block_asset.as_ref().unwrap();
+    assert_eq!(block_asset.format(), None);
+    assert_eq!(block_asset.name(), "butitrainedsong");
+
+    // Malformed urns are treated as not set
+    let json = String::from_utf8(song_file.read_file(MANIFEST_PATH).unwrap()).unwrap();
+    let malformed = json.replace("urn:image:dds:album_butitrainedsong", "urn:image");
+    assert_ne!(malformed, json);
+    let manifest: Manifest = serde_json::from_str(&malformed).unwrap();
+    let attributes = manifest.attributes().unwrap();
+    assert!(attributes.album_art.is_none());
+    assert!(attributes.block_asset.is_some());
+
+    // An empty song key isn't a key
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    for entry in value["Entries"].as_object_mut().unwrap().values_mut() {
+        entry["Attributes"]["SongKey"] = "".into();
+    }
+    let manifest: Manifest = serde_json::from_value(value).unwrap();
+    assert!(manifest.attributes().unwrap().song_key.is_none());
+
+    // Without an entity there's no entry to pick when there are multiple
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let entries = value["Entries"].as_object_mut().unwrap();
+    let entry = entries.values().next().unwrap().clone();
+    entries.insert("other".to_string(), entry);
+    let manifest: Manifest = serde_json::from_value(value).unwrap();
+    assert!(manifest.attributes().is_none());
+}
+
+#[test]
+fn test_urn() {
+    let urn: Urn = "urn:application:xml:butitrainedsong_lead".parse().unwrap();
+    assert_eq!(urn.kind(), "application");
+    assert_eq!(urn.format(), Some("xml"));
+    assert_eq!(urn.name(), "butitrainedsong_lead");
+    assert_eq!(urn.to_string(), "urn:application:xml:butitrainedsong_lead");
+
+    assert!(Urn::parse("").is_err());
+    assert!(Urn::parse("urn:image").is_err());
+    assert!(Urn::parse("song_butitrainedsong.bnk").is_err());
+}
+
+#[test]
+fn test_music() {
+    let song_file = SongFile::parse(PSARC).unwrap();
+    let song = &song_file.songs[0];
+
+    // Streaming the music from the archive decodes the same samples as reading it at once
+    let wem = song_file.wem(song).unwrap();
+    let streamed = song_file.music_decoder(song).unwrap().take(100_000);
+    assert!(streamed.eq(WemDecoder::new(&wem).unwrap().take(100_000)));
+
+    assert!(song_file.preview_decoder(song).unwrap().is_ok());
+}
+
+#[test]
+fn test_multiple_songs() {
+    let song_file = SongFile::parse(PSARC).unwrap();
+    assert_eq!(song_file.songs.len(), 1);
+    let song = &song_file.songs[0];
+    assert_eq!(song.manifests.len(), 1);
+    assert_eq!(song.song_path(), "audio/mac/1499529296.wem");
+    assert_eq!(song.preview_path(), Some("audio/mac/2085836403.wem"));
+    assert_eq!(
+        song.album_art_path(),
+        Some("gfxassets/album_art/album_butitrainedsong_256.dds")
+    );
+
+    // Add a second song with its own sound bank and music but without a preview
+    let xblock = String::from_utf8(
+        song_file
+            .read_file("gamexblocks/nsongs/butitrainedsong.xblock")
+            .unwrap(),
+    )
+    .unwrap();
+    let other_xblock = xblock.replace("song_butitrainedsong", "song_other");
+    // The sound bank references the music by its ID
+    let wem_id = 1499529296u32.to_le_bytes();
+    let other_wem_id = 1234567890u32.to_le_bytes();
+    let mut bnk = song_file
+        .read_file("audio/mac/song_butitrainedsong.bnk")
+        .unwrap();
+    let mut replaced = 0;
+    for offset in 0..bnk.len() - 4 {
+        if bnk[offset..offset + 4] == wem_id {
+            bnk[offset..offset + 4].copy_from_slice(&other_wem_id);
+            replaced += 1;
+        }
+    }
+    assert!(replaced > 0);
+    let wem = song_file.wem(song).unwrap();
+
+    // Entities without a sound bank belong to the song with the same key
+    let without_sound_bank = xblock.replace(r#"name="SoundBank""#, r#"name="Unused""#);
+    assert_ne!(without_sound_bank, xblock);
+    // Without a manifest there's no key and the entity is skipped
+    let without_manifest = without_sound_bank.replace(r#"name="Manifest""#, r#"name="Unused""#);
+    assert_ne!(without_manifest, without_sound_bank);
+
+    let psarc = PlaystationArchive::parse(PSARC).unwrap();
+    let mut patch = psarc.patch();
+    patch
+        .add_file("gamexblocks/nsongs/other.xblock", other_xblock.into_bytes())
+        .unwrap()
+        .add_file("audio/mac/song_other.bnk", bnk)
+        .unwrap()
+        .add_file("audio/mac/1234567890.wem", wem)
+        .unwrap()
+        .add_file(
+            "gamexblocks/nsongs/without_sound_bank.xblock",
+            without_sound_bank.into_bytes(),
+        )
+        .unwrap()
+        .add_file(
+            "gamexblocks/nsongs/without_manifest.xblock",
+            without_manifest.into_bytes(),
+        )
+        .unwrap();
+    let song_file = SongFile::parse(&patch.build().unwrap()).unwrap();
+
+    assert_eq!(song_file.songs.len(), 2);
+    let (song, other) = (&song_file.songs[0], &song_file.songs[1]);
+    assert_eq!(song.entities.len(), 2);
+    assert_eq!(song.song_path(), "audio/mac/1499529296.wem");
+    assert_eq!(other.entities.len(), 1);
+    assert_eq!(other.manifests.len(), 1);
+    assert_eq!(other.song_path(), "audio/mac/1234567890.wem");
+    assert_eq!(other.preview_path(), None);
+    assert!(song_file.parse_song_info(other, 0).is_ok());
+}
@@ -1,5 +1,6 @@
 use bitvec::{order::Lsb0, prelude::BitVec, view::BitView};
 use nom::{error::context, number::complete::le_u32};
+use nom_error::WithOffset;
 
 use crate::{
     error::{Result, WemError},
@@ -20,22 +21,28 @@ impl<'a> CodebookLibrary<'a> {
     #[profiling::function]
     pub fn from_bytes(bytes: &'a [u8]) -> Result<Self> {
         // Get the last 4 bytes of the stream as the offset for the list of offsets
+        let last = bytes
+            .len()
+            .checked_sub(4)
+            .ok_or_else(|| WemError::MissingData("codebook offsets offset".to_string()))?;
         let (_, offsets_offset) =
-            context("codebook offsets offset", le_u32)(&bytes[(bytes.len() - 4)..])?;
+            context("codebook offsets offset", le_u32)(&bytes[last..]).offset_in(bytes)?;
 
-        let codebook_count = (bytes.len() - offsets_offset as usize) / 4;
-        let data = &bytes[..offsets_offset as usize];
+        let data = bytes
+            .get(..offsets_offset as usize)
+            .ok_or_else(|| WemError::Corrupt("codebook offsets offset".to_string()))?;
+        let codebook_count = (bytes.len() - data.len()) / 4;
 
         // Get all offsets from the remaining data
-        let offsets =
-            (0..codebook_count)
-                .map(|i| {
-                    Ok(context("codebook offset", le_u32)(
-                        &bytes[(offsets_offset as usize + i * 4)..],
-                    )?
-                    .1)
-                })
-                .collect::<Result<Vec<_>>>()?;
+        let offsets = (0..codebook_count)
+            .map(|i| {
+                let (_, offset) =
+                    context("codebook offset", le_u32)(&bytes[(offsets_offset as usize + i * 4)..])
+                        .offset_in(bytes)?;
+
+                Ok(offset)
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         Ok(Self { offsets, data })
     }
@@ -56,17 +63,17 @@ impl<'a> CodebookLibrary<'a> {
         write(0x564342u32, &mut out, 24);
 
         // Read the metadata from the codebook
-        let (i, dimensions): (_, u32) = read(i, 4);
+        let (i, dimensions): (_, u32) = read(i, 4)?;
         write(dimensions, &mut out, 16);
 
-        let (i, entry_count): (_, u32) = read(i, 14);
+        let (i, entry_count): (_, u32) = read(i, 14)?;
         write(entry_count, &mut out, 24);
 
         // Ordered flag
-        let (mut i, ordered) = read_write_bool(i, &mut out);
+        let (mut i, ordered) = read_write_bool(i, &mut out)?;
         if ordered {
             let _initial_length: u8;
-            (i, _initial_length) = read_write(i, &mut out, 5);
+            (i, _initial_length) = read_write(i, &mut out, 5)?;
 
             let mut current_entry = 0;
             while current_entry < entry_count {
@@ -75,14 +82,14 @@ impl<'a> CodebookLibrary<'a> {
                     i,
                     &mut out,
                     crate::utils::log2(entry_count - current_entry) as usize,
-                );
+                )?;
 
                 current_entry += number;
             }
         } else {
             // Codewords
             let codeword_lengths_length: u8;
-            (i, codeword_lengths_length) = read(i, 3);
+            (i, codeword_lengths_length) = read(i, 3)?;
 
             if codeword_lengths_length == 0 || codeword_lengths_length > 5 {
                 return Err(WemError::Corrupt(
@@ -91,13 +98,13 @@ impl<'a> CodebookLibrary<'a> {
             }
 
             let sparse;
-            (i, sparse) = read_write_bool(i, &mut out);
+            (i, sparse) = read_write_bool(i, &mut out)?;
 
             for _ in 0..entry_count {
                 // Read and write the present bool if sparse is set
                 let present = if sparse {
                     let present;
-                    (i, present) = read_write_bool(i, &mut out);
+                    (i, present) = read_write_bool(i, &mut out)?;
 
                     present
                 } else {
@@ -106,34 +113,34 @@ impl<'a> CodebookLibrary<'a> {
 
                 if present {
                     let codeword_length: u8;
-                    (i, codeword_length) = read(i, codeword_lengths_length as usize);
+                    (i, codeword_length) = read(i, codeword_lengths_length as usize)?;
                     write(codeword_length, &mut out, 5);
                 }
             }
         }
 
         // Lookup table
-        let (mut i, lookup_type): (_, u8) = read(i, 1);
+        let (mut i, lookup_type): (_, u8) = read(i, 1)?;
         write(lookup_type, &mut out, 4);
 
         if lookup_type == 1 {
             let _min: u32;
-            (i, _min) = read_write(i, &mut out, 32);
+            (i, _min) = read_write(i, &mut out, 32)?;
 
             let _max: u32;
-            (i, _max) = read_write(i, &mut out, 32);
+            (i, _max) = read_write(i, &mut out, 32)?;
 
             let value_length: u8;
-            (i, value_length) = read_write(i, &mut out, 4);
+            (i, value_length) = read_write(i, &mut out, 4)?;
 
             let sequence_flag;
-            (i, sequence_flag) = read_bool(i);
+            (i, sequence_flag) = read_bool(i)?;
             out.push(sequence_flag);
 
             let quantvals = CodebookLibrary::quantvals(entry_count, dimensions);
             for _ in 0..quantvals {
                 let _val: u32;
-                (i, _val) = read_write(i, &mut out, value_length as usize + 1);
+                (i, _val) = read_write(i, &mut out, value_length as usize + 1)?;
             }
         } else if lookup_type != 0 {
             return Err(WemError::Corrupt("lookup type".to_string()));
@@ -163,13 +170,15 @@ impl<'a> CodebookLibrary<'a> {
             .map(|v| *v as usize)
             .unwrap_or(self.data.len());
 
-        Ok(&self.data[first_offset..last_offset])
+        self.data
+            .get(first_offset..last_offset)
+            .ok_or_else(|| WemError::Corrupt("codebook offset".to_string()))
     }
 
     /// Get the amount of quant values that should be parsed.
     #[profiling::function]
     pub fn quantvals(entries: u32, dimensions: u32) -> u32 {
-        let bits = log2(entries) as u32;
+        let bits = log2(entries);
         let mut vals = entries >> ((bits - 1) * (dimensions - 1) / dimensions);
 
         loop {
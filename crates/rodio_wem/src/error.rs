@@ -1,7 +1,5 @@
-use std::fmt::Debug;
-
 use lewton::{audio::AudioReadError, header::HeaderReadError};
-use nom::{error::VerboseError, Err};
+use nom_error::ParseError;
 use rodio::decoder::DecoderError;
 use thiserror::Error;
 
@@ -23,18 +21,26 @@ pub enum WemError {
     LewtonHeadRead(#[from] HeaderReadError),
     #[error("audio reading error: {0}")]
     AudioReadError(#[from] AudioReadError),
-    #[error("parsing error: {0}")]
-    Nom(String),
+    /// The offset is relative to the start of the parsed bytes.
+    #[error("parsing {context} failed at byte {offset}")]
+    Parse { context: String, offset: usize },
+    #[error("reading {bits} bits but only {remaining} bits are left")]
+    EndOfBits { bits: usize, remaining: usize },
+    #[error("audio packet {index} at byte {offset}: {source}")]
+    Packet {
+        index: usize,
+        offset: usize,
+        source: Box<WemError>,
+    },
     #[error("writing bytes error: {0}")]
     WritingBytes(#[from] std::io::Error),
 }
 
-impl<T: Debug> From<Err<VerboseError<T>>> for WemError {
-    fn from(err: Err<VerboseError<T>>) -> Self {
-        match err {
-            Err::Incomplete(_) => todo!(),
-            Err::Error(err) => Self::Nom(format!("{:?}", err)),
-            Err::Failure(_) => todo!(),
+impl From<ParseError> for WemError {
+    fn from(err: ParseError) -> Self {
+        Self::Parse {
+            context: err.context,
+            offset: err.offset,
         }
     }
 }
@@ -6,7 +6,7 @@ pub mod utils;
 pub use error::WemError;
 
 use std::{
-    io::Write,
+    io::{Cursor, Read, Write},
     time::{Duration, Instant},
     vec::IntoIter,
 };
@@ -21,6 +21,7 @@ use lewton::{
 use nom::{
     branch::alt,
     bytes::complete::{tag, take},
+    combinator::map,
     error::{context, VerboseError},
     number::{
         complete::{u16, u32, u8},
@@ -28,26 +29,29 @@ use nom::{
     },
     IResult,
 };
-use packet::Packet;
+use nom_error::WithOffset;
+use packet::{Packet, PacketReader};
 use rodio::Source;
 
 use crate::{
     codebook::CodebookLibrary,
     error::Result,
-    utils::{log2, read, read_bool, read_write, read_write_bool, write},
+    utils::{log2, read, read_bool, read_bytes, read_write, read_write_bool, write},
 };
 
 /// Decoder for an Wem file.
+///
+/// The audio packets are read from the source while decoding.
 #[derive(Clone)]
-pub struct WemDecoder {
+pub struct WemDecoder<R = Cursor<Vec<u8>>> {
     /// The fmt chunk.
     ///
     /// This is required to be one of the chunks.
     fmt: Fmt,
-    /// The raw data in bytes.
-    packets: Vec<Packet>,
-    /// Index of the current packet.
-    current_packet: usize,
+    /// Source of the audio packets.
+    packets: PacketReader<R>,
+    /// First packet, it's decoded twice because the first read initializes lewton.
+    first_packet: Option<Packet>,
     /// Decoding position.
     previous_window: PreviousWindowRight,
     /// Vorbis identification header.
@@ -66,17 +70,70 @@ pub struct WemDecoder {
 
 impl WemDecoder {
     /// Attempts to decode the data as a wwise file containing vorbis.
-    #[profiling::function]
     pub fn new(bytes: &[u8]) -> Result<WemDecoder> {
-        let WemParser {
-            comment_header,
-            ident_header,
-            setup_header,
-            packets,
-            fmt,
-        } = WemParser::new(bytes)?;
+        Self::from_reader(Cursor::new(bytes.to_vec()))
+    }
+}
+
+impl<R> WemDecoder<R>
+where
+    R: Read,
+{
+    /// Attempts to decode a stream as a wwise file containing vorbis.
+    ///
+    /// Only the headers are read up front, the audio packets are read one at a time while
+    /// decoding. The fmt chunk must come before the data chunk.
+    #[profiling::function]
+    pub fn from_reader(mut reader: R) -> Result<Self> {
+        // Get the endianness and verify the next block is "WAVE"
+        let header = read_bytes(&mut reader, 12, "RIFF header")?;
+        let (i, endianness) = parse_endianness_by_header(&header).offset_in(&header)?;
+        let (i, _riff_size) = context("riff size", u32(endianness))(i).offset_in(&header)?;
+        context("wave block", tag("WAVE"))(i).offset_in(&header)?;
+
+        // Read the chunks until the audio data
+        let mut fmt = None;
+        let data_size = loop {
+            let chunk_header = read_bytes(&mut reader, 8, "data chunk")?;
+            let (i, chunk_type) =
+                context("chunk type", map(take(4usize), chunk_type))(chunk_header.as_slice())
+                    .offset_in(&chunk_header)?;
+            let (_, size) = context("chunk size", u32(endianness))(i).offset_in(&chunk_header)?;
+
+            match &chunk_type {
+                b"fmt " => {
+                    let data = read_bytes(&mut reader, size, "fmt chunk")?;
+                    if let (_, Chunk::Fmt(chunk)) =
+                        parse_fmt_chunk(&data, endianness, size).offset_in(&data)?
+                    {
+                        fmt = Some(chunk);
+                    }
+                }
+                b"data" => break size,
+                // The data is skipped by the size
+                _ => {
+                    std::io::copy(&mut reader.by_ref().take(size as u64), &mut std::io::sink())?;
+                }
+            }
+        };
+
+        let fmt = fmt.ok_or_else(|| WemError::MissingChunk("fmt".to_string()))?;
+        if fmt.channels == 0 {
+            return Err(WemError::Corrupt("fmt chunk channels".to_string()));
+        }
+
+        // The setup packet is stored before the audio packets
+        let audio_size = data_size
+            .checked_sub(fmt.first_audio_packet_offset)
+            .ok_or_else(|| WemError::Corrupt("first audio packet offset".to_string()))?;
+        let setup_data = read_bytes(&mut reader, fmt.first_audio_packet_offset, "setup packet")?;
 
         // Setup the headers
+        let ident_header = fmt.to_ident_packet()?;
+        let comment_header = empty_comment_packet()?;
+        let (setup_header, mode_blockflag, mode_bits) =
+            create_setup_packet(endianness, &fmt, &setup_data)?;
+
         let ident = lewton::header::read_header_ident(&ident_header)?;
         let comment = lewton::header::read_header_comment(&comment_header)?;
         let setup = lewton::header::read_header_setup(
@@ -93,44 +150,65 @@ impl WemDecoder {
             ident,
             comment,
             setup,
-            packets,
+            packets: PacketReader::new(reader, audio_size as u64, mode_blockflag, mode_bits),
+            first_packet: None,
             current_data: Vec::new().into_iter(),
             done: false,
-            current_packet: 0,
             started: Instant::now(),
         };
 
         // The first read initializes lewton
-        this.read_packet()?;
+        let first_packet = this.next_packet()?;
+        this.decode_packet(&first_packet)?;
         // Don't skip the first packet though
-        this.current_packet = 0;
+        this.first_packet = Some(first_packet);
 
         Ok(this)
     }
 
-    /// Get the raw vorbis info.
-    pub fn into_raw(self) -> (HeaderSet, Vec<Packet>) {
-        ((self.ident, self.comment, self.setup), self.packets)
+    /// Get the raw vorbis info with all packets that haven't been decoded yet.
+    pub fn into_raw(mut self) -> Result<(HeaderSet, Vec<Packet>)> {
+        let mut packets = self.first_packet.take().into_iter().collect::<Vec<_>>();
+        while let Some(packet) = self.packets.next_packet()? {
+            packets.push(packet);
+        }
+
+        Ok(((self.ident, self.comment, self.setup), packets))
     }
 
-    /// Read a packet.
+    /// Read and decode a packet.
     #[profiling::function]
     fn read_packet(&mut self) -> Result<()> {
+        let packet = match self.first_packet.take() {
+            Some(packet) => packet,
+            None => self.next_packet()?,
+        };
+        self.decode_packet(&packet)?;
+
+        // We are done when we read all packets
+        self.done = self.packets.is_empty();
+
+        Ok(())
+    }
+
+    /// Read the next packet from the source.
+    fn next_packet(&mut self) -> Result<Packet> {
+        self.packets
+            .next_packet()?
+            .ok_or_else(|| WemError::MissingData("audio packet".to_string()))
+    }
+
+    /// Decode a packet into the current samples.
+    fn decode_packet(&mut self, packet: &Packet) -> Result<()> {
         let audio: InterleavedSamples<_> = lewton::audio::read_audio_packet_generic(
             &self.ident,
             &self.setup,
-            &self.packets[self.current_packet].data,
+            &packet.data,
             &mut self.previous_window,
         )?;
 
         self.current_data = audio.samples.into_iter();
 
-        // Move to the next packet
-        self.current_packet += 1;
-
-        // We are done when we read all packets
-        self.done = self.current_packet == self.packets.len();
-
         Ok(())
     }
 
@@ -140,7 +218,10 @@ impl WemDecoder {
     }
 }
 
-impl Source for WemDecoder {
+impl<R> Source for WemDecoder<R>
+where
+    R: Read,
+{
     #[inline]
     fn current_frame_len(&self) -> Option<usize> {
         let len = self.current_data.len();
@@ -170,7 +251,10 @@ impl Source for WemDecoder {
     }
 }
 
-impl Iterator for WemDecoder {
+impl<R> Iterator for WemDecoder<R>
+where
+    R: Read,
+{
     type Item = i16;
 
     #[inline]
@@ -208,7 +292,7 @@ pub struct WemParser {
     /// The fmt chunk.
     ///
     /// This is required to be one of the chunks.
-    fmt: Fmt,
+    pub fmt: Fmt,
 }
 
 impl WemParser {
@@ -216,22 +300,26 @@ impl WemParser {
     #[profiling::function]
     pub fn new(bytes: &[u8]) -> Result<Self> {
         // Get the endianness
-        let (i, endianness) = parse_endianness_by_header(bytes)?;
+        let (i, endianness) = parse_endianness_by_header(bytes).offset_in(bytes)?;
 
         // Get the size of the riff block
-        let (i, riff_size_u32) = context("endianness", u32(endianness))(i)?;
+        let (i, riff_size_u32) = context("endianness", u32(endianness))(i).offset_in(bytes)?;
         let _riff_size = riff_size_u32 as u64 + 8;
 
         // Verify the next block is "WAVE"
-        let (i, _) = context("wave block", tag("WAVE"))(i)?;
+        let (i, _) = context("wave block", tag("WAVE"))(i).offset_in(bytes)?;
 
         // Read the chunks
-        let (_, chunks) = parse_chunks(i, endianness)?;
+        let (_, chunks) = parse_chunks(i, endianness).offset_in(bytes)?;
 
         // Extract the required chunks
         let fmt = chunks.fmt()?.clone();
         let data = chunks.into_data()?;
 
+        if fmt.channels == 0 {
+            return Err(WemError::Corrupt("fmt chunk channels".to_string()));
+        }
+
         // Setup the headers
         let ident_header = fmt.to_ident_packet()?;
         let comment_header = empty_comment_packet()?;
@@ -239,11 +327,10 @@ impl WemParser {
             create_setup_packet(endianness, &fmt, &data)?;
 
         // Parse the data into packets
-        let packets = packet::parse_into_packets(
-            &data[(fmt.first_audio_packet_offset as usize)..],
-            mode_blockflag,
-            mode_bits,
-        )?;
+        let audio_data = data
+            .get(fmt.first_audio_packet_offset as usize..)
+            .ok_or_else(|| WemError::Corrupt("first audio packet offset".to_string()))?;
+        let packets = packet::parse_into_packets(audio_data, mode_blockflag, mode_bits)?;
 
         Ok(Self {
             comment_header,
@@ -315,6 +402,11 @@ impl Fmt {
 pub enum Chunk {
     Fmt(Fmt),
     Data(Vec<u8>),
+    /// Chunk that's not needed for decoding, such as cue points.
+    Other {
+        chunk_type: [u8; 4],
+        size: u32,
+    },
 }
 
 impl Chunk {
@@ -325,24 +417,21 @@ impl Chunk {
         endianness: Endianness,
     ) -> IResult<&'a [u8], Self, VerboseError<&'a [u8]>> {
         // Get the chunk type string
-        let (i, chunk_type_bytes) = context("chunk type", take(4usize))(i)?;
-        let chunk_type: &[u8; 4] = chunk_type_bytes
-            .try_into()
-            // Should never panic because nom should throw an error when the bytes can't be taken
-            .unwrap();
+        let (i, chunk_type) = context("chunk type", map(take(4usize), chunk_type))(i)?;
 
         // Get the remaining size of this chunk
         let (i, size) = context("chunk size", u32(endianness))(i)?;
 
         // Parse the chunk depending on the type
-        Ok(match chunk_type {
+        Ok(match &chunk_type {
             b"fmt " => parse_fmt_chunk(i, endianness, size)?,
             b"data" => {
-                let (i, data) = take(size)(i)?;
+                let (i, data) = context("data chunk", take(size))(i)?;
 
                 (i, Self::Data(data.to_vec()))
             }
-            _ => todo!(),
+            // The data is skipped by the size
+            _ => (i, Self::Other { chunk_type, size }),
         })
     }
 
@@ -351,6 +440,7 @@ impl Chunk {
         match self {
             Chunk::Fmt(Fmt { size, .. }) => *size,
             Chunk::Data(data) => data.len() as u32,
+            Chunk::Other { size, .. } => *size,
         }
     }
 }
@@ -405,6 +495,14 @@ fn parse_endianness_by_header<'a>(
     ))
 }
 
+/// Convert the taken bytes to the chunk type.
+fn chunk_type(bytes: &[u8]) -> [u8; 4] {
+    let mut chunk_type = [0; 4];
+    chunk_type.copy_from_slice(bytes);
+
+    chunk_type
+}
+
 /// Parse chunks.
 #[profiling::function]
 fn parse_chunks<'a>(
@@ -414,13 +512,14 @@ fn parse_chunks<'a>(
     let mut chunks = Vec::new();
 
     // Keep track of the chunks by way of the reported sizes
-    let mut chunk_offset = 0;
+    let mut chunk_offset = 0usize;
 
-    while (chunk_offset as usize) < i.len() - 12 {
+    // Stop when there are not enough bytes left for a chunk header
+    while chunk_offset + 12 < i.len() {
         // Parse the chunk
-        let (_, chunk) = Chunk::parse(&i[chunk_offset as usize..], endianness)?;
+        let (_, chunk) = Chunk::parse(&i[chunk_offset..], endianness)?;
 
-        chunk_offset += chunk.size() + 8;
+        chunk_offset += chunk.size() as usize + 8;
 
         chunks.push(chunk);
     }
@@ -447,13 +546,13 @@ fn parse_fmt_chunk<'a>(
     let (_, bits_per_sample) = context("fmt chunk bits per sample", u16(endianness))(i)?;
 
     // Read the vorbis data
-    let vorb_data = &data[0x18..];
+    let (vorb_data, _) = context("fmt chunk", take(0x18usize))(data)?;
     let (i, sample_count) = context("fmt vorbis chunk sample count", u32(endianness))(vorb_data)?;
     let (_, mod_signal) = context("fmt vorbis chunk mod signal", u32(endianness))(i)?;
     let mod_packets =
         mod_signal != 0x4A && mod_signal != 0x4B && mod_signal != 0x69 && mod_signal != 0x70;
 
-    let i = &vorb_data[0x10..];
+    let (i, _) = context("fmt vorbis chunk", take(0x10usize))(vorb_data)?;
     let (i, setup_packet_offset) =
         context("fmt vorbis chunk setup packet offset", u32(endianness))(i)?;
     let (_, first_audio_packet_offset) = context(
@@ -461,7 +560,7 @@ fn parse_fmt_chunk<'a>(
         u32(endianness),
     )(i)?;
 
-    let i = &vorb_data[0x24..];
+    let (i, _) = context("fmt vorbis chunk", take(0x24usize))(vorb_data)?;
     let (i, uid) = context("fmt vorbis chunk uid", u32(endianness))(i)?;
     let (i, block_size_0) = context("fmt vorbis chunk block size 0", u8)(i)?;
     let (i, block_size_1) = context("fmt vorbis chunk block size 1", u8)(i)?;
@@ -530,17 +629,19 @@ pub fn create_setup_packet(
     bytes.write_u8(5)?;
 
     // Magic
-    bytes.write("vorbis".as_bytes())?;
+    bytes.write_all("vorbis".as_bytes())?;
 
     // Read the size
-    let (i, _size) =
-        context("setup packet size", u16(endianness))(&data[(fmt.setup_packet_offset as usize)..])?;
+    let setup_data = data
+        .get(fmt.setup_packet_offset as usize..)
+        .ok_or_else(|| WemError::Corrupt("setup packet offset".to_string()))?;
+    let (i, _size) = context("setup packet size", u16(endianness))(setup_data).offset_in(data)?;
 
     // From now on we read individual bits
     let i = i.view_bits::<Lsb0>();
 
     // Get the amount of codebooks
-    let (mut i, codebook_count_minus_one): (_, u16) = read_write(i, &mut bytes, 8);
+    let (mut i, codebook_count_minus_one): (_, u16) = read_write(i, &mut bytes, 8)?;
     let codebook_count = codebook_count_minus_one + 1;
 
     // Rewrite the codebooks
@@ -548,7 +649,7 @@ pub fn create_setup_packet(
     for _ in 0..codebook_count {
         // Get the codebook index
         let id: u16;
-        (i, id) = read(i, 10);
+        (i, id) = read(i, 10)?;
 
         // Rewrite the codebook
         let new_bytes = codebook_lib.rebuild(id as usize)?;
@@ -563,7 +664,7 @@ pub fn create_setup_packet(
     write(0u16, &mut bytes, 16);
 
     // Rebuild floors
-    let (mut i, floor_count_minus_one): (_, u8) = read_write(i, &mut bytes, 6);
+    let (mut i, floor_count_minus_one): (_, u8) = read_write(i, &mut bytes, 6)?;
     let floor_count = floor_count_minus_one + 1;
 
     for _ in 0..floor_count {
@@ -571,14 +672,14 @@ pub fn create_setup_packet(
         write(1u16, &mut bytes, 16);
 
         let floor_partitions: usize;
-        (i, floor_partitions) = read_write(i, &mut bytes, 5);
+        (i, floor_partitions) = read_write(i, &mut bytes, 5)?;
 
         // Build the class list
         let mut floor_partition_class_list = Vec::with_capacity(floor_partitions);
         let mut maximum_class = 0;
         for _ in 0..floor_partitions {
             let floor_partition_class: u8;
-            (i, floor_partition_class) = read_write(i, &mut bytes, 4);
+            (i, floor_partition_class) = read_write(i, &mut bytes, 4)?;
 
             floor_partition_class_list.push(floor_partition_class);
             maximum_class = maximum_class.max(floor_partition_class);
@@ -587,59 +688,59 @@ pub fn create_setup_packet(
         let floor_class_dimensions_list = (0..=maximum_class)
             .map(|_| {
                 let class_dimensions_minus_one: u8;
-                (i, class_dimensions_minus_one) = read_write(i, &mut bytes, 3);
+                (i, class_dimensions_minus_one) = read_write(i, &mut bytes, 3)?;
 
                 let class_subclasses: u8;
-                (i, class_subclasses) = read_write(i, &mut bytes, 2);
+                (i, class_subclasses) = read_write(i, &mut bytes, 2)?;
 
                 if class_subclasses != 0 {
                     let masterbook: u8;
-                    (i, masterbook) = read_write(i, &mut bytes, 8);
+                    (i, masterbook) = read_write(i, &mut bytes, 8)?;
 
                     if masterbook as u16 >= codebook_count {
-                        // TODO: throw proper error
-                        panic!("invalid floor 1 masterbook");
+                        return Err(WemError::Corrupt("floor 1 masterbook".to_string()));
                     }
                 }
 
                 for _ in 0..(1 << class_subclasses as u32) {
                     let subclass_book_plus_one: u8;
-                    (i, subclass_book_plus_one) = read_write(i, &mut bytes, 8);
+                    (i, subclass_book_plus_one) = read_write(i, &mut bytes, 8)?;
 
                     let subclass_book = subclass_book_plus_one as i16 - 1;
                     if subclass_book >= 0 && subclass_book >= codebook_count as i16 {
-                        // TODO: throw proper error
-                        panic!("invalid floor 1 subclass book");
+                        return Err(WemError::Corrupt("floor 1 subclass book".to_string()));
                     }
                 }
 
-                class_dimensions_minus_one + 1
+                Ok(class_dimensions_minus_one + 1)
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>>>()?;
 
         let _floor_multiplier_minus_one: u8;
-        (i, _floor_multiplier_minus_one) = read_write(i, &mut bytes, 2);
+        (i, _floor_multiplier_minus_one) = read_write(i, &mut bytes, 2)?;
 
         let range_bits: usize;
-        (i, range_bits) = read_write(i, &mut bytes, 4);
+        (i, range_bits) = read_write(i, &mut bytes, 4)?;
 
         floor_partition_class_list
             .into_iter()
-            .for_each(|current_class_number| {
+            .try_for_each(|current_class_number| {
                 for _ in 0..floor_class_dimensions_list[current_class_number as usize] {
                     let _x: u16;
-                    (i, _x) = read_write(i, &mut bytes, range_bits);
+                    (i, _x) = read_write(i, &mut bytes, range_bits)?;
                 }
-            });
+
+                Ok::<_, WemError>(())
+            })?;
     }
 
     // Residues
-    let (mut i, residue_count_minus_one): (_, u8) = read_write(i, &mut bytes, 6);
+    let (mut i, residue_count_minus_one): (_, u8) = read_write(i, &mut bytes, 6)?;
     let residue_count = residue_count_minus_one + 1;
 
     for _ in 0..residue_count {
         let residue_type: u16;
-        (i, residue_type) = read(i, 2);
+        (i, residue_type) = read(i, 2)?;
         write(residue_type, &mut bytes, 16);
 
         if residue_type > 2 {
@@ -647,20 +748,20 @@ pub fn create_setup_packet(
         }
 
         let _residue_begin: u32;
-        (i, _residue_begin) = read_write(i, &mut bytes, 24);
+        (i, _residue_begin) = read_write(i, &mut bytes, 24)?;
 
         let _residue_end: u32;
-        (i, _residue_end) = read_write(i, &mut bytes, 24);
+        (i, _residue_end) = read_write(i, &mut bytes, 24)?;
 
         let _residue_partition_size_minus_one: u32;
-        (i, _residue_partition_size_minus_one) = read_write(i, &mut bytes, 24);
+        (i, _residue_partition_size_minus_one) = read_write(i, &mut bytes, 24)?;
 
         let residue_classifications_minus_one: u8;
-        (i, residue_classifications_minus_one) = read_write(i, &mut bytes, 6);
+        (i, residue_classifications_minus_one) = read_write(i, &mut bytes, 6)?;
         let residue_classifications = residue_classifications_minus_one + 1;
 
         let residue_classbook: u8;
-        (i, residue_classbook) = read_write(i, &mut bytes, 8);
+        (i, residue_classbook) = read_write(i, &mut bytes, 8)?;
 
         if residue_classbook as u16 >= codebook_count {
             return Err(WemError::Corrupt("residue classbook".to_string()));
@@ -669,23 +770,23 @@ pub fn create_setup_packet(
         let residue_cascade = (0..residue_classifications)
             .map(|_| {
                 let low_bits: u8;
-                (i, low_bits) = read_write(i, &mut bytes, 3);
+                (i, low_bits) = read_write(i, &mut bytes, 3)?;
 
                 let bit_flag;
-                (i, bit_flag) = read_bool(i);
+                (i, bit_flag) = read_bool(i)?;
                 bytes.push(bit_flag);
                 let high_bits = if bit_flag {
                     let high_bits: u8;
-                    (i, high_bits) = read_write(i, &mut bytes, 5);
+                    (i, high_bits) = read_write(i, &mut bytes, 5)?;
 
                     high_bits
                 } else {
                     0
                 };
 
-                high_bits as u32 * 8 + low_bits as u32
+                Ok(high_bits as u32 * 8 + low_bits as u32)
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>>>()?;
 
         residue_cascade
             .into_iter()
@@ -693,7 +794,7 @@ pub fn create_setup_packet(
                 for k in 0..8 {
                     if (residue_cascade & (1 << k)) > 0 {
                         let residue_book: u8;
-                        (i, residue_book) = read_write(i, &mut bytes, 8);
+                        (i, residue_book) = read_write(i, &mut bytes, 8)?;
 
                         if residue_book as u16 >= codebook_count {
                             return Err(WemError::Corrupt("residue book".to_string()));
@@ -706,7 +807,7 @@ pub fn create_setup_packet(
     }
 
     // Mapping
-    let (mut i, mapping_count_minus_one): (_, u8) = read_write(i, &mut bytes, 6);
+    let (mut i, mapping_count_minus_one): (_, u8) = read_write(i, &mut bytes, 6)?;
     let mapping_count = mapping_count_minus_one + 1;
 
     for _ in 0..mapping_count {
@@ -714,10 +815,10 @@ pub fn create_setup_packet(
         write(0u16, &mut bytes, 16);
 
         let submaps_flag;
-        (i, submaps_flag) = read_write_bool(i, &mut bytes);
+        (i, submaps_flag) = read_write_bool(i, &mut bytes)?;
         let submaps = if submaps_flag {
             let submaps_minus_one: u8;
-            (i, submaps_minus_one) = read_write(i, &mut bytes, 4);
+            (i, submaps_minus_one) = read_write(i, &mut bytes, 4)?;
 
             submaps_minus_one + 1
         } else {
@@ -725,18 +826,26 @@ pub fn create_setup_packet(
         };
 
         let square_polar_flag;
-        (i, square_polar_flag) = read_write_bool(i, &mut bytes);
+        (i, square_polar_flag) = read_write_bool(i, &mut bytes)?;
         if square_polar_flag {
             let coupling_steps_minus_one: u16;
-            (i, coupling_steps_minus_one) = read_write(i, &mut bytes, 8);
+            (i, coupling_steps_minus_one) = read_write(i, &mut bytes, 8)?;
             let coupling_steps = coupling_steps_minus_one + 1;
 
             for _ in 0..coupling_steps {
                 let magnitude: u32;
-                (i, magnitude) = read_write(i, &mut bytes, log2(fmt.channels as u32 - 1) as usize);
+                (i, magnitude) = read_write(
+                    i,
+                    &mut bytes,
+                    log2((fmt.channels as u32).saturating_sub(1)) as usize,
+                )?;
 
                 let angle: u32;
-                (i, angle) = read_write(i, &mut bytes, log2(fmt.channels as u32 - 1) as usize);
+                (i, angle) = read_write(
+                    i,
+                    &mut bytes,
+                    log2((fmt.channels as u32).saturating_sub(1)) as usize,
+                )?;
 
                 if angle == magnitude
                     || magnitude >= fmt.channels as u32
@@ -748,7 +857,7 @@ pub fn create_setup_packet(
         }
 
         let mapping_reserved: u8;
-        (i, mapping_reserved) = read_write(i, &mut bytes, 2);
+        (i, mapping_reserved) = read_write(i, &mut bytes, 2)?;
         if mapping_reserved != 0 {
             return Err(WemError::Corrupt(
                 "mapping reserved field nonzero".to_string(),
@@ -758,7 +867,7 @@ pub fn create_setup_packet(
         if submaps > 1 {
             for _ in 0..fmt.channels {
                 let mapping_mux: u8;
-                (i, mapping_mux) = read_write(i, &mut bytes, 4);
+                (i, mapping_mux) = read_write(i, &mut bytes, 4)?;
 
                 if mapping_mux >= submaps {
                     return Err(WemError::Corrupt("mapping mux >= submaps".to_string()));
@@ -768,16 +877,16 @@ pub fn create_setup_packet(
 
         for _ in 0..submaps {
             let _time_config: u8;
-            (i, _time_config) = read_write(i, &mut bytes, 8);
+            (i, _time_config) = read_write(i, &mut bytes, 8)?;
 
             let floor_number: u8;
-            (i, floor_number) = read_write(i, &mut bytes, 8);
+            (i, floor_number) = read_write(i, &mut bytes, 8)?;
             if floor_number >= floor_count {
                 return Err(WemError::Corrupt("floor mapping".to_string()));
             }
 
             let residue_number: u8;
-            (i, residue_number) = read_write(i, &mut bytes, 8);
+            (i, residue_number) = read_write(i, &mut bytes, 8)?;
             if residue_number >= residue_count {
                 return Err(WemError::Corrupt("residue mapping".to_string()));
             }
@@ -785,13 +894,13 @@ pub fn create_setup_packet(
     }
 
     // Mode count
-    let (mut i, mode_count_minus_one): (_, u8) = read_write(i, &mut bytes, 6);
+    let (mut i, mode_count_minus_one): (_, u8) = read_write(i, &mut bytes, 6)?;
     let mode_count = mode_count_minus_one + 1;
 
     let mode_blockflag = (0..mode_count)
         .map(|_| {
             let block_flag;
-            (i, block_flag) = read_write_bool(i, &mut bytes);
+            (i, block_flag) = read_write_bool(i, &mut bytes)?;
 
             // Window type
             write(0u16, &mut bytes, 16);
@@ -799,7 +908,7 @@ pub fn create_setup_packet(
             write(0u16, &mut bytes, 16);
 
             let mapping: u8;
-            (i, mapping) = read_write(i, &mut bytes, 8);
+            (i, mapping) = read_write(i, &mut bytes, 8)?;
             if mapping >= mapping_count {
                 Err(WemError::Corrupt("invalid mode mapping".to_string()))
             } else {
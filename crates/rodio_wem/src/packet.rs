@@ -1,10 +1,13 @@
+use std::io::Read;
+
 use bitvec::{order::Lsb0, prelude::BitVec, view::BitView};
 
 use nom::{error::context, number::complete::le_u16};
+use nom_error::WithOffset;
 
 use crate::{
-    error::Result,
-    utils::{read, read_write, write},
+    error::{Result, WemError},
+    utils::{read, read_bytes, read_write, write},
 };
 
 /// A vorbis data packet.
@@ -24,8 +27,11 @@ impl Packet {
         mode_bits: usize,
         previous_window_flag: bool,
     ) -> Result<(&'a [u8], Self)> {
-        let (packet, size) = context("packet size", le_u16)(data)?;
+        let (packet, size) = context("packet size", le_u16)(data).offset_in(data)?;
         let size = size as usize;
+        if size == 0 || size > packet.len() {
+            return Err(WemError::Corrupt("packet size".to_string()));
+        }
 
         // Change the mod flags
         let mut bits = BitVec::<u8, Lsb0>::new();
@@ -35,12 +41,14 @@ impl Packet {
 
         // Get mode number from first byte
         let i = packet.view_bits();
-        let (i, mode_number): (_, u8) = read_write(i, &mut bits, mode_bits);
+        let (i, mode_number): (_, u8) = read_write(i, &mut bits, mode_bits)?;
 
         // Read the rest of the input bits
-        let (_, remainder): (_, u8) = read(i, 8 - mode_bits);
+        let (_, remainder): (_, u8) = read(i, 8 - mode_bits)?;
 
-        let current_mode_block_flag = mode_block_flags[mode_number as usize];
+        let current_mode_block_flag = *mode_block_flags
+            .get(mode_number as usize)
+            .ok_or_else(|| WemError::Corrupt("packet mode number".to_string()))?;
         if current_mode_block_flag {
             // Long window, look at next frame
             let next_block = &packet[size..];
@@ -48,11 +56,13 @@ impl Packet {
                 false
             } else {
                 let (next_block, next_block_size) =
-                    context("next packet size", le_u16)(next_block)?;
+                    context("next packet size", le_u16)(next_block).offset_in(data)?;
                 if next_block_size > 0 {
-                    let (_, next_mode_number): (_, u8) = read(next_block.view_bits(), mode_bits);
+                    let (_, next_mode_number): (_, u8) = read(next_block.view_bits(), mode_bits)?;
 
-                    mode_block_flags[next_mode_number as usize]
+                    *mode_block_flags
+                        .get(next_mode_number as usize)
+                        .ok_or_else(|| WemError::Corrupt("next packet mode number".to_string()))?
                 } else {
                     false
                 }
@@ -95,12 +105,14 @@ impl std::fmt::Debug for Packet {
 /// Parse the data bytes into packets.
 #[profiling::function]
 pub fn parse_into_packets(
-    mut i: &[u8],
+    data: &[u8],
     mode_block_flag: Vec<bool>,
     mode_bits: u32,
 ) -> Result<Vec<Packet>> {
     let mut packets = Vec::new();
 
+    let mut i = data;
+
     // Keep track of the block_flag of the previous packet
     let mut previous_mode_block_flag = false;
     while !i.is_empty() {
@@ -110,7 +122,12 @@ pub fn parse_into_packets(
             &mode_block_flag,
             mode_bits as usize,
             previous_mode_block_flag,
-        )?;
+        )
+        .map_err(|err| WemError::Packet {
+            index: packets.len(),
+            offset: data.len() - i.len(),
+            source: Box::new(err),
+        })?;
         previous_mode_block_flag = packet.mode_block_flag;
 
         packets.push(packet);
@@ -118,3 +135,122 @@ pub fn parse_into_packets(
 
     Ok(packets)
 }
+
+/// Reads the audio packets one at a time while streaming the data chunk.
+#[derive(Clone)]
+pub(crate) struct PacketReader<R> {
+    /// Source positioned at the next packet.
+    reader: R,
+    /// Amount of bytes of the data chunk that haven't been read yet.
+    remaining: u64,
+    /// Raw bytes of the next packet including its size, the window of a packet depends on it.
+    next: Option<Vec<u8>>,
+    /// Block flags of the modes from the setup packet.
+    mode_block_flag: Vec<bool>,
+    /// Amount of bits used for the mode number.
+    mode_bits: usize,
+    /// Block flag of the previous packet.
+    previous_mode_block_flag: bool,
+    /// Index of the next packet.
+    index: usize,
+    /// Offset of the next packet in the audio data.
+    offset: usize,
+}
+
+impl<R> PacketReader<R>
+where
+    R: Read,
+{
+    /// Read the packets from a source positioned at the first audio packet.
+    pub(crate) fn new(reader: R, length: u64, mode_block_flag: Vec<bool>, mode_bits: u32) -> Self {
+        Self {
+            reader,
+            remaining: length,
+            next: None,
+            mode_block_flag,
+            mode_bits: mode_bits as usize,
+            previous_mode_block_flag: false,
+            index: 0,
+            offset: 0,
+        }
+    }
+
+    /// Whether all packets have been read.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.next.is_none() && self.remaining == 0
+    }
+
+    /// Read the next packet, `None` when all packets have been read.
+    #[profiling::function]
+    pub(crate) fn next_packet(&mut self) -> Result<Option<Packet>> {
+        let mut data = match self.next.take() {
+            Some(data) => data,
+            None => match self.read_raw(self.index, self.offset)? {
+                Some(data) => data,
+                None => return Ok(None),
+            },
+        };
+        let size = data.len();
+
+        // Look ahead at the mode of the next packet, the size and a single byte are enough
+        self.next = self.read_raw(self.index + 1, self.offset + size)?;
+        if let Some(next) = &self.next {
+            data.extend_from_slice(&next[..next.len().min(3)]);
+        }
+
+        let (_, packet) = Packet::parse(
+            &data,
+            &self.mode_block_flag,
+            self.mode_bits,
+            self.previous_mode_block_flag,
+        )
+        .map_err(|err| packet_error(self.index, self.offset, err))?;
+        self.previous_mode_block_flag = packet.mode_block_flag;
+
+        self.index += 1;
+        self.offset += size;
+
+        Ok(Some(packet))
+    }
+
+    /// Read the bytes of a single packet including its size.
+    fn read_raw(&mut self, index: usize, offset: usize) -> Result<Option<Vec<u8>>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        if self.remaining < 2 {
+            return Err(packet_error(
+                index,
+                offset,
+                WemError::Corrupt("packet size".to_string()),
+            ));
+        }
+
+        let mut data = read_bytes(&mut self.reader, 2, "packet size")
+            .map_err(|err| packet_error(index, offset, err))?;
+        let size = u16::from_le_bytes([data[0], data[1]]) as u64;
+        if size == 0 || size + 2 > self.remaining {
+            return Err(packet_error(
+                index,
+                offset,
+                WemError::Corrupt("packet size".to_string()),
+            ));
+        }
+
+        let packet = read_bytes(&mut self.reader, size as u32, "packet data")
+            .map_err(|err| packet_error(index, offset, err))?;
+        data.extend(packet);
+        self.remaining -= size + 2;
+
+        Ok(Some(data))
+    }
+}
+
+/// Add the position of the packet to an error.
+fn packet_error(index: usize, offset: usize, err: WemError) -> WemError {
+    WemError::Packet {
+        index,
+        offset,
+        source: Box::new(err),
+    }
+}
@@ -1,34 +1,66 @@
+use std::io::Read;
+
 use bitvec::{
     field::BitField, macros::internal::funty::Integral, order::Lsb0, prelude::BitVec,
     slice::BitSlice, store::BitStore, view::BitView,
 };
 
+use crate::error::{Result, WemError};
+
+/// Read an exact amount of bytes, the name is used for the error when the input ends too soon.
+pub fn read_bytes<R>(reader: &mut R, length: u32, name: &str) -> Result<Vec<u8>>
+where
+    R: Read,
+{
+    // Don't allocate everything up front when a corrupt length is huge
+    let mut bytes = Vec::new();
+    reader.take(length as u64).read_to_end(&mut bytes)?;
+
+    if bytes.len() == length as usize {
+        Ok(bytes)
+    } else {
+        Err(WemError::MissingData(name.to_string()))
+    }
+}
+
 /// Read bits from bit slice and return the rest of the slice.
-pub fn read<I, T>(slice: &BitSlice<T, Lsb0>, bits: usize) -> (&BitSlice<T, Lsb0>, I)
+pub fn read<I, T>(slice: &BitSlice<T, Lsb0>, bits: usize) -> Result<(&BitSlice<T, Lsb0>, I)>
 where
     I: Integral,
     T: BitStore,
 {
-    let result = slice[..bits].load_le();
+    if bits > slice.len() {
+        return Err(WemError::EndOfBits {
+            bits,
+            remaining: slice.len(),
+        });
+    }
+
+    // Loading from an empty slice isn't allowed
+    let result = if bits == 0 {
+        I::ZERO
+    } else {
+        slice[..bits].load_le()
+    };
 
-    (&slice[bits..], result)
+    Ok((&slice[bits..], result))
 }
 
 /// Read bits from bit slice, write them to a bit vec and return the rest.
-pub fn read_write<'a, 'b, I, T>(
-    slice: &'b BitSlice<T, Lsb0>,
-    out: &'a mut BitVec<T, Lsb0>,
+pub fn read_write<'a, I, T>(
+    slice: &'a BitSlice<T, Lsb0>,
+    out: &mut BitVec<T, Lsb0>,
     bits: usize,
-) -> (&'b BitSlice<T, Lsb0>, I)
+) -> Result<(&'a BitSlice<T, Lsb0>, I)>
 where
     I: Integral + BitStore,
     T: BitStore,
 {
-    let result: I = slice[..bits].load_le();
+    let (slice, result) = read(slice, bits)?;
 
     write(result, out, bits);
 
-    (&slice[bits..], result)
+    Ok((slice, result))
 }
 
 /// Write the number to the bitvec.
@@ -41,29 +73,28 @@ where
 }
 
 /// Read a single bool from the bit slice and return the rest.
-pub fn read_bool<T>(slice: &BitSlice<T, Lsb0>) -> (&BitSlice<T, Lsb0>, bool)
+pub fn read_bool<T>(slice: &BitSlice<T, Lsb0>) -> Result<(&BitSlice<T, Lsb0>, bool)>
 where
     T: BitStore,
 {
-    let result: u8 = slice[..1].load_le();
+    let (slice, result): (_, u8) = read(slice, 1)?;
 
-    (&slice[1..], result != 0)
+    Ok((slice, result != 0))
 }
 
 /// Read bool from bit slice, write them to a bit vec and return the rest.
-pub fn read_write_bool<'a, 'b, T>(
-    slice: &'b BitSlice<T, Lsb0>,
-    out: &'a mut BitVec<T, Lsb0>,
-) -> (&'b BitSlice<T, Lsb0>, bool)
+pub fn read_write_bool<'a, T>(
+    slice: &'a BitSlice<T, Lsb0>,
+    out: &mut BitVec<T, Lsb0>,
+) -> Result<(&'a BitSlice<T, Lsb0>, bool)>
 where
     T: BitStore,
 {
-    let result: u8 = slice[..1].load_le();
-    let result = result != 0;
+    let (slice, result) = read_bool(slice)?;
 
     out.push(result);
 
-    (&slice[1..], result)
+    Ok((slice, result))
 }
 
 /// Non-nightly `u32::log2'.
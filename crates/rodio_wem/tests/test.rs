@@ -0,0 +1,67 @@
+use proptest::prelude::*;
+use rodio_wem::{WemDecoder, WemError, WemParser};
+
+const WEM: &[u8] = include_bytes!("./test.wem");
+
+#[test]
+fn test_parse() {
+    let parser = WemParser::new(WEM).unwrap();
+    assert_eq!(parser.packets.len(), 1429);
+
+    WemDecoder::new(WEM).unwrap();
+}
+
+#[test]
+fn test_stream() {
+    let parser = WemParser::new(WEM).unwrap();
+
+    // Streaming reads the same packets as parsing all bytes at once
+    let decoder = WemDecoder::from_reader(WEM).unwrap();
+    let (_, packets) = decoder.into_raw().unwrap();
+    assert_eq!(packets.len(), parser.packets.len());
+    assert!(packets
+        .iter()
+        .zip(&parser.packets)
+        .all(|(packet, parsed)| packet.data == parsed.data));
+
+    // A truncated stream plays until it ends and fails at the packet where it ends
+    assert!(
+        WemDecoder::from_reader(&WEM[..WEM.len() / 2])
+            .unwrap()
+            .count()
+            > 0
+    );
+    assert!(matches!(
+        WemDecoder::from_reader(&WEM[..WEM.len() / 2])
+            .unwrap()
+            .into_raw(),
+        Err(WemError::Packet { .. })
+    ));
+}
+
+#[test]
+fn test_truncated_offset() {
+    // Only the RIFF header is left
+    assert!(matches!(
+        WemParser::new(&WEM[..6]),
+        Err(WemError::Parse { offset: 4, .. })
+    ));
+}
+
+proptest! {
+    // Parsing the whole file is slow in debug builds
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn truncated_doesnt_panic(length in 0..WEM.len()) {
+        let _ = WemParser::new(&WEM[..length]);
+    }
+
+    #[test]
+    fn bit_flipped_doesnt_panic(bit in 0..WEM.len() * 8) {
+        let mut bytes = WEM.to_vec();
+        bytes[bit / 8] ^= 1 << (bit % 8);
+
+        let _ = WemParser::new(&bytes);
+    }
+}
@@ -1,14 +1,17 @@
 use std::path::{Path, PathBuf};
 
 use bevy::{
-    asset::{AssetIo, AssetIoError, BoxedFuture, Metadata},
+    asset::{AssetIo, AssetIoError, BoxedFuture, FileType, Metadata},
     prelude::{App, AssetServer, Plugin},
     tasks::IoTaskPool,
 };
 
 use crate::LOADED_SONG;
 
-/// Rocksmith archive representing a bevy virtual file system.
+/// Rocksmith archives representing a bevy virtual file system.
+///
+/// Paths found in the archives of the loaded song are read from there, everything else from the
+/// regular filesystem.
 pub struct Filesystem {
     /// The regular bevy filesystem IO is used.
     file: Box<dyn AssetIo>,
@@ -17,22 +20,34 @@ pub struct Filesystem {
 impl AssetIo for Filesystem {
     fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
         if let Some(song) = &*LOADED_SONG.lock().unwrap() {
-            // Load a file from the archive
-            let bytes = song
-                .read_file(path.to_str().expect("could not convert path to psarc path"))
-                // TODO: use proper errors
-                .expect("could not read path in psarc file");
-
-            Box::pin(async move { Ok(bytes) })
-        } else {
-            self.file.load_path(path)
+            if let Some(entry) = path.to_str().and_then(|path| song.archive.resolve(path)) {
+                // Load a file from the archive
+                let result = entry.read().map_err(|err| {
+                    AssetIoError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+                });
+
+                return Box::pin(async move { result });
+            }
         }
+
+        self.file.load_path(path)
     }
 
     fn read_directory(
         &self,
         path: &Path,
     ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        if let Some(song) = &*LOADED_SONG.lock().unwrap() {
+            let children = path
+                .to_str()
+                .map(|path| song.archive.read_directory(path))
+                .unwrap_or_default();
+
+            if !children.is_empty() {
+                return Ok(Box::new(children.into_iter().map(PathBuf::from)));
+            }
+        }
+
         self.file.read_directory(path)
     }
 
@@ -45,6 +60,14 @@ impl AssetIo for Filesystem {
     }
 
     fn get_metadata(&self, path: &Path) -> Result<Metadata, AssetIoError> {
+        if let (Some(song), Some(path)) = (&*LOADED_SONG.lock().unwrap(), path.to_str()) {
+            if song.archive.contains(path) {
+                return Ok(Metadata::new(FileType::File));
+            } else if song.archive.is_directory(path) {
+                return Ok(Metadata::new(FileType::Directory));
+            }
+        }
+
         self.file.get_metadata(path)
     }
 }
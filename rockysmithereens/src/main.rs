@@ -58,8 +58,10 @@ pub enum Phase {
 pub struct State {
     /// Song asset.
     handle: Handle<RocksmithAsset>,
-    /// Which song got selected.
-    current_song: Option<usize>,
+    /// Which song of the archive got selected.
+    current_song: usize,
+    /// Which arrangement of the song got selected.
+    current_arrangement: Option<usize>,
     /// The current difficulty.
     difficulty: usize,
 }
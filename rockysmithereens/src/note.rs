@@ -4,10 +4,11 @@ use crate::{Phase, State, LOADED_SONG};
 use bevy::{
     pbr::{PbrBundle, StandardMaterial},
     prelude::{
-        shape::Cube, App, Assets, Color, Commands, Component, Mesh, Plugin, Res, ResMut, SystemSet,
-        Transform,
+        shape::Cube, App, Assets, Color, Commands, Component, Entity, Mesh, Plugin, Query, Res,
+        ResMut, SystemSet, Transform, With,
     },
 };
+use rockysmithereens_parser::song::{DifficultyNotes, Song};
 
 /// How high each note will get.
 pub const Y_NOTE_SCALE: f32 = 1.2;
@@ -127,7 +128,8 @@ pub struct NotePlugin;
 
 impl Plugin for NotePlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(Phase::Loading).with_system(inject_notes));
+        app.add_system_set(SystemSet::on_enter(Phase::Loading).with_system(inject_notes))
+            .add_system_set(SystemSet::on_update(Phase::Playing).with_system(change_difficulty));
     }
 }
 
@@ -142,38 +144,79 @@ fn inject_notes(
 ) {
     if let Some(song) = &*LOADED_SONG.lock().unwrap() {
         // TODO: handle errors
-        let parsed_song = song.parse_song_info(state.current_song.unwrap()).unwrap();
-
-        for note in parsed_song.notes_iter() {
-            // Spawn the notes
-            let mut entity = commands.spawn();
-            entity.insert(Note);
-
-            entity.insert(TriggerTime(note.time));
-
-            let string = StringNumber::from(note.string);
-            entity.insert(string);
-
-            // The fret
-            let fret = Fret::from(note.fret);
-            entity.insert(fret);
-
-            if let Some(x) = fret.x() {
-                // The mesh
-                entity.insert_bundle(PbrBundle {
-                    mesh: meshes.add(Mesh::from(Cube { size: 1.0 })),
-                    // Color of the mesh is based on the string
-                    material: materials.add(Color::from(string).into()),
-                    transform: Transform::from_xyz(x, string.y(), note.time * Z_NOTE_SCALE),
-                    ..Default::default()
-                });
-            }
-        }
+        let parsed_song = song
+            .parse_song_info(
+                &song.songs[state.current_song],
+                state.current_arrangement.unwrap(),
+            )
+            .unwrap();
+
+        let notes = parsed_song.notes_for_difficulty(state.difficulty as u8);
+        spawn_notes(&mut commands, &mut meshes, &mut materials, &notes);
 
-        // Add it as a resource
+        // Add them as resources
         commands.insert_resource(parsed_song);
+        commands.insert_resource(notes);
 
         // We are ready to play
         phase.overwrite_set(Phase::Playing).unwrap();
     }
 }
+
+/// Replace the notes when the difficulty is changed while playing.
+#[profiling::function]
+fn change_difficulty(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    state: Res<State>,
+    song: Res<Song>,
+    mut notes: ResMut<DifficultyNotes>,
+    spawned: Query<Entity, With<Note>>,
+) {
+    let difficulty = state.difficulty as u8;
+    if notes.difficulty == difficulty {
+        return;
+    }
+
+    spawned
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn());
+
+    *notes = song.notes_for_difficulty(difficulty);
+    spawn_notes(&mut commands, &mut meshes, &mut materials, &notes);
+}
+
+/// Spawn an entity for every note.
+fn spawn_notes(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    notes: &DifficultyNotes,
+) {
+    for note in notes.notes.iter() {
+        // Spawn the notes
+        let mut entity = commands.spawn();
+        entity.insert(Note);
+
+        entity.insert(TriggerTime(note.time));
+
+        let string = StringNumber::from(note.string);
+        entity.insert(string);
+
+        // The fret
+        let fret = Fret::from(note.fret);
+        entity.insert(fret);
+
+        if let Some(x) = fret.x() {
+            // The mesh
+            entity.insert_bundle(PbrBundle {
+                mesh: meshes.add(Mesh::from(Cube { size: 1.0 })),
+                // Color of the mesh is based on the string
+                material: materials.add(Color::from(string).into()),
+                transform: Transform::from_xyz(x, string.y(), note.time * Z_NOTE_SCALE),
+                ..Default::default()
+            });
+        }
+    }
+}
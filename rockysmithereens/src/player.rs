@@ -117,9 +117,10 @@ fn load_song(
     asset_server: Res<AssetServer>,
     audio: Res<Audio<WemSource>>,
     sinks: Res<Assets<AudioSink>>,
+    state: Res<State>,
 ) {
     if let Some(song) = &*LOADED_SONG.lock().unwrap() {
-        let music = asset_server.load(song.song_path());
+        let music = asset_server.load(song.songs[state.current_song].song_path());
         let handle = sinks.get_handle(audio.play(music.clone_weak()));
         commands.insert_resource(MusicController::new(handle, music));
     }
@@ -1,6 +1,6 @@
-use std::{fs::File, io::Read, path::PathBuf};
+use std::{fs::File, path::PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use bevy::prelude::{App, Commands, Component, Entity, Plugin, Query, SystemSet};
 use rockysmithereens_parser::SongFile;
 
@@ -41,17 +41,15 @@ fn load_preview(mut commands: Commands, query: Query<(Entity, &UnloadedPath)>) {
         commands.entity(entity).despawn();
 
         let _result: Result<()> = (|| {
-            // Read the .psarc file
-            let mut file = File::open(path)?;
-            let metadata = std::fs::metadata(path)?;
+            // Open the .psarc file, only the parts needed for the preview will be read
+            let file = File::open(path)?;
 
-            // Read the bytes
-            let mut bytes = vec![0; metadata.len() as usize];
-            file.read_exact(&mut bytes)?;
+            let songfile = SongFile::from_reader(file)?;
 
-            let songfile = SongFile::parse(&bytes)?;
-
-            let attributes = songfile.manifests[0].attributes();
+            // Song packs are shown by their first song
+            let attributes = songfile.songs[0].manifests[0]
+                .attributes()
+                .context("missing song attributes")?;
 
             // Insert the preview
             commands.spawn().insert(Preview {
@@ -15,11 +15,20 @@ pub fn ui(
     mut phase: ResMut<bevy::prelude::State<Phase>>,
     mut album_art_image_handle: Local<Handle<BevyImage>>,
     mut album_art_texture: Local<Option<TextureId>>,
+    mut album_art_song: Local<Option<usize>>,
     assets: ResMut<Assets<BevyImage>>,
 ) {
-    if let Some(song) = &*LOADED_SONG.lock().unwrap() {
-        if assets.get(&album_art_image_handle.clone_weak()).is_none() {
+    if let Some(song_file) = &*LOADED_SONG.lock().unwrap() {
+        // Song packs contain more than one song
+        state.current_song = state.current_song.min(song_file.songs.len() - 1);
+        let song = &song_file.songs[state.current_song];
+
+        if *album_art_song != Some(state.current_song)
+            || assets.get(&album_art_image_handle.clone_weak()).is_none()
+        {
             // Load the album art
+            *album_art_song = Some(state.current_song);
+            *album_art_texture = None;
             if let Some(path) = song.album_art_path() {
                 *album_art_image_handle = asset_server.load(path);
                 *album_art_texture = Some(context.add_image(album_art_image_handle.clone_weak()));
@@ -28,8 +37,28 @@ pub fn ui(
 
         // A song has been loaded
         CentralPanel::default().show(context.ctx_mut(), |ui| {
+            // Select the song when there are multiple
+            if song_file.songs.len() > 1 {
+                ui.horizontal_wrapped(|ui| {
+                    for (i, pack_song) in song_file.songs.iter().enumerate() {
+                        if let Some(attributes) = pack_song
+                            .manifests
+                            .get(0)
+                            .and_then(|manifest| manifest.attributes())
+                        {
+                            ui.selectable_value(&mut state.current_song, i, attributes.name());
+                        }
+                    }
+                });
+                ui.separator();
+            }
+
             // Get the first manifest for the song information
-            if let Some(manifest) = song.manifests.get(0) {
+            if let Some(attributes) = song
+                .manifests
+                .get(0)
+                .and_then(|manifest| manifest.attributes())
+            {
                 ui.horizontal(|ui| {
                     // Show the album art if loaded
                     if let Some(album_art_texture) = *album_art_texture {
@@ -37,7 +66,6 @@ pub fn ui(
                     }
 
                     ui.vertical(|ui| {
-                        let attributes = manifest.attributes();
                         ui.horizontal_wrapped(|ui| {
                             ui.label(&attributes.song_name);
                             ui.label("-");
@@ -55,14 +83,20 @@ pub fn ui(
                 });
             }
 
-            // List the different songs
+            // List the different arrangements
             ScrollArea::vertical().show(ui, |ui| {
                 for (i, manifest) in song.manifests.iter().enumerate() {
-                    ui.group(|ui| {
-                        let attributes = manifest.attributes();
+                    // Vocals, showlights and arrangements without attributes have nothing to play
+                    let attributes = match manifest.attributes() {
+                        Some(attributes) if attributes.path().is_playable() => attributes,
+                        _ => continue,
+                    };
 
+                    ui.group(|ui| {
                         if ui.button(&attributes.arrangement_name).clicked() {
-                            state.current_song = Some(i);
+                            state.current_arrangement = Some(i);
+                            // Start with every phrase at its hardest level
+                            state.difficulty = attributes.max_phrase_difficulty as usize;
 
                             phase.set(Phase::Loading).unwrap();
                         }
@@ -14,14 +14,19 @@ pub fn ui(
     controller: Res<MusicController>,
     mut phase: ResMut<bevy::prelude::State<Phase>>,
 ) {
-    if let Some(current_song) = state.current_song {
-        if let Some(song) = &*LOADED_SONG.lock().unwrap() {
+    if let Some(current_arrangement) = state.current_arrangement {
+        if let Some(song_file) = &*LOADED_SONG.lock().unwrap() {
+            let song = &song_file.songs[state.current_song];
+
             // A song has been loaded
             TopBottomPanel::top("topbar").show(context.ctx_mut(), |ui| {
                 // Get the first manifest for the song information
-                if let Some(manifest) = song.manifests.get(0) {
+                if let Some(attributes) = song
+                    .manifests
+                    .get(0)
+                    .and_then(|manifest| manifest.attributes())
+                {
                     ui.horizontal(|ui| {
-                        let attributes = manifest.attributes();
                         ui.horizontal_wrapped(|ui| {
                             ui.label(&attributes.song_name);
                             ui.label("-");
@@ -47,17 +52,21 @@ pub fn ui(
                             ui.label("Difficulty");
                             ui.add(
                                 DragValue::new(&mut state.difficulty)
-                                    .clamp_range(1..=attributes.max_phrase_difficulty as usize),
+                                    .clamp_range(0..=attributes.max_phrase_difficulty as usize),
                             );
                         }
                     });
                 }
 
                 // Show the progress of the current song
-                let attributes = song.manifests[current_song].attributes();
-
-                // Show the phrases
-                super::phrases_plot::ui(ui, attributes, Some(controller.time_playing()));
+                if let Some(attributes) = song
+                    .manifests
+                    .get(current_arrangement)
+                    .and_then(|manifest| manifest.attributes())
+                {
+                    // Show the phrases
+                    super::phrases_plot::ui(ui, attributes, Some(controller.time_playing()));
+                }
             });
         }
     }
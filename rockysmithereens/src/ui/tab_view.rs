@@ -7,7 +7,7 @@ use bevy_egui::{
     },
     EguiContext,
 };
-use rockysmithereens_parser::song::Song;
+use rockysmithereens_parser::{note::Techniques, song::DifficultyNotes};
 
 /// Until how many seconds after playing the notes should be shown.
 const NOTE_KEEP_PLAYING_TIME: f32 = 3.0;
@@ -19,7 +19,7 @@ const BEND_FACTOR: f32 = 0.2;
 pub fn ui(
     mut context: ResMut<EguiContext>,
     music_controller: Res<MusicController>,
-    song: Res<Song>,
+    notes: Res<DifficultyNotes>,
     mut visible: Local<bool>,
 ) {
     TopBottomPanel::bottom("notes").show(context.ctx_mut(), |ui| {
@@ -27,10 +27,9 @@ pub fn ui(
             let time_playing_secs = music_controller.time_playing().as_secs_f32();
 
             // Get the notes that will be played soon
-            let notes = song.notes_between_time_iter(
+            let notes = notes.between(
                 time_playing_secs - NOTE_KEEP_PLAYING_TIME,
                 time_playing_secs + NOTE_SPAWN_TIME,
-                200,
             );
 
             if ui.button("Hide Tab").clicked() {
@@ -54,7 +53,7 @@ pub fn ui(
                 .height(300.0)
                 .show(ui, |plot_ui| {
                     // Each regular note
-                    notes.for_each(|note| {
+                    notes.iter().for_each(|note| {
                         // Get the starting position of the note
                         let x = note.time - time_playing_secs;
                         let pos = [
@@ -89,16 +88,15 @@ pub fn ui(
 
                         // Draw the number for the note if it's not an intermediate note
                         if note.show {
-                            if note.slide_to_next {
-                                // Draw slides differently
-                                plot_ui.text(
-                                    Text::new(pos.into(), format!("{}>", note.fret)).color(color),
-                                );
-                            } else {
-                                plot_ui.text(
-                                    Text::new(pos.into(), format!("{}", note.fret)).color(color),
-                                );
-                            }
+                            // Draw slides with the target fret
+                            let label = match note.slide_to.or(note.slide_unpitch_to) {
+                                Some(slide_to) => format!("{}/{slide_to}", note.fret),
+                                None => format!("{}", note.fret),
+                            };
+                            plot_ui.text(
+                                Text::new(pos.into(), label + technique_suffix(note.techniques))
+                                    .color(color),
+                            );
                         }
                     });
 
@@ -113,6 +111,25 @@ pub fn ui(
     });
 }
 
+/// Tablature letters for the techniques of a note.
+fn technique_suffix(techniques: Techniques) -> &'static str {
+    if techniques.contains(Techniques::HAMMER_ON) {
+        "h"
+    } else if techniques.contains(Techniques::PULL_OFF) {
+        "p"
+    } else if techniques.contains(Techniques::TAP) {
+        "t"
+    } else if techniques.contains(Techniques::VIBRATO) {
+        "~"
+    } else if techniques.intersects(Techniques::HARMONIC | Techniques::PINCH_HARMONIC) {
+        "*"
+    } else if techniques.contains(Techniques::PALM_MUTE) {
+        "pm"
+    } else {
+        ""
+    }
+}
+
 /// Get the string color as an egui color.
 pub fn string_number_to_color(string: u8) -> Color32 {
     match string {
@@ -1,6 +1,6 @@
-use std::{fs::File, io::Read, path::PathBuf, time::Duration};
+use std::{fs::File, path::PathBuf, time::Duration};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::Parser;
 use rockysmithereens_parser::SongFile;
 use rodio::{OutputStream, Source};
@@ -22,22 +22,23 @@ fn main() -> Result<()> {
     let cli = Cli::parse();
 
     // Open the archive
-    let mut file = File::open(cli.path)?;
-    let mut buf = Vec::new();
-    file.read_to_end(&mut buf)?;
+    let file = File::open(cli.path)?;
 
     // Read the archive
-    let song = SongFile::parse(&buf)?;
+    let song_file = SongFile::from_reader(file)?;
 
-    // Find song information
-    let attributes = song.manifests[0].attributes();
+    // Find song information, only the first song of a song pack is played
+    let song = &song_file.songs[0];
+    let attributes = song.manifests[0]
+        .attributes()
+        .context("missing song attributes")?;
     println!(
         "playing song '{}' by '{}' from album '{}' for '{}' seconds",
         attributes.song_name, attributes.artist_name, attributes.album_name, attributes.song_length
     );
 
     // Convert the raw song binary to an audio source
-    let decoder = song.music_decoder()?;
+    let decoder = song_file.music_decoder(song)?;
 
     // Play the song
     let (_stream, stream_handle) = OutputStream::try_default()?;
@@ -1,14 +1,14 @@
 mod ogg;
 
 use std::{
-    fs::File,
-    io::{Read, Write},
-    path::PathBuf,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
 };
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use psarc::PlaystationArchive;
+use psarc::{ArchivePatch, PlaystationArchive};
 
 /// Command line arguments.
 #[derive(Parser, Debug)]
@@ -23,8 +23,10 @@ struct Cli {
 
 #[derive(Debug, Subcommand)]
 enum Commands {
-    /// List all paths in the psarc file.
+    /// List all files in the psarc file with their sizes.
     List,
+    /// Check all files in the psarc file for corruption.
+    Verify,
     /// Export a specific file to the target destination.
     Extract {
         /// Which file to export.
@@ -43,6 +45,30 @@ enum Commands {
         #[clap(value_parser)]
         target: PathBuf,
     },
+    /// Replace the contents of a file in the psarc file.
+    Replace {
+        /// Which file to replace.
+        #[clap(value_parser)]
+        path: String,
+        /// File with the new contents.
+        #[clap(value_parser)]
+        source: PathBuf,
+    },
+    /// Add a new file to the psarc file.
+    Add {
+        /// Path of the file in the archive.
+        #[clap(value_parser)]
+        path: String,
+        /// File to add.
+        #[clap(value_parser)]
+        source: PathBuf,
+    },
+    /// Remove a file from the psarc file.
+    Rm {
+        /// Which file to remove.
+        #[clap(value_parser)]
+        path: String,
+    },
 }
 
 fn main() -> Result<()> {
@@ -52,19 +78,27 @@ fn main() -> Result<()> {
     let cli = Cli::parse();
 
     // Open the archive
-    let mut file = File::open(cli.path)?;
-    let mut buf = Vec::new();
-    file.read_to_end(&mut buf)?;
+    let file = File::open(&cli.path)?;
 
-    let archive = PlaystationArchive::parse(&buf)?;
+    let archive = PlaystationArchive::from_reader(file)?;
 
     match cli.command {
-        Commands::List => archive.paths_iter().for_each(|file| println!("{}", file)),
+        Commands::List => list(&archive),
+        Commands::Verify => {
+            let errors = archive.verify();
+            errors.iter().for_each(|err| println!("{}", err));
+
+            if errors.is_empty() {
+                println!("no problems found");
+            } else {
+                anyhow::bail!("found {} problems", errors.len());
+            }
+        }
         Commands::Extract { path, target } => {
-            let extracted = archive.read_file_with_path(&path)?;
+            let mut entry = archive.open_entry_with_path(&path)?;
 
             let mut target_file = File::create(&target)?;
-            target_file.write_all(&extracted)?;
+            std::io::copy(&mut entry, &mut target_file)?;
 
             println!("written to {:?}", target);
         }
@@ -77,7 +111,69 @@ fn main() -> Result<()> {
 
             println!("written to {:?}", target);
         }
+        Commands::Replace { path, source } => {
+            let mut patch = archive.patch();
+            patch.replace_file(&path, fs::read(source)?)?;
+
+            write_patch(&patch, &cli.path)?;
+        }
+        Commands::Add { path, source } => {
+            let mut patch = archive.patch();
+            patch.add_file(&path, fs::read(source)?)?;
+
+            write_patch(&patch, &cli.path)?;
+        }
+        Commands::Rm { path } => {
+            let mut patch = archive.patch();
+            patch.remove_file(&path)?;
+
+            write_patch(&patch, &cli.path)?;
+        }
     }
 
     Ok(())
 }
+
+/// Write the patched archive next to the original and replace it when it succeeded.
+fn write_patch(patch: &ArchivePatch, path: &Path) -> Result<()> {
+    let temporary_path = path.with_extension("psarc.tmp");
+
+    let mut writer = BufWriter::new(File::create(&temporary_path)?);
+    patch.write(&mut writer)?;
+    writer.flush()?;
+
+    fs::rename(&temporary_path, path)?;
+
+    println!("written to {:?}", path);
+
+    Ok(())
+}
+
+/// Print the archive header and a table of all files.
+fn list(archive: &PlaystationArchive) {
+    println!(
+        "version {}, {:?} compression, {} byte blocks, flags {:?}",
+        archive.version(),
+        archive.compression_type(),
+        archive.block_size().to_u32(),
+        archive.archive_flags()
+    );
+    println!();
+
+    println!(
+        "{:>10} {:>10} {:>6} {:>6} {:>10} {:32} path",
+        "size", "stored", "ratio", "blocks", "offset", "digest"
+    );
+    for entry in archive.metadata_iter() {
+        println!(
+            "{:>10} {:>10} {:>5.1}% {:>6} {:>10} {:032X} {}",
+            entry.length(),
+            entry.stored_length(),
+            entry.compression_ratio() * 100.0,
+            entry.block_count(),
+            entry.offset(),
+            entry.name_digest(),
+            entry.path()
+        );
+    }
+}